@@ -0,0 +1,396 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::FileMap;
+
+// Bump whenever the rendering changes in a way that makes old entries wrong
+const CACHE_FORMAT: u32 = 2;
+
+/// Version stamp stored in every entry; entries from another version are ignored.
+pub const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// The subdirectories entries are kept in. The cache only ever removes files
+// in them that are named like its entries, so a cache directory shared with
+// other files is safe to prune and clear
+const FILES_DIR: &str = "files";
+const CRATES_DIR: &str = "crates";
+
+// Temporary files younger than this may still be written to by another process
+const TEMP_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// An on-disk cache of per-file codemaps.
+///
+/// There is one entry per source file and set of options, stored in `files/`
+/// under a hash of its absolute path, the options and the codemap version. An entry is only
+/// used when the file content matches too, so a changed file simply overwrites
+/// its old entry, and mapping with other options doesn't evict it.
+///
+/// Crates from a registry never change once published, so their codemaps are
+/// stored whole instead, one entry per crate version and set of options,
+/// under `crates/`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    options: String,
+}
+
+/// Summary of a cache entry, for inspecting the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    /// The source file the entry was created for, or `<name>-<version>` for
+    /// a crate
+    pub source: PathBuf,
+    pub version: String,
+    /// Whether the entry was written by this version and, for a file, still
+    /// matches it on disk. This doesn't depend on the options the entry was
+    /// mapped with, see [`Cache::prune`]
+    pub fresh: bool,
+    /// Size of the entry on disk in bytes
+    pub size: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    format: u32,
+    version: String,
+    options: String,
+    source: PathBuf,
+    content_hash: String,
+    file_map: FileMap,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredCrate {
+    format: u32,
+    version: String,
+    options: String,
+    crate_options: String,
+    files: Vec<(PathBuf, FileMap)>,
+}
+
+impl Cache {
+    /// Open (creating it if needed) a cache directory.
+    ///
+    /// `options` identifies the mapping options; entries written with other
+    /// options are treated as misses.
+    pub fn open(dir: impl Into<PathBuf>, options: impl Into<String>) -> io::Result<Cache> {
+        let dir = dir.into();
+        fs::create_dir_all(dir.join(FILES_DIR))?;
+        fs::create_dir_all(dir.join(CRATES_DIR))?;
+        Ok(Cache {
+            dir,
+            options: options.into(),
+        })
+    }
+
+    /// The default cache directory: `$CODEMAP_CACHE_DIR`, or `codemap` in the
+    /// user's cache directory.
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("CODEMAP_CACHE_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(base.join("codemap"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up the codemap of `path`, given its current contents.
+    pub fn get(&self, path: &Path, contents: &str) -> Option<FileMap> {
+        let entry = self.read_entry(&self.entry_path(path)).ok()?;
+        let valid = entry.format == CACHE_FORMAT
+            && entry.version == CACHE_VERSION
+            && entry.options == self.options
+            && entry.content_hash == content_hash(contents);
+        valid.then_some(entry.file_map)
+    }
+
+    /// Store the codemap of `path` for its current contents.
+    pub fn put(&self, path: &Path, contents: &str, file_map: &FileMap) -> io::Result<()> {
+        let entry = StoredEntry {
+            format: CACHE_FORMAT,
+            version: CACHE_VERSION.to_string(),
+            options: self.options.clone(),
+            source: absolute(path),
+            content_hash: content_hash(contents),
+            file_map: file_map.clone(),
+        };
+        let json = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+        write_entry(&self.entry_path(path), &json)
+    }
+
+    /// Look up the codemaps of the files of a published crate version.
+    ///
+    /// `crate_options` identifies the options applied to the crate as a
+    /// whole rather than file by file, e.g. how re-exports are expanded.
+    pub fn get_crate(
+        &self,
+        name: &str,
+        version: &str,
+        crate_options: &str,
+    ) -> Option<Vec<(PathBuf, FileMap)>> {
+        let json = fs::read(self.crate_path(name, version, crate_options)).ok()?;
+        let entry: StoredCrate = serde_json::from_slice(&json).ok()?;
+        let valid = entry.format == CACHE_FORMAT
+            && entry.version == CACHE_VERSION
+            && entry.options == self.options
+            && entry.crate_options == crate_options;
+        valid.then_some(entry.files)
+    }
+
+    /// Store the codemaps of the files of a published crate version, see
+    /// [`Cache::get_crate`].
+    pub fn put_crate(
+        &self,
+        name: &str,
+        version: &str,
+        crate_options: &str,
+        files: &[(PathBuf, FileMap)],
+    ) -> io::Result<()> {
+        let entry = StoredCrate {
+            format: CACHE_FORMAT,
+            version: CACHE_VERSION.to_string(),
+            options: self.options.clone(),
+            crate_options: crate_options.to_string(),
+            files: files.to_vec(),
+        };
+        let json = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+        write_entry(&self.crate_path(name, version, crate_options), &json)
+    }
+
+    /// List every entry in the cache, of files and of crates.
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for path in own_files(&self.dir.join(FILES_DIR))? {
+            if is_temp_file(&path) {
+                continue;
+            }
+
+            let size = fs::metadata(&path)?.len();
+            let Ok(entry) = self.read_entry(&path) else {
+                // Unreadable entries are reported as stale so prune removes them
+                entries.push(CacheEntry {
+                    source: path,
+                    version: String::new(),
+                    fresh: false,
+                    size,
+                });
+                continue;
+            };
+
+            let fresh = is_fresh(&entry);
+            entries.push(CacheEntry {
+                source: entry.source,
+                version: entry.version,
+                fresh,
+                size,
+            });
+        }
+
+        for path in own_files(&self.dir.join(CRATES_DIR))? {
+            if is_temp_file(&path) {
+                continue;
+            }
+
+            let size = fs::metadata(&path)?.len();
+            let entry = read_crate(&path);
+            entries.push(CacheEntry {
+                source: crate_source(&path),
+                fresh: entry.as_ref().is_some_and(is_current),
+                version: entry.map(|entry| entry.version).unwrap_or_default(),
+                size,
+            });
+        }
+        entries.sort_by(|a, b| a.source.cmp(&b.source));
+        Ok(entries)
+    }
+
+    /// Remove entries whose source file is gone or changed, or that were
+    /// written by another version, and temporary files of writes that were
+    /// interrupted over an hour ago. Returns how many were removed.
+    ///
+    /// Entries of every set of options are kept as long as they are fresh,
+    /// whatever options the cache was opened with.
+    pub fn prune(&self) -> io::Result<usize> {
+        let mut removed = 0;
+        for path in own_files(&self.dir.join(FILES_DIR))? {
+            if is_temp_file(&path) {
+                if is_abandoned(&path) {
+                    fs::remove_file(&path)?;
+                    removed += 1;
+                }
+                continue;
+            }
+
+            if !self.read_entry(&path).is_ok_and(|entry| is_fresh(&entry)) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        for path in own_files(&self.dir.join(CRATES_DIR))? {
+            if is_temp_file(&path) {
+                if is_abandoned(&path) {
+                    fs::remove_file(&path)?;
+                    removed += 1;
+                }
+                continue;
+            }
+            if !read_crate(&path).is_some_and(|entry| is_current(&entry)) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove every entry. Other files in the cache directory are left alone.
+    pub fn clear(&self) -> io::Result<()> {
+        for dir in [FILES_DIR, CRATES_DIR] {
+            for path in own_files(&self.dir.join(dir))? {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let hash = hash(&[
+            &absolute(path).to_string_lossy(),
+            &self.options,
+            CACHE_VERSION,
+            &CACHE_FORMAT.to_string(),
+        ]);
+        self.dir.join(FILES_DIR).join(format!("{}.json", hash))
+    }
+
+    // Named after the crate version, and the options so that mapping with
+    // other options doesn't overwrite the entry
+    fn crate_path(&self, name: &str, version: &str, crate_options: &str) -> PathBuf {
+        let hash = hash(&[
+            &self.options,
+            crate_options,
+            CACHE_VERSION,
+            &CACHE_FORMAT.to_string(),
+        ]);
+        self.dir
+            .join(CRATES_DIR)
+            .join(format!("{}-{}-{}.json", name, version, &hash[..16]))
+    }
+
+    fn read_entry(&self, entry_path: &Path) -> io::Result<StoredEntry> {
+        let json = fs::read(entry_path)?;
+        serde_json::from_slice(&json).map_err(io::Error::other)
+    }
+}
+
+// The hex blake3 hash of `parts`
+fn hash(parts: &[&str]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for part in parts {
+        // Length-prefixed so the parts can't run into each other
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+// Whether an entry was written by this version for the current contents of
+// its source file
+fn is_fresh(entry: &StoredEntry) -> bool {
+    entry.format == CACHE_FORMAT
+        && entry.version == CACHE_VERSION
+        && fs::read_to_string(&entry.source)
+            .is_ok_and(|contents| content_hash(&contents) == entry.content_hash)
+}
+
+// Crates never change once published, so their entries only go stale with
+// the codemap version
+fn is_current(entry: &StoredCrate) -> bool {
+    entry.format == CACHE_FORMAT && entry.version == CACHE_VERSION
+}
+
+fn read_crate(entry_path: &Path) -> Option<StoredCrate> {
+    let json = fs::read(entry_path).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+// `<name>-<version>` for a crate entry named `<name>-<version>-<hash>.json`
+fn crate_source(entry_path: &Path) -> PathBuf {
+    let stem = entry_path.file_stem().unwrap_or_default().to_string_lossy();
+    let source = stem.rsplit_once('-').map_or(&*stem, |(source, _)| source);
+    PathBuf::from(source)
+}
+
+// The files in `dir` named like cache entries, `<hash>.json` for files and
+// `<name>-<version>-<hash>.json` for crates, or like their temporary files
+fn own_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let files = match fs::read_dir(dir) {
+        Ok(files) => files,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut paths = Vec::new();
+    for file in files {
+        let file = file?;
+        let Some(name) = file.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let stem = match name.strip_suffix(".tmp") {
+            Some(temp) => temp.split_once(".json.").map(|(stem, _)| stem),
+            None => name.strip_suffix(".json"),
+        };
+        let hash = stem.and_then(|stem| stem.rsplit('-').next());
+        let is_hash = hash
+            .is_some_and(|hash| hash.len() >= 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()));
+        if is_hash && file.file_type()?.is_file() {
+            paths.push(file.path());
+        }
+    }
+    Ok(paths)
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "tmp")
+}
+
+// Whether a temporary file is old enough that its write was interrupted
+fn is_abandoned(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age > TEMP_FILE_AGE)
+        })
+}
+
+// Write to a temporary file first so a concurrent reader never sees a
+// half-written entry
+fn write_entry(entry_path: &Path, json: &[u8]) -> io::Result<()> {
+    let mut temp_path = entry_path.as_os_str().to_owned();
+    temp_path.push(format!(
+        ".{}.{:?}.tmp",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    fs::write(&temp_path, json)?;
+    fs::rename(&temp_path, entry_path)
+}
+
+fn content_hash(contents: &str) -> String {
+    blake3::hash(contents.as_bytes()).to_hex().to_string()
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,327 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{CodemapOptions, Error, Format, PathOverride, Visibility};
+
+/// Name of the configuration file looked up next to the mapped path and in
+/// every directory above it.
+pub const CONFIG_FILE: &str = "codemap.toml";
+
+/// Settings for a repository, from `codemap.toml` or the
+/// `[package.metadata.codemap]` (or `[workspace.metadata.codemap]`) table of
+/// `Cargo.toml`.
+///
+/// Patterns are relative to the directory of the file they are in, and
+/// [`Config::discover`] rebases them onto the mapped directory, where
+/// `--include` and `--exclude` apply. Unknown keys are an error. Flags given
+/// on the command line or as MCP tool arguments take precedence.
+///
+/// ```toml
+/// exclude = ["tests/**"]
+/// visibility = "crate"
+/// format = "markdown"
+/// token_budget = 20000
+///
+/// [[overrides]]
+/// path = "src/generated/**"
+/// visibility = "public"
+/// docs = false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(try_from = "RawConfig")]
+pub struct Config {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub format: Option<Format>,
+    /// Leave out items once the output would be about this many tokens
+    pub token_budget: Option<usize>,
+    #[serde(flatten)]
+    pub settings: MapSettings,
+    /// Settings for the files matching a pattern, applied on top of the
+    /// settings above; later overrides win
+    pub overrides: Vec<PathConfig>,
+}
+
+/// Settings for the files matching `path`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(try_from = "RawPathConfig")]
+pub struct PathConfig {
+    pub path: String,
+    pub settings: MapSettings,
+}
+
+// `deny_unknown_fields` doesn't work together with `flatten`, so the keys no
+// field took are collected and rejected instead
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawConfig {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    format: Option<Format>,
+    token_budget: Option<usize>,
+    overrides: Vec<PathConfig>,
+    #[serde(flatten)]
+    settings: MapSettings,
+    #[serde(flatten)]
+    unknown: toml::Table,
+}
+
+impl TryFrom<RawConfig> for Config {
+    type Error = String;
+
+    fn try_from(raw: RawConfig) -> Result<Self, Self::Error> {
+        check_unknown(&raw.unknown)?;
+        Ok(Config {
+            include: raw.include,
+            exclude: raw.exclude,
+            format: raw.format,
+            token_budget: raw.token_budget,
+            settings: raw.settings,
+            overrides: raw.overrides,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawPathConfig {
+    path: String,
+    #[serde(flatten)]
+    settings: MapSettings,
+    #[serde(flatten)]
+    unknown: toml::Table,
+}
+
+impl TryFrom<RawPathConfig> for PathConfig {
+    type Error = String;
+
+    fn try_from(raw: RawPathConfig) -> Result<Self, Self::Error> {
+        check_unknown(&raw.unknown)?;
+        Ok(PathConfig {
+            path: raw.path,
+            settings: raw.settings,
+        })
+    }
+}
+
+fn check_unknown(unknown: &toml::Table) -> Result<(), String> {
+    match unknown.keys().next() {
+        Some(key) => Err(format!("unknown field `{}`", key)),
+        None => Ok(()),
+    }
+}
+
+/// The [`CodemapOptions`] a configuration sets; unset ones keep their value.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MapSettings {
+    pub visibility: Option<Visibility>,
+    pub uses: Option<bool>,
+    pub impls: Option<bool>,
+    pub trait_impls: Option<bool>,
+    pub docs: Option<bool>,
+    pub attributes: Option<bool>,
+    pub line_numbers: Option<bool>,
+    pub max_signature_length: Option<usize>,
+}
+
+impl MapSettings {
+    /// `options` with every setting that is set applied.
+    pub fn apply(&self, mut options: CodemapOptions) -> CodemapOptions {
+        if let Some(visibility) = self.visibility {
+            options = options.visibility(visibility);
+        }
+        if let Some(uses) = self.uses {
+            options = options.include_uses(uses);
+        }
+        if let Some(impls) = self.impls {
+            options = options.include_impls(impls);
+        }
+        if let Some(trait_impls) = self.trait_impls {
+            options = options.include_trait_impls(trait_impls);
+        }
+        if let Some(docs) = self.docs {
+            options = options.include_docs(docs);
+        }
+        if let Some(attributes) = self.attributes {
+            options = options.include_attributes(attributes);
+        }
+        if let Some(line_numbers) = self.line_numbers {
+            options = options.include_line_numbers(line_numbers);
+        }
+        if let Some(length) = self.max_signature_length {
+            options = options.max_signature_length(Some(length));
+        }
+        options
+    }
+}
+
+impl Config {
+    /// Find the configuration for `path`: the nearest `codemap.toml`, or
+    /// `Cargo.toml` with a `codemap` metadata table, in `path` or one of the
+    /// directories above it. Returns the file it was read from, with its
+    /// patterns rebased onto `path`.
+    pub fn discover(path: &Path) -> Result<Option<(PathBuf, Config)>, Error> {
+        let path = std::path::absolute(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut dirs = path.ancestors().filter(|dir| dir.is_dir()).peekable();
+        let Some(mapped) = dirs.peek().map(|dir| dir.to_path_buf()) else {
+            return Ok(None);
+        };
+        for dir in dirs {
+            let config_file = dir.join(CONFIG_FILE);
+            let config = if config_file.is_file() {
+                Some((config_file.clone(), Config::load(&config_file)?))
+            } else {
+                let manifest = dir.join("Cargo.toml");
+                match manifest.is_file() {
+                    true => Config::from_manifest(&manifest)?.map(|config| (manifest, config)),
+                    false => None,
+                }
+            };
+            if let Some((file, config)) = config {
+                let prefix = mapped.strip_prefix(dir).unwrap_or(Path::new(""));
+                return Ok(Some((file, config.rebase(prefix))));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Read a `codemap.toml` file.
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let contents = read(path)?;
+        toml::from_str(&contents).map_err(|err| config_error(path, &contents, err))
+    }
+
+    // The `codemap` metadata table of a Cargo manifest, if it has one
+    fn from_manifest(path: &Path) -> Result<Option<Config>, Error> {
+        let contents = read(path)?;
+        // Deserialized in place so errors point into the manifest
+        let manifest: Manifest =
+            toml::from_str(&contents).map_err(|err| config_error(path, &contents, err))?;
+        Ok([manifest.package, manifest.workspace]
+            .into_iter()
+            .flatten()
+            .find_map(|section| section.metadata?.codemap))
+    }
+
+    // The configuration with its patterns relative to the directory `prefix`
+    // below the one they were written for
+    fn rebase(mut self, prefix: &Path) -> Config {
+        for glob in self.include.iter_mut().chain(&mut self.exclude).chain(
+            self.overrides
+                .iter_mut()
+                .map(|path_config| &mut path_config.path),
+        ) {
+            *glob = rebase_glob(glob, prefix);
+        }
+        self
+    }
+
+    /// The options for mapping files, and for the files matching each
+    /// override, with `flags` (e.g. from the command line) applied last.
+    pub fn map_options(&self, flags: &MapSettings) -> (CodemapOptions, Vec<PathOverride>) {
+        let base = self.settings.apply(CodemapOptions::default());
+        let overrides = self
+            .overrides
+            .iter()
+            .map(|path_config| PathOverride {
+                glob: path_config.path.clone(),
+                codemap: flags.apply(path_config.settings.apply(base.clone())),
+            })
+            .collect();
+        (flags.apply(base), overrides)
+    }
+}
+
+// A gitignore-style glob relative to a directory, relative to the directory
+// `prefix` below it instead. Globs without a `/` match at any depth and stay
+// as they are. Leading segments matching `prefix` are dropped and the rest of
+// `prefix` becomes `..`, so globs for paths outside of it match nothing
+fn rebase_glob(glob: &str, prefix: &Path) -> String {
+    let anchored = glob.trim_end_matches('/').contains('/');
+    if !anchored || prefix.as_os_str().is_empty() {
+        return glob.to_string();
+    }
+
+    let mut segments: Vec<&str> = glob.trim_start_matches('/').split('/').collect();
+    let components: Vec<String> = prefix
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let mut matched = 0;
+    for component in &components {
+        let Some(segment) = segments.first() else {
+            break;
+        };
+        // `**` matches the rest of the prefix too
+        if *segment == "**" {
+            return segments.join("/");
+        }
+        let matches = globset::Glob::new(segment)
+            .is_ok_and(|segment| segment.compile_matcher().is_match(component));
+        if !matches {
+            break;
+        }
+        // The glob matches `prefix` or a directory above it, so everything
+        if segments.len() == 1 {
+            return "**".to_string();
+        }
+        segments.remove(0);
+        matched += 1;
+    }
+
+    let up = vec![".."; components.len() - matched];
+    [up, segments].concat().join("/")
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// The parts of a Cargo manifest that hold its configuration
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<ManifestSection>,
+    workspace: Option<ManifestSection>,
+}
+
+#[derive(Deserialize)]
+struct ManifestSection {
+    metadata: Option<Metadata>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    codemap: Option<Config>,
+}
+
+// The error with the line and column it is at in `contents`, when known
+fn config_error(path: &Path, contents: &str, err: toml::de::Error) -> Error {
+    let message = err.message().trim_end();
+    let message = match err.span() {
+        Some(span) => {
+            let before = &contents[..span.start];
+            let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+            format!(
+                "{} at line {}, column {}",
+                message,
+                before.matches('\n').count() + 1,
+                before[line_start..].chars().count() + 1
+            )
+        }
+        None => message.to_string(),
+    };
+    Error::Config {
+        path: path.to_path_buf(),
+        message,
+    }
+}
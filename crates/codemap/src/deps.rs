@@ -0,0 +1,352 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::directory::find_packages;
+use crate::languages::path_dependencies;
+use crate::{CargoManifest, Error, LanguageRegistry, Options, ProjectMap, Rust, map_directory};
+
+/// A package pinned in a `Cargo.lock`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Where the package comes from, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index`; `None` for
+    /// workspace members and path dependencies
+    pub source: Option<String>,
+    /// The packages it depends on, as `name`, or `name version` when several
+    /// versions of it are locked
+    pub dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Whether the package comes from a registry, where published versions
+    /// never change.
+    pub fn is_published(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+    }
+
+    // Whether an entry of `dependencies` refers to this package
+    fn is(&self, dependency: &str) -> bool {
+        let mut parts = dependency.split_whitespace();
+        parts.next() == Some(self.name.as_str())
+            && parts.next().is_none_or(|version| version == self.version)
+    }
+}
+
+/// The `Cargo.lock` of the project `path` is in, looked up in `path` and the
+/// directories above it.
+pub fn find_lockfile(path: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    path.ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lockfile| lockfile.is_file())
+}
+
+/// Every package a `Cargo.lock` pins.
+pub fn read_lockfile(path: &Path) -> Result<Vec<LockedPackage>, Error> {
+    let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let lockfile: toml::Table = toml::from_str(&contents).map_err(|err| Error::Lockfile {
+        path: path.to_path_buf(),
+        message: err.message().to_string(),
+    })?;
+
+    let packages = lockfile
+        .get("package")
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_table)
+        .filter_map(|package| {
+            let string = |key| package.get(key)?.as_str().map(str::to_string);
+            // Dependencies from another source also name it in parentheses
+            let dependencies = package
+                .get("dependencies")
+                .and_then(toml::Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(toml::Value::as_str)
+                .map(|dependency| {
+                    let parts: Vec<&str> = dependency.split_whitespace().take(2).collect();
+                    parts.join(" ")
+                })
+                .collect();
+            Some(LockedPackage {
+                name: string("name")?,
+                version: string("version")?,
+                source: string("source"),
+                dependencies,
+            })
+        })
+        .collect();
+    Ok(packages)
+}
+
+/// The packages the local packages of a lockfile depend on, sorted by name
+/// and version. With `member`, only the dependencies of that package.
+///
+/// Local packages are the ones without a source. The path dependencies among
+/// them are listed with the other dependencies rather than as members.
+pub fn direct_dependencies<'a>(
+    packages: &'a [LockedPackage],
+    member: Option<&str>,
+) -> Result<Vec<&'a LockedPackage>, Error> {
+    let local: Vec<&LockedPackage> = packages
+        .iter()
+        .filter(|package| package.source.is_none())
+        .collect();
+    let members: Vec<&LockedPackage> = match member {
+        Some(member) => local
+            .iter()
+            .copied()
+            .filter(|package| package.name == member)
+            .collect(),
+        None => local
+            .iter()
+            .copied()
+            .filter(|package| {
+                !local
+                    .iter()
+                    .flat_map(|other| &other.dependencies)
+                    .any(|dependency| package.is(dependency))
+            })
+            .collect(),
+    };
+    if let Some(member) = member
+        && members.is_empty()
+    {
+        return Err(Error::UnknownPackage {
+            name: member.to_string(),
+        });
+    }
+
+    let mut dependencies: Vec<&LockedPackage> = packages
+        .iter()
+        .filter(|package| !members.contains(package))
+        .filter(|package| {
+            members
+                .iter()
+                .flat_map(|member| &member.dependencies)
+                .any(|dependency| package.is(dependency))
+        })
+        .collect();
+    dependencies.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    Ok(dependencies)
+}
+
+/// The locked packages `spec` refers to: every version of a `name`, or the
+/// one of a `name@version`.
+pub fn find_locked<'a>(
+    packages: &'a [LockedPackage],
+    spec: &str,
+) -> Result<Vec<&'a LockedPackage>, Error> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+    let found: Vec<&LockedPackage> = packages
+        .iter()
+        .filter(|package| package.name == name)
+        .filter(|package| version.is_none_or(|version| package.version == version))
+        .collect();
+    if found.is_empty() {
+        return Err(Error::UnknownPackage {
+            name: spec.to_string(),
+        });
+    }
+    Ok(found)
+}
+
+/// Cargo's home directory: `$CARGO_HOME`, or `.cargo` in the home directory.
+pub fn cargo_home() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(dir));
+    }
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(".cargo"))
+}
+
+/// Where the sources of a locked package already are, if anywhere.
+///
+/// Nothing is downloaded. Sources are looked for in `vendor/` under `root`,
+/// the directory of the lockfile, then in the registry sources and git
+/// checkouts under `cargo_home`. Packages without a source are looked for
+/// among the packages under `root` and their path dependencies.
+pub fn locate_package(
+    package: &LockedPackage,
+    root: &Path,
+    cargo_home: Option<&Path>,
+) -> Option<PathBuf> {
+    // `cargo vendor` only adds the version to the directory name when several
+    // versions are vendored
+    let vendored = [
+        root.join("vendor")
+            .join(format!("{}-{}", package.name, package.version)),
+        root.join("vendor").join(&package.name),
+    ];
+    if let Some(dir) = vendored.into_iter().find(|dir| is_package(dir, package)) {
+        return Some(dir);
+    }
+
+    match package.source.as_deref() {
+        Some(_) if package.is_published() => {
+            let registries = subdirectories(&cargo_home?.join("registry/src"));
+            registries
+                .into_iter()
+                .map(|registry| registry.join(format!("{}-{}", package.name, package.version)))
+                .find(|dir| dir.join("Cargo.toml").is_file())
+        }
+        Some(source) if source.starts_with("git+") => {
+            // Checkouts are named after the first characters of the revision
+            let (_, revision) = source.rsplit_once('#')?;
+            let repositories = subdirectories(&cargo_home?.join("git/checkouts"));
+            repositories
+                .iter()
+                .flat_map(|repository| subdirectories(repository))
+                .filter(|checkout| {
+                    checkout
+                        .file_name()
+                        .is_some_and(|name| revision.starts_with(&*name.to_string_lossy()))
+                })
+                .find_map(|checkout| find_package(&checkout, &package.name))
+        }
+        Some(_) => None,
+        None => {
+            if let Some(dir) = find_package(root, &package.name) {
+                return Some(dir);
+            }
+            find_packages(root, &Options::default())
+                .into_iter()
+                .flat_map(|(dir, _)| {
+                    let dir = root.join(dir);
+                    let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap_or_default();
+                    path_dependencies(&manifest)
+                        .into_iter()
+                        .map(move |path| dir.join(path))
+                })
+                .find(|dir| is_package(dir, package))
+        }
+    }
+}
+
+/// Map the public API of a dependency whose sources are in `dir`, with paths
+/// prefixed with `name-version/`.
+///
+/// Only the manifest and the Rust files are mapped, leaving out tests,
+/// benches and examples. The globs and overrides of `options` are about the
+/// project rather than its dependencies and aren't used. With a cache, the
+/// codemap of a published crate is stored and reused as a whole.
+pub fn map_dependency(
+    package: &LockedPackage,
+    dir: &Path,
+    options: &Options,
+) -> Result<ProjectMap, Error> {
+    let cache = options.cache.as_ref().filter(|_| package.is_published());
+    let crate_options = crate_options(options);
+    if let Some(files) =
+        cache.and_then(|cache| cache.get_crate(&package.name, &package.version, &crate_options))
+    {
+        return Ok(ProjectMap {
+            files,
+            errors: Vec::new(),
+        });
+    }
+
+    let mut languages = LanguageRegistry::new();
+    languages.register(Arc::new(Rust));
+    languages.register(Arc::new(CargoManifest));
+    let options = Options {
+        include: Vec::new(),
+        exclude: ["tests/**", "benches/**", "examples/**"]
+            .map(str::to_string)
+            .to_vec(),
+        overrides: Vec::new(),
+        // Vendored sources are often ignored by git
+        no_ignore: true,
+        languages,
+        package: None,
+        // A published crate is stored whole rather than file by file
+        cache: options.cache.clone().filter(|_| cache.is_none()),
+        ..options.clone()
+    };
+    let mut project = map_directory(dir, &options)?;
+    let prefix = format!("{}-{}", package.name, package.version);
+    for (path, file_map) in &mut project.files {
+        *path = Path::new(&prefix).join(&*path);
+        for file in file_map
+            .items
+            .iter_mut()
+            .filter_map(|item| item.file.as_mut())
+        {
+            *file = Path::new(&prefix).join(&*file);
+        }
+    }
+
+    // Failing to store the entry isn't worth reporting
+    if let Some(cache) = cache
+        && project.errors.is_empty()
+    {
+        let _ = cache.put_crate(
+            &package.name,
+            &package.version,
+            &crate_options,
+            &project.files,
+        );
+    }
+    Ok(project)
+}
+
+// What the codemap of a whole crate depends on besides the options of its
+// files, which the cache was opened with: which files are walked and how
+// re-exports are expanded once they are mapped
+fn crate_options(options: &Options) -> String {
+    format!(
+        "hidden: {}, max_depth: {:?}, follow_links: {}, max_file_size: {:?}, show_origin: {}",
+        options.hidden,
+        options.max_depth,
+        options.follow_links,
+        options.max_file_size,
+        options.show_origin
+    )
+}
+
+// Whether `dir` holds the sources of `package`
+fn is_package(dir: &Path, package: &LockedPackage) -> bool {
+    let Ok(manifest) = fs::read_to_string(dir.join("Cargo.toml")) else {
+        return false;
+    };
+    let Ok(manifest) = toml::from_str::<toml::Table>(&manifest) else {
+        return false;
+    };
+    let field = |key| manifest.get("package")?.get(key)?.as_str();
+    field("name") == Some(package.name.as_str())
+        // Path dependencies may inherit their version from a workspace
+        && field("version").is_none_or(|version| version == package.version)
+}
+
+// The directory of the package named `name` under `root`
+fn find_package(root: &Path, name: &str) -> Option<PathBuf> {
+    find_packages(root, &Options::default())
+        .into_iter()
+        .find(|(_, package)| package == name)
+        .map(|(dir, _)| root.join(dir))
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FileMap, Item};
+
+/// How the public items of one file changed between two codemaps.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub added: Vec<Item>,
+    pub removed: Vec<Item>,
+    /// Items with the same kind and name whose interface changed, as `(old, new)`
+    pub changed: Vec<(Item, Item)>,
+}
+
+impl FileDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two sets of file codemaps, matching files by path and items by
+/// kind and name. Files without any change are left out.
+pub fn diff_maps(old: &[(PathBuf, FileMap)], new: &[(PathBuf, FileMap)]) -> Vec<FileDiff> {
+    let mut diffs = Vec::new();
+
+    for (path, old_map) in old {
+        let new_items = new
+            .iter()
+            .find(|(new_path, _)| new_path == path)
+            .map(|(_, new_map)| new_map.items.as_slice())
+            .unwrap_or_default();
+        diffs.push(diff_items(path, &old_map.items, new_items));
+    }
+
+    // Files that only exist in the new tree
+    for (path, new_map) in new {
+        if !old.iter().any(|(old_path, _)| old_path == path) {
+            diffs.push(diff_items(path, &[], &new_map.items));
+        }
+    }
+
+    diffs.retain(|diff| !diff.is_empty());
+    diffs
+}
+
+fn diff_items(path: &Path, old: &[Item], new: &[Item]) -> FileDiff {
+    let mut diff = FileDiff {
+        path: path.to_path_buf(),
+        ..FileDiff::default()
+    };
+
+    // Pair each old item with the first unmatched new item of the same
+    // identity, so duplicate names (e.g. `#[cfg]` variants) pair up in order
+    let mut matched = vec![false; new.len()];
+    for old_item in old {
+        let counterpart =
+            (0..new.len()).find(|&index| !matched[index] && same_item(old_item, &new[index]));
+
+        match counterpart {
+            Some(index) => {
+                matched[index] = true;
+                if new[index].text != old_item.text {
+                    diff.changed.push((old_item.clone(), new[index].clone()));
+                }
+            }
+            None => diff.removed.push(old_item.clone()),
+        }
+    }
+
+    for (index, new_item) in new.iter().enumerate() {
+        if !matched[index] {
+            diff.added.push(new_item.clone());
+        }
+    }
+
+    diff
+}
+
+// Named items are identified by kind and name, unnamed ones (`use`
+// declarations) only by their text
+fn same_item(old: &Item, new: &Item) -> bool {
+    match (&old.name, &new.name) {
+        (Some(old_name), Some(new_name)) => old.kind == new.kind && old_name == new_name,
+        (None, None) => old.text == new.text,
+        _ => false,
+    }
+}
@@ -0,0 +1,637 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState, types::TypesBuilder};
+
+use crate::incremental::file_stamp;
+use crate::languages::{discover_crates, group_packages, mapped_packages, package_of_manifest};
+use crate::{
+    Cache, CodemapOptions, Error, FileMap, Format, LanguageRegistry, Mapper, ReexportOptions,
+    Workspace, expand_reexports, render_files,
+};
+
+/// Which files of a directory to map and how.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Only map files matching these globs
+    pub include: Vec<String>,
+    /// Skip files matching these globs
+    pub exclude: Vec<String>,
+    /// Map hidden files and directories
+    pub hidden: bool,
+    /// Don't respect `.gitignore`, `.ignore` and other ignore files
+    pub no_ignore: bool,
+    /// Descend at most this many directories
+    pub max_depth: Option<usize>,
+    pub follow_links: bool,
+    /// The languages to map, picked by file extension; files of other
+    /// languages are skipped
+    pub languages: LanguageRegistry,
+    /// What every file map includes and how it is rendered
+    pub codemap: CodemapOptions,
+    /// Other options for the files matching a pattern; later overrides win
+    pub overrides: Vec<PathOverride>,
+    /// Annotate re-exported items with where they are defined
+    pub show_origin: bool,
+    /// Number of threads used to walk and parse files; `None` for one per CPU
+    pub jobs: Option<usize>,
+    /// Report files larger than this many bytes instead of mapping them
+    pub max_file_size: Option<u64>,
+    /// Reuse codemaps of files that didn't change since they were cached. The
+    /// cache should be opened with the fingerprint of `codemap`
+    pub cache: Option<Cache>,
+    /// Keep parse trees between calls so changed files are re-parsed
+    /// incrementally; takes precedence over `cache`
+    pub workspace: Option<Arc<Workspace>>,
+    /// Only map the files of the Cargo package with this name, found among
+    /// the manifests under the mapped directory
+    pub package: Option<String>,
+}
+
+/// Options for the files matching a glob, relative to the mapped directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathOverride {
+    pub glob: String,
+    pub codemap: CodemapOptions,
+}
+
+/// The codemaps of every file under a directory.
+#[derive(Debug, Default)]
+pub struct ProjectMap {
+    /// Files with their path relative to the mapped directory, sorted by path.
+    /// In a workspace, files of a crate are relative to it and prefixed with
+    /// its name instead
+    pub files: Vec<(PathBuf, FileMap)>,
+    /// Files and directories that couldn't be mapped, sorted by path; the
+    /// other files are still mapped
+    pub errors: Vec<Error>,
+}
+
+impl ProjectMap {
+    pub fn render(&self, format: Format) -> String {
+        render_files(&self.files, format)
+    }
+}
+
+/// Map every source file under `path` in one of the languages of
+/// [`Options::languages`], expanding Rust re-exports once all files are known.
+///
+/// The Go files of a directory are merged into one `dir/*.go` entry for their
+/// package, with methods listed under their type whichever file declares them.
+///
+/// Cargo manifests list the targets Cargo discovers among the mapped files
+/// and the workspace members their globs match. When mapped manifests declare
+/// packages below `path`, or [`Options::package`] picks one, files are
+/// grouped by crate: `crates/app/src/main.rs` becomes `app/src/main.rs`.
+///
+/// Files that can't be read or parsed are reported in [`ProjectMap::errors`];
+/// only invalid options or a broken grammar make the whole call fail.
+pub fn map_directory(path: &Path, options: &Options) -> Result<ProjectMap, Error> {
+    let files = Mutex::new(Vec::new());
+    let errors = Mutex::new(Vec::new());
+    // Only picking a package needs them before the walk
+    let packages = match options.package {
+        Some(_) => find_packages(path, options),
+        None => Vec::new(),
+    };
+
+    walk(path, options, &packages, false, |result| match result {
+        Ok(file) => files.lock().unwrap().push(file),
+        Err(err) => errors.lock().unwrap().push(err),
+    })?;
+
+    // Sort so the output doesn't depend on thread scheduling
+    let mut files = files.into_inner().unwrap();
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut files = group_packages(files);
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    discover_crates(path, &mut files);
+    let mut errors = errors.into_inner().unwrap();
+    errors.sort_by(|a, b| a.path().cmp(&b.path()));
+
+    expand_reexports(
+        &mut files,
+        &ReexportOptions {
+            show_origin: options.show_origin,
+        },
+    )?;
+
+    // Whether each file wants line numbers, decided by its path in the
+    // mapped directory before crates are grouped
+    let overrides = build_overrides(path, options)?;
+    let line_numbers: Vec<bool> = files
+        .iter()
+        .map(|(file, _)| {
+            let file = match path.is_dir() {
+                true => path.join(file),
+                false => path.to_path_buf(),
+            };
+            file_options(&overrides, options, &file).include_line_numbers
+        })
+        .collect();
+
+    let packages = match options.package {
+        Some(_) => packages,
+        None => mapped_packages(&files),
+    };
+    // A single package at the root is the whole project, there's nothing to
+    // group
+    let in_subdirectory = packages.iter().any(|(dir, _)| !dir.as_os_str().is_empty());
+    if in_subdirectory || options.package.is_some() {
+        group_crates(&mut files, &packages);
+    }
+
+    // Only now do re-exported and merged items know the file their lines are
+    // in, as it will be shown
+    for ((file, file_map), line_numbers) in files.iter_mut().zip(line_numbers) {
+        if line_numbers {
+            add_line_comments(file, file_map, options);
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(ProjectMap { files, errors })
+}
+
+/// Map every source file under `path`, yielding each file as soon as it is
+/// mapped.
+///
+/// Files come in no particular order, re-exports aren't expanded, Go files
+/// aren't merged per package and manifests only list what they declare, since
+/// that needs every file of the crate or package; use [`map_directory`] for
+/// that.
+pub fn map_directory_iter(path: &Path, options: &Options) -> Result<MapIter, Error> {
+    // Check the options up front so they fail here rather than in the stream
+    let packages = match options.package {
+        Some(_) => find_packages(path, options),
+        None => Vec::new(),
+    };
+    build_walker(path, &walk_root(path, options, &packages)?.0, options)?;
+    build_overrides(path, options)?;
+    check_grammars(options)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let path = path.to_path_buf();
+    let options = options.clone();
+    thread::spawn(move || {
+        // The receiver hanging up just means nobody wants the rest
+        let _ = walk(&path, &options, &packages, true, |result| {
+            let _ = sender.send(result);
+        });
+    });
+
+    Ok(MapIter { receiver })
+}
+
+/// Files mapped by [`map_directory_iter`], as they are mapped.
+pub struct MapIter {
+    receiver: mpsc::Receiver<Result<(PathBuf, FileMap), Error>>,
+}
+
+impl Iterator for MapIter {
+    type Item = Result<(PathBuf, FileMap), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Tells which paths under a directory [`map_directory`] would map without
+/// walking it, e.g. to pick out the changes to a directory that matter.
+///
+/// The include and exclude globs, ignore files, and hidden file and depth
+/// rules of the walk apply. Ignore files are read the first time a path
+/// under their directory is checked, so a filter has to be created again when
+/// they change. Directories pass when files in them could be mapped.
+pub struct PathFilter {
+    root: PathBuf,
+    languages: LanguageRegistry,
+    overrides: Override,
+    hidden: bool,
+    no_ignore: bool,
+    max_depth: Option<usize>,
+    // `.gitignore` files only count inside a git repository, like in the walk
+    in_git: bool,
+    ignores: Mutex<HashMap<PathBuf, Arc<Gitignore>>>,
+}
+
+impl PathFilter {
+    pub fn new(path: &Path, options: &Options) -> Result<Self, Error> {
+        let mut overrides = OverrideBuilder::new(path);
+        for glob in &options.include {
+            overrides.add(glob).map_err(Error::Glob)?;
+        }
+        for glob in &options.exclude {
+            overrides.add(&format!("!{}", glob)).map_err(Error::Glob)?;
+        }
+        let root = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let in_git = root.ancestors().any(|dir| dir.join(".git").exists());
+
+        Ok(PathFilter {
+            root,
+            languages: options.languages.clone(),
+            overrides: overrides.build().map_err(Error::Glob)?,
+            hidden: options.hidden,
+            no_ignore: options.no_ignore,
+            max_depth: options.max_depth,
+            in_git,
+            ignores: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `path` would be mapped, or for a directory (including one that
+    /// was removed), whether files in it could be.
+    pub fn is_mapped(&self, path: &Path) -> bool {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let is_dir = path.is_dir() || (!path.exists() && path.extension().is_none());
+
+        if self
+            .max_depth
+            .is_some_and(|depth| relative.components().count() > depth)
+        {
+            return false;
+        }
+        let is_hidden = |component: std::path::Component| {
+            component.as_os_str().to_string_lossy().starts_with('.')
+        };
+        if !self.hidden && relative.components().any(is_hidden) {
+            return false;
+        }
+
+        // The walk doesn't descend into an ignored directory, so the path is
+        // skipped if it or any directory above it is
+        let mut current = self.root.clone();
+        for component in relative.components() {
+            current.push(component);
+            let current_is_dir = current != path || is_dir;
+            if self.overrides.matched(&current, current_is_dir).is_ignore()
+                || self.is_ignored(&current, current_is_dir)
+            {
+                return false;
+            }
+        }
+
+        is_dir || self.languages.for_path(&path).is_some()
+    }
+
+    // Whether the ignore files of the directories above `path` ignore it. The
+    // deepest file with a matching pattern decides
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.no_ignore {
+            return false;
+        }
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        for dir in parent.ancestors() {
+            if !dir.starts_with(&self.root) {
+                break;
+            }
+            let ignore = self.ignore_of(dir);
+            let matched = ignore.matched(path, is_dir);
+            if !matched.is_none() {
+                return matched.is_ignore();
+            }
+        }
+        false
+    }
+
+    // The patterns of the ignore files of `dir`, with `.ignore` taking
+    // precedence over `.gitignore`
+    fn ignore_of(&self, dir: &Path) -> Arc<Gitignore> {
+        let mut ignores = self.ignores.lock().unwrap();
+        let ignore = ignores.entry(dir.to_path_buf()).or_insert_with(|| {
+            let mut builder = GitignoreBuilder::new(dir);
+            let names: &[&str] = match self.in_git {
+                true => &[".gitignore", ".ignore"],
+                false => &[".ignore"],
+            };
+            for name in names {
+                let file = dir.join(name);
+                if file.is_file() {
+                    // Invalid patterns are skipped, like in the walk
+                    let _ = builder.add(file);
+                }
+            }
+            Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()))
+        });
+        Arc::clone(ignore)
+    }
+}
+
+// Walk `start`, with globs relative to `path`
+fn build_walker(
+    path: &Path,
+    start: &Path,
+    options: &Options,
+) -> Result<ignore::WalkParallel, Error> {
+    // Select the files of every language by name or extension
+    let mut types = TypesBuilder::new();
+    for extension in options.languages.extensions() {
+        types
+            .add("codemap", &format!("*.{}", extension))
+            .map_err(Error::Glob)?;
+    }
+    for file_name in options.languages.file_names() {
+        types.add("codemap", file_name).map_err(Error::Glob)?;
+    }
+    let types = types.select("codemap").build().map_err(Error::Glob)?;
+
+    // Include globs whitelist files, exclude globs are negated overrides
+    let mut overrides = OverrideBuilder::new(path);
+    for glob in &options.include {
+        overrides.add(glob).map_err(Error::Glob)?;
+    }
+    for glob in &options.exclude {
+        overrides.add(&format!("!{}", glob)).map_err(Error::Glob)?;
+    }
+    let overrides = overrides.build().map_err(Error::Glob)?;
+
+    Ok(WalkBuilder::new(start)
+        .types(types)
+        .overrides(overrides)
+        .standard_filters(!options.no_ignore)
+        .hidden(!options.hidden)
+        .max_depth(options.max_depth)
+        .follow_links(options.follow_links)
+        .threads(options.jobs.unwrap_or(0))
+        .build_parallel())
+}
+
+// Walk `path` in parallel and hand every mapped file or error to `sink`.
+// Files are mapped, and cached, without line numbers; with `line_numbers`
+// they are added before files are handed over
+fn walk(
+    path: &Path,
+    options: &Options,
+    packages: &[(PathBuf, String)],
+    line_numbers: bool,
+    sink: impl Fn(Result<(PathBuf, FileMap), Error>) + Sync,
+) -> Result<(), Error> {
+    let (start, nested) = walk_root(path, options, packages)?;
+    let walker = build_walker(path, &start, options)?;
+    let overrides = build_overrides(path, options)?;
+    check_grammars(options)?;
+    let sink = &sink;
+    let overrides = &overrides;
+    let nested = &nested;
+
+    walker.run(|| {
+        // Each worker thread gets its own parser per language, created when
+        // the first file of the language comes up
+        let mut mappers: HashMap<&'static str, Mapper> = HashMap::new();
+
+        Box::new(move |result| {
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(err) => {
+                    sink(Err(Error::Walk(err)));
+                    return WalkState::Continue;
+                }
+            };
+            if entry.path().is_dir() {
+                if nested.iter().any(|dir| dir == entry.path()) {
+                    return WalkState::Skip;
+                }
+                return WalkState::Continue;
+            }
+            // Files an include glob let through can be in any language
+            let Some(backend) = options.languages.for_path(entry.path()) else {
+                return WalkState::Continue;
+            };
+            let mapper = match mappers.entry(backend.name()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    match Mapper::with_backend(backend.clone(), options.codemap.clone()) {
+                        Ok(mapper) => entry.insert(mapper),
+                        Err(_) => return WalkState::Quit,
+                    }
+                }
+            };
+
+            let file_options = file_options(overrides, options, entry.path());
+            let mapped_options = file_options.clone().include_line_numbers(false);
+            if *mapper.options() != mapped_options {
+                mapper.set_options(mapped_options);
+            }
+
+            match map_file(mapper, options, entry.path()) {
+                Ok(Some(mut file_map)) => {
+                    let display = display_path(path, entry.path());
+                    if line_numbers && file_options.include_line_numbers {
+                        add_line_comments(&display, &mut file_map, options);
+                    }
+                    sink(Ok((display, file_map)))
+                }
+                // Not in the language after all
+                Ok(None) => {}
+                Err(err) => sink(Err(err)),
+            }
+            WalkState::Continue
+        })
+    });
+
+    Ok(())
+}
+
+// Where to start walking, and the directories to leave out: the directory of
+// the selected package without the packages nested in it, or all of `path`
+fn walk_root(
+    path: &Path,
+    options: &Options,
+    packages: &[(PathBuf, String)],
+) -> Result<(PathBuf, Vec<PathBuf>), Error> {
+    let Some(name) = &options.package else {
+        return Ok((path.to_path_buf(), Vec::new()));
+    };
+    let (dir, _) = packages
+        .iter()
+        .find(|(_, package)| package == name)
+        .ok_or_else(|| Error::UnknownPackage { name: name.clone() })?;
+    let nested = packages
+        .iter()
+        .filter(|(other, _)| other != dir && other.starts_with(dir))
+        .map(|(other, _)| path.join(other))
+        .collect();
+    Ok((path.join(dir), nested))
+}
+
+// The Cargo packages under `path`: the directory of every manifest that
+// declares one, relative to `path`, and the package name. Manifests that
+// can't be read are reported when they are mapped
+pub(crate) fn find_packages(path: &Path, options: &Options) -> Vec<(PathBuf, String)> {
+    let walker = WalkBuilder::new(path)
+        .standard_filters(!options.no_ignore)
+        .hidden(!options.hidden)
+        .follow_links(options.follow_links)
+        .build();
+    let mut packages: Vec<(PathBuf, String)> = walker
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() == "Cargo.toml" && entry.path().is_file())
+        .filter_map(|entry| {
+            let source = std::fs::read_to_string(entry.path()).ok()?;
+            let name = package_of_manifest(&source)?;
+            let dir = entry.path().parent()?.strip_prefix(path).ok()?;
+            Some((dir.to_path_buf(), name))
+        })
+        .collect();
+    packages.sort();
+    packages
+}
+
+// Name files after the innermost package they are in rather than its
+// directory. Files outside every package keep their path, and are left in
+// the same order
+fn group_crates(files: &mut [(PathBuf, FileMap)], packages: &[(PathBuf, String)]) {
+    let group = |path: &mut PathBuf| {
+        let package = packages
+            .iter()
+            .filter(|(dir, _)| path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count());
+        if let Some((dir, name)) = package
+            && let Ok(relative) = path.strip_prefix(dir)
+        {
+            *path = Path::new(name).join(relative);
+        }
+    };
+    for (path, file_map) in files.iter_mut() {
+        group(path);
+        // Re-exported items name the file they are defined in
+        for file in file_map
+            .items
+            .iter_mut()
+            .filter_map(|item| item.file.as_mut())
+        {
+            group(file);
+        }
+    }
+}
+
+// The options of the last override matching `path`, or the default ones
+fn file_options<'a>(
+    overrides: &[(Override, &'a CodemapOptions)],
+    options: &'a Options,
+    path: &Path,
+) -> &'a CodemapOptions {
+    overrides
+        .iter()
+        .rev()
+        .find(|(glob, _)| glob.matched(path, false).is_whitelist())
+        .map_or(&options.codemap, |(_, codemap)| codemap)
+}
+
+// Precede every item of a file with the lines it spans, in the comment syntax
+// of the file's language
+fn add_line_comments(path: &Path, file_map: &mut FileMap, options: &Options) {
+    let Some(backend) = options.languages.for_path(path) else {
+        return;
+    };
+    for item in &mut file_map.items {
+        item.add_line_comment(backend.comment_prefix());
+    }
+}
+
+// Fail once up front if a grammar can't be loaded instead of for every file
+fn check_grammars(options: &Options) -> Result<(), Error> {
+    for backend in options.languages.backends() {
+        Mapper::with_backend(backend.clone(), options.codemap.clone())?;
+    }
+    Ok(())
+}
+
+// A matcher for the glob of every path override
+fn build_overrides<'a>(
+    path: &Path,
+    options: &'a Options,
+) -> Result<Vec<(Override, &'a CodemapOptions)>, Error> {
+    options
+        .overrides
+        .iter()
+        .map(|path_override| {
+            let mut glob = OverrideBuilder::new(path);
+            glob.add(&path_override.glob).map_err(Error::Glob)?;
+            let glob = glob.build().map_err(Error::Glob)?;
+            Ok((glob, &path_override.codemap))
+        })
+        .collect()
+}
+
+fn read_file(path: &Path, max_file_size: Option<u64>) -> Result<String, Error> {
+    let io_error = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    if let Some(limit) = max_file_size {
+        let size = std::fs::metadata(path).map_err(io_error)?.len();
+        if size > limit {
+            return Err(Error::TooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit,
+            });
+        }
+    }
+
+    let bytes = std::fs::read(path).map_err(io_error)?;
+    String::from_utf8(bytes).map_err(|_| Error::InvalidUtf8 {
+        path: path.to_path_buf(),
+    })
+}
+
+// A workspace keeps the trees of files it mapped before, and doesn't even read
+// the files that weren't modified since. Otherwise only parse files the cache
+// doesn't know in this exact version. Failing to store an entry isn't worth
+// reporting. `None` for files the backend doesn't accept
+fn map_file(mapper: &mut Mapper, options: &Options, path: &Path) -> Result<Option<FileMap>, Error> {
+    if let Some(workspace) = &options.workspace {
+        let stamp = file_stamp(path);
+        if let Some(file_map) = stamp.and_then(|stamp| workspace.unchanged(mapper, path, stamp)) {
+            return Ok(Some(file_map));
+        }
+        let contents = read_file(path, options.max_file_size)?;
+        if !mapper.backend().accepts(&contents) {
+            return Ok(None);
+        }
+        return workspace
+            .map_stamped(mapper, path, &contents, stamp)
+            .map(Some);
+    }
+
+    let contents = &read_file(path, options.max_file_size)?;
+    if !mapper.backend().accepts(contents) {
+        return Ok(None);
+    }
+    let cache = options.cache.as_ref();
+    if let Some(file_map) = cache.and_then(|cache| cache.get(path, contents)) {
+        return Ok(Some(file_map));
+    }
+
+    let file_map = mapper.map(contents).map_err(|_| Error::Parse {
+        path: Some(path.to_path_buf()),
+    })?;
+    if let Some(cache) = cache {
+        let _ = cache.put(path, contents, &file_map);
+    }
+    Ok(Some(file_map))
+}
+
+// Show paths relative to the walked directory, or the file name when a single
+// file was given
+fn display_path(root: &Path, path: &Path) -> PathBuf {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => {
+            path.file_name().map(PathBuf::from).unwrap_or_default()
+        }
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Everything that can go wrong while mapping source code.
+#[derive(Debug)]
+pub enum Error {
+    /// The grammar couldn't be loaded, e.g. because it was built for an
+    /// incompatible tree-sitter version
+    Language(tree_sitter::LanguageError),
+    /// tree-sitter gave up on a file without producing a tree
+    Parse { path: Option<PathBuf> },
+    /// An include or exclude glob is invalid
+    Glob(ignore::Error),
+    /// A directory entry couldn't be walked
+    Walk(ignore::Error),
+    /// A file couldn't be opened or read, e.g. for lack of permission
+    Io { path: PathBuf, source: io::Error },
+    /// A file isn't valid UTF-8, so it can't be source code
+    InvalidUtf8 { path: PathBuf },
+    /// A configuration file is malformed
+    Config { path: PathBuf, message: String },
+    /// A file is larger than the configured limit
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+    /// No Cargo package with this name is under the mapped directory
+    UnknownPackage { name: String },
+    /// A `Cargo.lock` is malformed
+    Lockfile { path: PathBuf, message: String },
+}
+
+impl Error {
+    /// The file the error is about, if it is about one.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Parse { path } => path.as_deref(),
+            Error::Io { path, .. }
+            | Error::InvalidUtf8 { path }
+            | Error::Config { path, .. }
+            | Error::Lockfile { path, .. }
+            | Error::TooLarge { path, .. } => Some(path),
+            Error::Walk(err) => walk_error_path(err),
+            Error::Language(_) | Error::Glob(_) | Error::UnknownPackage { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Language(err) => write!(f, "can't load grammar: {}", err),
+            Error::Parse { path: Some(path) } => write!(f, "{}: parsing failed", path.display()),
+            Error::Parse { path: None } => write!(f, "parsing failed"),
+            Error::Glob(err) => write!(f, "invalid glob: {}", err),
+            Error::Walk(err) => write!(f, "{}", err),
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::InvalidUtf8 { path } => write!(f, "{}: not valid UTF-8", path.display()),
+            Error::Config { path, message } | Error::Lockfile { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            Error::TooLarge { path, size, limit } => write!(
+                f,
+                "{}: too large ({} bytes, limit is {})",
+                path.display(),
+                size,
+                limit
+            ),
+            Error::UnknownPackage { name } => write!(f, "no package named `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Language(err) => Some(err),
+            Error::Glob(err) | Error::Walk(err) => Some(err),
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { .. }
+            | Error::InvalidUtf8 { .. }
+            | Error::Config { .. }
+            | Error::Lockfile { .. }
+            | Error::TooLarge { .. }
+            | Error::UnknownPackage { .. } => None,
+        }
+    }
+}
+
+impl From<tree_sitter::LanguageError> for Error {
+    fn from(err: tree_sitter::LanguageError) -> Self {
+        Error::Language(err)
+    }
+}
+
+// Walk errors wrap the path they happened at in a few layers
+fn walk_error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use tree_sitter::{InputEdit, Node, Point, Tree};
+
+use crate::{CodemapOptions, Error, FileMap, Mapper, node_text};
+
+/// A mapped file together with its parse tree, so that it can be re-mapped
+/// incrementally after an edit with [`Mapper::update_file`].
+pub struct ParsedFile {
+    source: String,
+    // `None` for backends without a tree-sitter grammar
+    tree: Option<Tree>,
+    // The backend the tree was parsed with
+    backend: &'static str,
+    // The options the memo was filled with
+    options: CodemapOptions,
+    memo: ItemMemo,
+    file_map: FileMap,
+}
+
+impl ParsedFile {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn file_map(&self) -> &FileMap {
+        &self.file_map
+    }
+}
+
+impl Mapper {
+    /// Parse and map a file, keeping its tree for later incremental updates.
+    pub fn parse_file(&mut self, source_code: &str) -> Result<ParsedFile, Error> {
+        let tree = self.parse(source_code, None)?;
+        let mut memo = ItemMemo::default();
+        let file_map = self.map_tree(tree.as_ref(), source_code, Some(&mut memo));
+
+        Ok(ParsedFile {
+            source: source_code.to_string(),
+            tree,
+            backend: self.backend.name(),
+            options: self.options.clone(),
+            memo,
+            file_map,
+        })
+    }
+
+    /// Re-map a file after its contents changed.
+    ///
+    /// The edit between the old and new contents is applied to the previous
+    /// tree so tree-sitter only re-parses the changed region, and only items
+    /// whose text changed are processed again. Files of backends without a
+    /// grammar are mapped again as a whole. On error `file` is left as it
+    /// was.
+    pub fn update_file(&mut self, file: &mut ParsedFile, source_code: &str) -> Result<(), Error> {
+        // A tree of another grammar is no use to this parser
+        if file.backend != self.backend.name() {
+            *file = self.parse_file(source_code)?;
+            return Ok(());
+        }
+        // Items rendered with other options can't be reused
+        if file.options != self.options {
+            file.options = self.options.clone();
+            file.memo = ItemMemo::default();
+            file.file_map = self.map_tree(file.tree.as_ref(), &file.source, Some(&mut file.memo));
+        }
+        if file.source == source_code {
+            return Ok(());
+        }
+
+        let old_tree = file.tree.clone().map(|mut old_tree| {
+            old_tree.edit(&input_edit(&file.source, source_code));
+            old_tree
+        });
+        file.tree = self.parse(source_code, old_tree.as_ref())?;
+        file.source = source_code.to_string();
+        file.file_map = self.map_tree(file.tree.as_ref(), source_code, Some(&mut file.memo));
+        Ok(())
+    }
+}
+
+/// The parsed files of a long-running session (the MCP server, watch mode),
+/// keyed by path, so that files mapped again are only re-parsed where they
+/// changed.
+///
+/// A `Workspace` can be shared between threads; each thread brings its own
+/// [`Mapper`].
+#[derive(Default)]
+pub struct Workspace {
+    // Files with the stamp they had when read, if the reader took one
+    files: Mutex<HashMap<PathBuf, (ParsedFile, Option<FileStamp>)>>,
+}
+
+// When a file was last modified and its size, to tell whether it changed
+// without reading it
+pub(crate) type FileStamp = (SystemTime, u64);
+
+pub(crate) fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `path` with its current contents, incrementally if it was mapped
+    /// before.
+    pub fn map(&self, mapper: &mut Mapper, path: &Path, contents: &str) -> Result<FileMap, Error> {
+        self.map_stamped(mapper, path, contents, None)
+    }
+
+    // The map of `path` from when it was last mapped, if the file still has
+    // the same stamp and `mapper` would map it the same way
+    pub(crate) fn unchanged(
+        &self,
+        mapper: &Mapper,
+        path: &Path,
+        stamp: FileStamp,
+    ) -> Option<FileMap> {
+        let files = self.files.lock().unwrap();
+        let (file, file_stamp) = files.get(path)?;
+        let unchanged = *file_stamp == Some(stamp)
+            && file.backend == mapper.backend.name()
+            && file.options == mapper.options;
+        unchanged.then(|| file.file_map.clone())
+    }
+
+    // Map `path` with `contents`, read after taking `stamp`, so a write in
+    // between is seen as a change next time
+    pub(crate) fn map_stamped(
+        &self,
+        mapper: &mut Mapper,
+        path: &Path,
+        contents: &str,
+        stamp: Option<FileStamp>,
+    ) -> Result<FileMap, Error> {
+        // Take the file out while working on it so other threads aren't
+        // blocked; a path is only mapped by one thread at a time
+        let previous = self
+            .files
+            .lock()
+            .unwrap()
+            .remove(path)
+            .map(|(file, _)| file);
+
+        let parse_error = |_| Error::Parse {
+            path: Some(path.to_path_buf()),
+        };
+        let file = match previous {
+            // A failed update leaves the file as it was, which is kept so the
+            // next update is still incremental
+            Some(mut file) => {
+                let updated = mapper.update_file(&mut file, contents);
+                let file_map = file.file_map.clone();
+                // Without a stamp the failed contents are read again next time
+                let stamp = stamp.filter(|_| updated.is_ok());
+                self.files
+                    .lock()
+                    .unwrap()
+                    .insert(path.to_path_buf(), (file, stamp));
+                return updated.map(|()| file_map).map_err(parse_error);
+            }
+            None => mapper.parse_file(contents).map_err(parse_error)?,
+        };
+        let file_map = file.file_map.clone();
+
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (file, stamp));
+        Ok(file_map)
+    }
+
+    /// Forget a file, e.g. because it was deleted.
+    pub fn remove(&self, path: &Path) {
+        self.files.lock().unwrap().remove(path);
+    }
+
+    /// Forget every file that no longer exists on disk.
+    pub fn remove_missing(&self) {
+        self.files.lock().unwrap().retain(|path, _| path.exists());
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Rendered items of a file keyed by the source text of their node, kept
+/// between incremental updates of the file.
+#[derive(Default)]
+pub struct ItemMemo {
+    pub(crate) items: Memo<Option<String>>,
+    pub(crate) impls: Memo<Option<(String, String)>>,
+}
+
+impl ItemMemo {
+    /// The rendering of `node` from the last version of the file if a node
+    /// with the same text was rendered then, otherwise `render()`.
+    pub fn get_or_render(
+        &mut self,
+        node: &Node,
+        source: &str,
+        render: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        self.items.get_or_insert_with(node, source, render)
+    }
+
+    /// Like [`ItemMemo::get_or_render`], for renderings that depend on more
+    /// than the text of a node, e.g. the key the node is the value of. `key`
+    /// has to include everything the rendering depends on.
+    pub fn get_or_render_keyed(
+        &mut self,
+        key: String,
+        render: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        self.items.get_or_insert(key, render)
+    }
+
+    // Entries not used while mapping the current version of the file are
+    // dropped
+    pub(crate) fn finish(&mut self) {
+        self.items.finish();
+        self.impls.finish();
+    }
+}
+
+pub(crate) struct Memo<T> {
+    previous: HashMap<String, T>,
+    current: HashMap<String, T>,
+}
+
+impl<T> Default for Memo<T> {
+    fn default() -> Self {
+        Memo {
+            previous: HashMap::new(),
+            current: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> Memo<T> {
+    pub(crate) fn get_or_insert_with(
+        &mut self,
+        node: &Node,
+        source: &str,
+        render: impl FnOnce() -> T,
+    ) -> T {
+        self.get_or_insert(node_text(node, source).to_string(), render)
+    }
+
+    pub(crate) fn get_or_insert(&mut self, key: String, render: impl FnOnce() -> T) -> T {
+        if let Some(value) = self.current.get(&key) {
+            return value.clone();
+        }
+
+        let value = self.previous.remove(&key).unwrap_or_else(render);
+        self.current.insert(key, value.clone());
+        value
+    }
+
+    fn finish(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+// Describe the change from `old` to `new` as a single edit spanning from the
+// first to the last differing byte
+fn input_edit(old: &str, new: &str) -> InputEdit {
+    let mut start = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Edits have to start on a character boundary in both texts
+    while !old.is_char_boundary(start) || !new.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let max_suffix = old.len().min(new.len()) - start;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point_at(old, start),
+        old_end_position: point_at(old, old_end),
+        new_end_position: point_at(new, new_end),
+    }
+}
+
+// Row and byte column of a byte offset
+fn point_at(text: &str, offset: usize) -> Point {
+    let before = &text[..offset];
+    let row = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    Point::new(row, offset - line_start)
+}
@@ -0,0 +1,564 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// C source files, which only contribute their non-`static` function and
+/// variable definitions; C headers are mapped by [`CppHeader`].
+pub struct C;
+
+/// C++ source files, which only contribute their non-`static` function and
+/// variable definitions.
+pub struct Cpp;
+
+/// C and C++ headers: prototypes, types, typedefs, constant-like macros,
+/// classes with their public sections, namespaces and templates.
+///
+/// Headers are parsed as C++, whose grammar also covers C declarations.
+/// `static` declarations and anonymous namespaces are private.
+pub struct CppHeader;
+
+impl LanguageBackend for C {
+    fn name(&self) -> &'static str {
+        "c"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["c"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_c::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo, false)
+    }
+}
+
+impl LanguageBackend for Cpp {
+    fn name(&self) -> &'static str {
+        "cpp"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["cc", "cpp", "cxx"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_cpp::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo, false)
+    }
+}
+
+impl LanguageBackend for CppHeader {
+    fn name(&self) -> &'static str {
+        "cpp-header"
+    }
+
+    fn fence(&self) -> &'static str {
+        "cpp"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["h", "hh", "hpp", "hxx"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_cpp::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo, true)
+    }
+}
+
+fn map_items(
+    tree: &Tree,
+    source: &str,
+    options: &CodemapOptions,
+    mut memo: Option<&mut ItemMemo>,
+    is_header: bool,
+) -> Vec<Item> {
+    let mut items = Vec::new();
+    for node in declarations(&tree.root_node()) {
+        let Some(entry) = describe(&node, source, options, is_header) else {
+            continue;
+        };
+        if entry.visibility < options.visibility || !header_is_clean(&node) {
+            continue;
+        }
+
+        let text = match memo.as_deref_mut() {
+            Some(memo) => {
+                memo.get_or_render(&node, source, || render(&node, source, options, is_header))
+            }
+            None => render(&node, source, options, is_header),
+        };
+        // The doc comment isn't part of the declaration's text, so it is added
+        // after the memo
+        if let Some(text) = text {
+            items.push(Item {
+                kind: entry.kind,
+                name: entry.name,
+                text: with_doc(&node, source, options, text),
+                lines: Some(Lines::of(&node)),
+                file: None,
+            });
+        }
+    }
+    items
+}
+
+// The declarations of a file or namespace, looking into preprocessor
+// conditionals and `extern "C"` blocks, whose branches are all mapped
+fn declarations<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    let mut found = Vec::new();
+    let mut cursor = node.walk();
+    for (index, child) in node.named_children(&mut cursor).enumerate() {
+        let field = node.field_name_for_named_child(index as u32);
+        if matches!(field, Some("name" | "condition")) {
+            continue;
+        }
+        match child.kind() {
+            "ERROR" | "preproc_if" | "preproc_ifdef" | "preproc_else" | "preproc_elif"
+            | "preproc_elifdef" | "declaration_list" => {
+                found.extend(declarations(&child));
+            }
+            "linkage_specification" => {
+                if let Some(body) = child.child_by_field_name("body") {
+                    match body.kind() {
+                        "declaration_list" => found.extend(declarations(&body)),
+                        _ => found.push(child),
+                    }
+                }
+            }
+            _ => found.push(child),
+        }
+    }
+    found
+}
+
+// What a declaration is and whether it is part of the map
+struct Entry {
+    kind: ItemKind,
+    name: Option<String>,
+    visibility: Visibility,
+}
+
+fn describe(node: &Node, source: &str, options: &CodemapOptions, is_header: bool) -> Option<Entry> {
+    let visibility = if has_storage_class(node, source, "static") {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    };
+    let entry = |kind, name| {
+        Some(Entry {
+            kind,
+            name,
+            visibility,
+        })
+    };
+
+    // Source files only contribute what they define
+    if !is_header {
+        return match node.kind() {
+            "function_definition" => entry(ItemKind::Function, declarator_name(node, source)),
+            "declaration"
+                if !is_function_declaration(node) && !has_storage_class(node, source, "extern") =>
+            {
+                entry(ItemKind::Const, declarator_name(node, source))
+            }
+            "template_declaration" => {
+                let inner = template_inner(node)?;
+                (inner.kind() == "function_definition")
+                    .then(|| describe(&inner, source, options, is_header))?
+            }
+            "linkage_specification" => describe(
+                &node.child_by_field_name("body")?,
+                source,
+                options,
+                is_header,
+            ),
+            "namespace_definition" => namespace_entry(node, source, options, is_header),
+            _ => None,
+        };
+    }
+
+    match node.kind() {
+        "preproc_def" => {
+            // Only macros with a value look like constants; include guards
+            // have none
+            node.child_by_field_name("value")?;
+            entry(ItemKind::Const, field_text(node, "name", source))
+        }
+        "preproc_include" => entry(ItemKind::UseDeclaration, None)
+            .filter(|_| options.include_uses && options.visibility == Visibility::Private),
+        "function_definition" => entry(ItemKind::Function, declarator_name(node, source)),
+        "declaration" if is_function_declaration(node) => {
+            entry(ItemKind::Function, declarator_name(node, source))
+        }
+        "declaration" => entry(ItemKind::Const, declarator_name(node, source)),
+        "type_definition" | "alias_declaration" => {
+            let name = field_text(node, "name", source).or_else(|| declarator_name(node, source));
+            entry(ItemKind::TypeAlias, name)
+        }
+        "struct_specifier" | "union_specifier" => {
+            entry(ItemKind::Struct, field_text(node, "name", source))
+        }
+        "class_specifier" => entry(ItemKind::Class, field_text(node, "name", source)),
+        "enum_specifier" => entry(ItemKind::Enum, field_text(node, "name", source)),
+        "template_declaration" => describe(&template_inner(node)?, source, options, is_header),
+        "linkage_specification" => describe(
+            &node.child_by_field_name("body")?,
+            source,
+            options,
+            is_header,
+        ),
+        "namespace_definition" => namespace_entry(node, source, options, is_header),
+        _ => None,
+    }
+}
+
+// Namespaces are listed when something in them is
+fn namespace_entry(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    is_header: bool,
+) -> Option<Entry> {
+    let name = field_text(node, "name", source);
+    let body = node.child_by_field_name("body")?;
+    let has_items = declarations(&body).iter().any(|declaration| {
+        describe(declaration, source, options, is_header)
+            .is_some_and(|entry| entry.visibility >= options.visibility)
+    });
+    has_items.then_some(Entry {
+        kind: ItemKind::Module,
+        visibility: match name {
+            // Anonymous namespaces are local to their file
+            Some(_) => Visibility::Public,
+            None => Visibility::Private,
+        },
+        name,
+    })
+}
+
+fn has_storage_class(node: &Node, source: &str, storage_class: &str) -> bool {
+    node.named_children(&mut node.walk()).any(|child| {
+        child.kind() == "storage_class_specifier" && node_text(&child, source) == storage_class
+    })
+}
+
+fn is_function_declaration(node: &Node) -> bool {
+    let mut declarator = node.child_by_field_name("declarator");
+    while let Some(current) = declarator {
+        if current.kind() == "function_declarator" {
+            return true;
+        }
+        declarator = current.child_by_field_name("declarator");
+    }
+    false
+}
+
+// The identifier a declaration declares, inside pointers, arrays and
+// function declarators
+fn declarator_name(node: &Node, source: &str) -> Option<String> {
+    let mut declarator = node.child_by_field_name("declarator")?;
+    while let Some(inner) = declarator.child_by_field_name("declarator").or_else(|| {
+        // `(*callback)` has no field for what it wraps
+        (declarator.kind() == "parenthesized_declarator")
+            .then(|| declarator.named_child(0))
+            .flatten()
+    }) {
+        declarator = inner;
+    }
+    Some(node_text(&declarator, source).to_string())
+}
+
+fn field_text(node: &Node, field: &str, source: &str) -> Option<String> {
+    let child = node.child_by_field_name(field)?;
+    Some(node_text(&child, source).to_string())
+}
+
+// The declaration a `template <...>` applies to
+fn template_inner<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    let parameters = node.child_by_field_name("parameters")?;
+    node.named_children(&mut node.walk())
+        .find(|child| child.start_byte() >= parameters.end_byte() && child.kind() != "comment")
+}
+
+fn render(node: &Node, source: &str, options: &CodemapOptions, is_header: bool) -> Option<String> {
+    match node.kind() {
+        "preproc_def" => {
+            let value = node.child_by_field_name("value")?;
+            let name = field_text(node, "name", source)?;
+            let value = node_text(&value, source).trim();
+            // Multi-line macros continue lines with `\`
+            if value.contains('\n') {
+                Some(format!("#define {} ...", name))
+            } else {
+                Some(format!("#define {} {}", name, collapse_whitespace(value)))
+            }
+        }
+        "function_definition" => signature(node, source),
+        "declaration" | "field_declaration" => render_declaration(node, source),
+        "type_definition" => {
+            let declared = node.child_by_field_name("type")?;
+            match declared.child_by_field_name("body") {
+                Some(body) => {
+                    let record = render_record(&declared, source, options)?;
+                    let declarators = source.get(body.end_byte()..node.end_byte())?;
+                    Some(format!(
+                        "typedef {} {}",
+                        record,
+                        collapse_whitespace(declarators)
+                    ))
+                }
+                None => Some(collapse_whitespace(node_text(node, source))),
+            }
+        }
+        "struct_specifier" | "union_specifier" | "class_specifier" | "enum_specifier" => {
+            render_record(node, source, options).map(|record| format!("{};", record))
+        }
+        "template_declaration" => {
+            let inner = template_inner(node)?;
+            let head = collapse_whitespace(source.get(node.start_byte()..inner.start_byte())?);
+            let inner = render(&inner, source, options, is_header)?;
+            Some(format!("{}\n{}", head, inner))
+        }
+        // `extern "C"` for a single declaration
+        "linkage_specification" => {
+            let body = node.child_by_field_name("body")?;
+            let head = collapse_whitespace(source.get(node.start_byte()..body.start_byte())?);
+            let body = render(&body, source, options, is_header)?;
+            Some(format!("{} {}", head, body))
+        }
+        "namespace_definition" => render_namespace(node, source, options, is_header),
+        _ => Some(collapse_whitespace(node_text(node, source))),
+    }
+}
+
+// A function without its body, constructor initializers included
+fn signature(node: &Node, source: &str) -> Option<String> {
+    let end = node
+        .named_children(&mut node.walk())
+        .find(|child| {
+            matches!(
+                child.kind(),
+                "compound_statement" | "field_initializer_list"
+            )
+        })
+        .map(|body| body.start_byte());
+    match end {
+        Some(end) => Some(format!(
+            "{};",
+            collapse_whitespace(source.get(node.start_byte()..end)?)
+        )),
+        // `= default;` and `= delete;` have no body
+        None => Some(collapse_whitespace(node_text(node, source))),
+    }
+}
+
+// A declaration, without the values of variables initialized over several
+// lines
+fn render_declaration(node: &Node, source: &str) -> Option<String> {
+    let text = node_text(node, source);
+    if !text.contains('\n') {
+        return Some(collapse_whitespace(text));
+    }
+
+    let declarators: Vec<Node> = node
+        .children_by_field_name("declarator", &mut node.walk())
+        .collect();
+    let Some(first) = declarators.first() else {
+        return Some(collapse_whitespace(text));
+    };
+    let head = collapse_whitespace(source.get(node.start_byte()..first.start_byte())?);
+    let names: Vec<String> = declarators
+        .iter()
+        .map(|declarator| {
+            let declarator = match declarator.kind() {
+                "init_declarator" => declarator
+                    .child_by_field_name("declarator")
+                    .unwrap_or(*declarator),
+                _ => *declarator,
+            };
+            collapse_whitespace(node_text(&declarator, source))
+        })
+        .collect();
+    Some(format!("{} {};", head, names.join(", ")))
+}
+
+// A struct, union, class or enum with its visible members, without the `;`
+fn render_record(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let Some(body) = node.child_by_field_name("body") else {
+        return Some(collapse_whitespace(node_text(node, source)));
+    };
+    let header = collapse_whitespace(source.get(node.start_byte()..body.start_byte())?);
+
+    let members = match body.kind() {
+        "enumerator_list" => {
+            let enumerators: Vec<String> = body
+                .named_children(&mut body.walk())
+                .filter(|child| child.kind() == "enumerator")
+                .map(|enumerator| {
+                    let text = collapse_whitespace(node_text(&enumerator, source));
+                    with_doc(&enumerator, source, options, text)
+                })
+                .collect();
+            indent(&enumerators.join(",\n"))
+        }
+        _ => record_members(node, &body, source, options).join("\n"),
+    };
+
+    Some(if members.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, members)
+    })
+}
+
+// The indented members of the sections a struct or class shows; class
+// members are private until an access specifier says otherwise. Sections
+// are labeled, unindented, where their access differs from the one before
+fn record_members(node: &Node, body: &Node, source: &str, options: &CodemapOptions) -> Vec<String> {
+    let mut access = match node.kind() {
+        "class_specifier" => (Visibility::Private, "private"),
+        _ => (Visibility::Public, "public"),
+    };
+    // The access of the members shown last
+    let mut shown = access;
+    let mut members = Vec::new();
+    for member in body.named_children(&mut body.walk()) {
+        if member.kind() == "access_specifier" {
+            access = match node_text(&member, source) {
+                "public" => (Visibility::Public, "public"),
+                "protected" => (Visibility::Crate, "protected"),
+                _ => (Visibility::Private, "private"),
+            };
+            continue;
+        }
+        // Drop members that didn't parse rather than show half of them
+        if access.0 < options.visibility || member.has_error() {
+            continue;
+        }
+        let rendered = match member.kind() {
+            "comment" => None,
+            kind if kind.starts_with("preproc") => None,
+            "function_definition" => signature(&member, source),
+            "field_declaration" | "declaration" => render_declaration(&member, source),
+            "struct_specifier" | "union_specifier" | "class_specifier" | "enum_specifier" => {
+                render_record(&member, source, options).map(|record| format!("{};", record))
+            }
+            "template_declaration" => render(&member, source, options, true),
+            _ => Some(collapse_whitespace(node_text(&member, source))),
+        };
+        if let Some(rendered) = rendered {
+            if access != shown {
+                members.push(format!("{}:", access.1));
+                shown = access;
+            }
+            members.push(indent(&with_doc(&member, source, options, rendered)));
+        }
+    }
+    members
+}
+
+fn render_namespace(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    is_header: bool,
+) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let header = collapse_whitespace(source.get(node.start_byte()..body.start_byte())?);
+
+    let mut members = Vec::new();
+    for declaration in declarations(&body) {
+        let is_visible = describe(&declaration, source, options, is_header)
+            .is_some_and(|entry| entry.visibility >= options.visibility);
+        if !is_visible || !header_is_clean(&declaration) {
+            continue;
+        }
+        if let Some(rendered) = render(&declaration, source, options, is_header) {
+            members.push(with_doc(&declaration, source, options, rendered));
+        }
+    }
+    Some(format!(
+        "{} {{\n{}\n}}",
+        header,
+        indent(&members.join("\n\n"))
+    ))
+}
+
+// Indent every line by one level
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| match line {
+            "" => String::new(),
+            line => format!("    {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Precede `text` with the first line of the comment right above `node`
+fn with_doc(node: &Node, source: &str, options: &CodemapOptions, text: String) -> String {
+    match doc_summary(node, source).filter(|_| options.include_docs) {
+        Some(doc) => format!("// {}\n{}", doc, text),
+        None => text,
+    }
+}
+
+// The first line of the comment ending on the line before `node`, in any of
+// the `//`, `///`, `/* */` and `/** */` styles
+fn doc_summary(node: &Node, source: &str) -> Option<String> {
+    let mut comment = node.prev_named_sibling()?;
+    if comment.kind() != "comment" || comment.end_position().row + 1 != node.start_position().row {
+        return None;
+    }
+    // A block of line comments starts at its first line
+    while let Some(previous) = comment.prev_named_sibling() {
+        if previous.kind() != "comment"
+            || previous.end_position().row + 1 != comment.start_position().row
+            || !node_text(&comment, source).starts_with("//")
+        {
+            break;
+        }
+        comment = previous;
+    }
+    let text = node_text(&comment, source);
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches('/')
+                .trim_start_matches(['*', '!'])
+                .trim()
+        })
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,508 @@
+use std::path::{Path, PathBuf};
+
+use tree_sitter::{Language, Node, Tree};
+
+use super::LanguageBackend;
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, FileMap, Item, ItemKind, Lines, node_text};
+
+const DEPENDENCY_KINDS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Cargo manifests: the package, its targets, features and the names of its
+/// dependencies, and the members of a workspace.
+///
+/// Only files named `Cargo.toml` are mapped. Targets Cargo discovers on its own and
+/// workspace member globs are filled in by [`map_directory`](crate::map_directory),
+/// which knows the other files of the crate.
+pub struct CargoManifest;
+
+impl LanguageBackend for CargoManifest {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn fence(&self) -> &'static str {
+        "toml"
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        "#"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn file_names(&self) -> &'static [&'static str] {
+        &["Cargo.toml"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_toml_ng::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        _memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        // Syntax errors are reported from the tree
+        let Ok(manifest) = toml::from_str::<toml::Table>(source) else {
+            return Vec::new();
+        };
+        let tables = tables(&tree.root_node(), source);
+        let lines = |path: &[&str]| section_lines(&tables, path);
+
+        let mut items = Vec::new();
+        if let Some(package) = table(&manifest, "package") {
+            let name = string(package, "name");
+            let mut lines_of_package = vec!["[package]".to_string()];
+            for key in ["name", "version"] {
+                if let Some(value) = string(package, key) {
+                    lines_of_package.push(format!("{} = {}", key, quote(&value)));
+                }
+            }
+            if options.include_docs
+                && let Some(description) = string(package, "description")
+            {
+                lines_of_package.push(format!("description = {}", quote(&description)));
+            }
+            items.push(Item {
+                kind: ItemKind::Module,
+                name,
+                text: lines_of_package.join("\n"),
+                lines: lines(&["package"]),
+                file: None,
+            });
+        }
+
+        if let Some(lib) = table(&manifest, "lib") {
+            items.push(target_item("lib", lib, lines(&["lib"])));
+        }
+        for kind in ["bin", "example"] {
+            let targets = manifest.get(kind).and_then(toml::Value::as_array);
+            for target in targets
+                .into_iter()
+                .flatten()
+                .filter_map(toml::Value::as_table)
+            {
+                items.push(target_item(kind, target, lines(&[kind])));
+            }
+        }
+
+        if let Some(features) = table(&manifest, "features") {
+            let mut text = vec!["[features]".to_string()];
+            for (feature, enables) in features {
+                text.push(format!("{} = {}", key(feature), enables));
+            }
+            items.push(Item {
+                kind: ItemKind::Other("features".to_string()),
+                name: None,
+                text: text.join("\n"),
+                lines: lines(&["features"]),
+                file: None,
+            });
+        }
+
+        for kind in DEPENDENCY_KINDS {
+            if let Some(dependencies) = table(&manifest, kind) {
+                items.push(dependencies_item(kind, dependencies, lines(&[kind])));
+            }
+        }
+        for (platform, platform_table) in table(&manifest, "target").into_iter().flatten() {
+            for kind in DEPENDENCY_KINDS {
+                if let Some(dependencies) = platform_table.get(kind).and_then(toml::Value::as_table)
+                {
+                    let header = format!("target.{}.{}", key(platform), kind);
+                    let lines = lines(&["target", platform, kind]);
+                    items.push(dependencies_item(&header, dependencies, lines));
+                }
+            }
+        }
+
+        if let Some(workspace) = table(&manifest, "workspace") {
+            let mut text = vec!["[workspace]".to_string()];
+            for list in ["members", "exclude"] {
+                if let Some(globs) = workspace.get(list).filter(|globs| globs.is_array()) {
+                    text.push(format!("{} = {}", list, globs));
+                }
+            }
+            items.push(Item {
+                kind: ItemKind::Other("workspace".to_string()),
+                name: None,
+                text: text.join("\n"),
+                lines: lines(&["workspace"]),
+                file: None,
+            });
+            if let Some(dependencies) = table(workspace, "dependencies") {
+                let lines = lines(&["workspace", "dependencies"]);
+                items.push(dependencies_item(
+                    "workspace.dependencies",
+                    dependencies,
+                    lines,
+                ));
+            }
+        }
+
+        items
+    }
+}
+
+/// The packages of the mapped manifests among `files`: the directory of
+/// every manifest that declares one and the package name, sorted.
+pub(crate) fn mapped_packages(files: &[(PathBuf, FileMap)]) -> Vec<(PathBuf, String)> {
+    let mut packages: Vec<_> = files
+        .iter()
+        .filter(|(path, _)| is_manifest(path))
+        .filter_map(|(path, file_map)| Some((parent(path), package_name(file_map)?)))
+        .collect();
+    packages.sort();
+    packages
+}
+
+/// Fill in what a manifest leaves for Cargo to work out from the files
+/// around it: the targets it discovers under `src/` and `examples/`, and the
+/// workspace members its globs match.
+///
+/// `files` are the files of the project under `root`, with paths relative to
+/// it.
+pub(crate) fn discover_crates(root: &Path, files: &mut [(PathBuf, FileMap)]) {
+    let paths: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    // Directories of the packages, relative to the root
+    let packages: Vec<PathBuf> = files
+        .iter()
+        .filter(|(path, file_map)| is_manifest(path) && package_name(file_map).is_some())
+        .map(|(path, _)| parent(path))
+        .collect();
+
+    for (path, file_map) in files.iter_mut().filter(|(path, _)| is_manifest(path)) {
+        // The items only hold the rendered manifest, so it is read again. A
+        // single manifest given as the root is named after itself
+        let source = if root.is_dir() {
+            std::fs::read_to_string(root.join(&*path))
+        } else {
+            std::fs::read_to_string(root)
+        };
+        let Some(manifest) = source
+            .ok()
+            .and_then(|source| toml::from_str::<toml::Table>(&source).ok())
+        else {
+            continue;
+        };
+        let dir = parent(path);
+        let relative: Vec<&Path> = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&dir).ok())
+            .collect();
+        if let Some(package) = package_name(file_map) {
+            add_discovered_targets(file_map, &manifest, &package, &relative);
+        }
+
+        let members: Vec<String> = packages
+            .iter()
+            .filter_map(|package| package.strip_prefix(&dir).ok())
+            .filter(|member| !member.as_os_str().is_empty())
+            .map(|member| member.to_string_lossy().replace('\\', "/"))
+            .collect();
+        resolve_members(file_map, &manifest, &members);
+    }
+}
+
+/// The name of the package a manifest declares, if it declares one.
+pub(crate) fn package_of_manifest(source: &str) -> Option<String> {
+    let manifest = toml::from_str::<toml::Table>(source).ok()?;
+    string(table(&manifest, "package")?, "name")
+}
+
+/// The `path` of every dependency a manifest declares, relative to it.
+pub(crate) fn path_dependencies(source: &str) -> Vec<String> {
+    let Ok(manifest) = toml::from_str::<toml::Table>(source) else {
+        return Vec::new();
+    };
+    let mut tables: Vec<&toml::Table> = DEPENDENCY_KINDS
+        .iter()
+        .filter_map(|kind| table(&manifest, kind))
+        .collect();
+    for (_, platform) in table(&manifest, "target").into_iter().flatten() {
+        tables.extend(
+            DEPENDENCY_KINDS
+                .iter()
+                .filter_map(|kind| platform.get(kind).and_then(toml::Value::as_table)),
+        );
+    }
+    if let Some(workspace) = table(&manifest, "workspace") {
+        tables.extend(table(workspace, "dependencies"));
+    }
+    tables
+        .into_iter()
+        .flat_map(|dependencies| dependencies.values())
+        .filter_map(|dependency| dependency.get("path").and_then(toml::Value::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_manifest(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "Cargo.toml")
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn package_name(file_map: &FileMap) -> Option<String> {
+    file_map
+        .items
+        .iter()
+        .find(|item| item.kind == ItemKind::Module)
+        .and_then(|item| item.name.clone())
+}
+
+// Add the targets Cargo would find in the package's directory to the ones its
+// manifest declares, after them
+fn add_discovered_targets(
+    file_map: &mut FileMap,
+    manifest: &toml::Table,
+    package: &str,
+    files: &[&Path],
+) {
+    let is_declared = |kind: &str, name: &str, path: &str| match kind {
+        "lib" => table(manifest, "lib").is_some(),
+        _ => manifest
+            .get(kind)
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_table)
+            .any(|target| {
+                string(target, "name").as_deref() == Some(name)
+                    || string(target, "path").as_deref() == Some(path)
+            }),
+    };
+
+    let mut discovered = Vec::new();
+    for file in files {
+        let file = file.to_string_lossy().replace('\\', "/");
+        let (kind, name) = match file.split('/').collect::<Vec<_>>().as_slice() {
+            ["src", "lib.rs"] => ("lib", package.to_string()),
+            ["src", "main.rs"] => ("bin", package.to_string()),
+            ["src", "bin", name] | ["examples", name] if name.ends_with(".rs") => {
+                let kind = if file.starts_with("src") {
+                    "bin"
+                } else {
+                    "example"
+                };
+                (kind, name.trim_end_matches(".rs").to_string())
+            }
+            ["src", "bin", name, "main.rs"] => ("bin", name.to_string()),
+            ["examples", name, "main.rs"] => ("example", name.to_string()),
+            _ => continue,
+        };
+        if is_declared(kind, &name, &file) {
+            continue;
+        }
+        let text = match kind {
+            "lib" => format!("[lib]\npath = {}", quote(&file)),
+            kind => format!(
+                "[[{}]]\nname = {}\npath = {}",
+                kind,
+                quote(&name),
+                quote(&file)
+            ),
+        };
+        discovered.push((kind, name, text));
+    }
+    // Libraries first, then binaries and examples, each sorted by path
+    discovered.sort_by_key(|(kind, _, _)| ["lib", "bin", "example"].iter().position(|k| k == kind));
+
+    let position = file_map
+        .items
+        .iter()
+        .rposition(|item| {
+            item.kind == ItemKind::Other("target".to_string()) || item.kind == ItemKind::Module
+        })
+        .map_or(0, |index| index + 1);
+    let targets = discovered.into_iter().map(|(_, name, text)| Item {
+        kind: ItemKind::Other("target".to_string()),
+        name: Some(name),
+        text,
+        lines: None,
+        file: None,
+    });
+    file_map.items.splice(position..position, targets);
+}
+
+// Replace the member globs of a workspace with the packages they match
+fn resolve_members(file_map: &mut FileMap, manifest: &toml::Table, packages: &[String]) {
+    let (Some(workspace), Some(item)) = (
+        table(manifest, "workspace"),
+        file_map
+            .items
+            .iter_mut()
+            .find(|item| item.kind == ItemKind::Other("workspace".to_string())),
+    ) else {
+        return;
+    };
+    let globs = |list: &str| -> Vec<String> {
+        workspace
+            .get(list)
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|glob| {
+                glob.as_str()
+                    .map(|glob| glob.trim_end_matches('/').to_string())
+            })
+            .collect()
+    };
+    let (members, exclude) = (globs("members"), globs("exclude"));
+
+    let matched: Vec<&String> = packages
+        .iter()
+        .filter(|package| members.iter().any(|glob| glob_matches(glob, package)))
+        .filter(|package| !exclude.iter().any(|glob| glob_matches(glob, package)))
+        .collect();
+    // Keep the line numbers above the header, when they are shown
+    let header = item.text.find("[workspace]").unwrap_or(0);
+    let mut text = vec![format!("{}[workspace]", &item.text[..header])];
+    if !matched.is_empty() {
+        let matched: Vec<String> = matched.iter().map(|member| quote(member)).collect();
+        text.push(format!("members = [{}]", matched.join(", ")));
+    }
+    item.text = text.join("\n");
+}
+
+// Cargo's member globs: `*` and `?` within a path segment
+fn glob_matches(glob: &str, path: &str) -> bool {
+    let globs: Vec<&str> = glob.split('/').collect();
+    let segments: Vec<&str> = path.split('/').collect();
+    globs.len() == segments.len()
+        && globs
+            .iter()
+            .zip(&segments)
+            .all(|(glob, segment)| segment_matches(glob.as_bytes(), segment.as_bytes()))
+}
+
+fn segment_matches(glob: &[u8], text: &[u8]) -> bool {
+    match (glob.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            segment_matches(&glob[1..], text)
+                || (!text.is_empty() && segment_matches(glob, &text[1..]))
+        }
+        (Some(b'?'), Some(_)) => segment_matches(&glob[1..], &text[1..]),
+        (Some(g), Some(t)) if g == t => segment_matches(&glob[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+fn target_item(kind: &str, target: &toml::Table, lines: Option<Lines>) -> Item {
+    let header = match kind {
+        "lib" => "[lib]".to_string(),
+        kind => format!("[[{}]]", kind),
+    };
+    let mut text = vec![header];
+    for key in ["name", "path", "required-features", "proc-macro"] {
+        if let Some(value) = target.get(key) {
+            text.push(format!("{} = {}", key, value));
+        }
+    }
+    Item {
+        kind: ItemKind::Other("target".to_string()),
+        name: string(target, "name"),
+        text: text.join("\n"),
+        lines,
+        file: None,
+    }
+}
+
+// `[dependencies]` and the names of the dependencies on one line
+fn dependencies_item(header: &str, dependencies: &toml::Table, lines: Option<Lines>) -> Item {
+    let names: Vec<&str> = dependencies.keys().map(String::as_str).collect();
+    Item {
+        kind: ItemKind::Other("dependencies".to_string()),
+        name: None,
+        text: format!("[{}]\n{}", header, names.join(", ")),
+        lines,
+        file: None,
+    }
+}
+
+fn table<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Table> {
+    table.get(key)?.as_table()
+}
+
+fn string(table: &toml::Table, key: &str) -> Option<String> {
+    Some(table.get(key)?.as_str()?.to_string())
+}
+
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+// A key, quoted unless it is a bare key
+fn key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_bare { key.to_string() } else { quote(key) }
+}
+
+// The header of every table of the file with the node it spans
+fn tables<'a>(root: &Node<'a>, source: &str) -> Vec<(Vec<String>, Node<'a>)> {
+    root.named_children(&mut root.walk())
+        .filter(|node| matches!(node.kind(), "table" | "table_array_element"))
+        .filter_map(|table| {
+            let header = table
+                .named_children(&mut table.walk())
+                .find(|child| child.kind().ends_with("key"))?;
+            Some((key_path(&header, source), table))
+        })
+        .collect()
+}
+
+fn key_path(node: &Node, source: &str) -> Vec<String> {
+    match node.kind() {
+        "dotted_key" => node
+            .named_children(&mut node.walk())
+            .flat_map(|part| key_path(&part, source))
+            .collect(),
+        "quoted_key" => {
+            let text = node_text(node, source);
+            vec![text[1..text.len().saturating_sub(1)].to_string()]
+        }
+        _ => vec![node_text(node, source).to_string()],
+    }
+}
+
+// The lines of the tables a section is written in, `[dependencies]` and
+// `[dependencies.serde]` alike
+fn section_lines(tables: &[(Vec<String>, Node)], path: &[&str]) -> Option<Lines> {
+    let mut section: Option<Lines> = None;
+    for (header, table) in tables {
+        if header.len() < path.len() || header.iter().zip(path).any(|(a, b)| a != b) {
+            continue;
+        }
+        // Tables take in the blank lines after them, so they end where their
+        // last key does
+        let last = table
+            .named_children(&mut table.walk())
+            .filter(|child| child.kind() != "comment")
+            .last()
+            .unwrap_or(*table);
+        let lines = Lines {
+            start: table.start_position().row + 1,
+            end: last.end_position().row + 1,
+        };
+        section = Some(match section {
+            Some(section) => Lines {
+                start: section.start.min(lines.start),
+                end: section.end.max(lines.end),
+            },
+            None => lines,
+        });
+    }
+    section
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Diagnostic, FileMap, Item, ItemKind, Lines, Visibility, node_text};
+
+/// Go, with exported (capitalized) identifiers public and everything else
+/// private. Methods are listed under their receiver type, the way Rust impl
+/// blocks are listed under their struct.
+///
+/// [`map_directory`](crate::map_directory) merges the files of a package
+/// into one entry per package.
+pub struct Go;
+
+impl LanguageBackend for Go {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["go"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_go::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let declarations = top_level_items(&tree.root_node());
+
+        // First pass: render the methods of every receiver type
+        let mut methods: HashMap<String, Vec<String>> = HashMap::new();
+        for node in declarations
+            .iter()
+            .filter(|node| node.kind() == "method_declaration")
+        {
+            let Some(receiver) = receiver_type(node, source) else {
+                continue;
+            };
+            if !header_is_clean(node)
+                || visibility(&receiver) < options.visibility
+                || !is_visible(node, source, options)
+            {
+                continue;
+            }
+            let signature = match memo.as_deref_mut() {
+                Some(memo) => memo.get_or_render(node, source, || signature(node, source)),
+                None => signature(node, source),
+            };
+            if let Some(signature) = signature {
+                let signature = with_doc(node, source, options, signature);
+                methods.entry(receiver).or_default().push(signature);
+            }
+        }
+
+        // Second pass: everything else, with the methods under their type
+        let mut items = Vec::new();
+        for node in &declarations {
+            if !header_is_clean(node) {
+                continue;
+            }
+            match node.kind() {
+                "package_clause" => items.push(Item {
+                    kind: ItemKind::Module,
+                    name: None,
+                    text: with_doc(
+                        node,
+                        source,
+                        options,
+                        collapse_whitespace(node_text(node, source)),
+                    ),
+                    lines: Some(Lines::of(node)),
+                    file: None,
+                }),
+                "import_declaration"
+                    if options.include_uses && options.visibility == Visibility::Private =>
+                {
+                    items.push(Item {
+                        kind: ItemKind::UseDeclaration,
+                        name: None,
+                        text: node_text(node, source).to_string(),
+                        lines: Some(Lines::of(node)),
+                        file: None,
+                    });
+                }
+                "function_declaration" => {
+                    if !is_visible(node, source, options) {
+                        continue;
+                    }
+                    let text = match memo.as_deref_mut() {
+                        Some(memo) => memo.get_or_render(node, source, || signature(node, source)),
+                        None => signature(node, source),
+                    };
+                    if let Some(text) = text {
+                        items.push(Item {
+                            kind: ItemKind::Function,
+                            name: name(node, source),
+                            text: with_doc(node, source, options, text),
+                            lines: Some(Lines::of(node)),
+                            file: None,
+                        });
+                    }
+                }
+                "type_declaration" => {
+                    let specs: Vec<Node> = node
+                        .named_children(&mut node.walk())
+                        .filter(|spec| matches!(spec.kind(), "type_spec" | "type_alias"))
+                        .collect();
+                    for spec in &specs {
+                        let Some(type_name) = name(spec, source) else {
+                            continue;
+                        };
+                        if visibility(&type_name) < options.visibility {
+                            continue;
+                        }
+                        let text = match memo.as_deref_mut() {
+                            Some(memo) => memo
+                                .get_or_render(spec, source, || render_type(spec, source, options)),
+                            None => render_type(spec, source, options),
+                        };
+                        let Some(mut text) = text else {
+                            continue;
+                        };
+                        if let Some(methods) = methods.remove(&type_name) {
+                            text = format!("{}\n\n{}", text, methods.join("\n"));
+                        }
+                        // A lone spec is documented above `type`
+                        let documented = if specs.len() == 1 { node } else { spec };
+                        items.push(Item {
+                            kind: type_kind(spec),
+                            name: Some(type_name),
+                            text: with_doc(documented, source, options, text),
+                            lines: Some(Lines::of(documented)),
+                            file: None,
+                        });
+                    }
+                }
+                "const_declaration" | "var_declaration" => {
+                    let text = match memo.as_deref_mut() {
+                        Some(memo) => memo
+                            .get_or_render(node, source, || render_values(node, source, options)),
+                        None => render_values(node, source, options),
+                    };
+                    if let Some(text) = text {
+                        items.push(Item {
+                            kind: ItemKind::Const,
+                            name: value_names(node, source).into_iter().next(),
+                            text: with_doc(node, source, options, text),
+                            lines: Some(Lines::of(node)),
+                            file: None,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Methods of types declared in another file of the package; merging
+        // the package moves them under their type
+        let mut orphans: Vec<_> = methods.into_iter().collect();
+        orphans.sort();
+        for (receiver, methods) in orphans {
+            items.push(Item {
+                kind: ItemKind::Impl,
+                name: Some(receiver),
+                text: methods.join("\n"),
+                lines: None,
+                file: None,
+            });
+        }
+
+        items
+    }
+}
+
+/// Merge the Go files of every directory into one entry per package, named
+/// `dir/*.go`, so a type is listed with the methods declared in other files
+/// of its package. Other files and packages of a single file are left as
+/// they are.
+///
+/// Items keep the lines of the file they come from and name it in
+/// [`Item::file`]; diagnostics are prefixed with their file name.
+pub(crate) fn group_packages(files: Vec<(PathBuf, FileMap)>) -> Vec<(PathBuf, FileMap)> {
+    // Other files stay where they are, packages take the place of their
+    // first file
+    let mut grouped: Vec<Result<(PathBuf, FileMap), PathBuf>> = Vec::new();
+    let mut packages: HashMap<PathBuf, Vec<(PathBuf, FileMap)>> = HashMap::new();
+    for (path, file_map) in files {
+        if path.extension().is_none_or(|extension| extension != "go") {
+            grouped.push(Ok((path, file_map)));
+            continue;
+        }
+        let package = path.with_file_name("*.go");
+        let package_files = packages.entry(package.clone()).or_default();
+        if package_files.is_empty() {
+            grouped.push(Err(package));
+        }
+        package_files.push((path, file_map));
+    }
+
+    grouped
+        .into_iter()
+        .map(|entry| {
+            let package = match entry {
+                Ok(file) => return file,
+                Err(package) => package,
+            };
+            let mut files = packages.remove(&package).unwrap_or_default();
+            // A package of one file keeps the file's name
+            if files.len() == 1 {
+                return files.remove(0);
+            }
+            (package, merge_package(files))
+        })
+        .collect()
+}
+
+fn merge_package(files: Vec<(PathBuf, FileMap)>) -> FileMap {
+    let mut merged = FileMap::default();
+    for (path, file_map) in files {
+        for mut item in file_map.items {
+            if item.lines.is_some() {
+                item.file = Some(path.clone());
+            }
+            if item.kind != ItemKind::Module {
+                merged.items.push(item);
+                continue;
+            }
+            // Every file repeats the package clause; keep the documented one
+            let clause = item.text.lines().last();
+            let existing = merged.items.iter_mut().find(|other| {
+                other.kind == ItemKind::Module && other.text.lines().last() == clause
+            });
+            match existing {
+                Some(existing) if existing.text.len() < item.text.len() => *existing = item,
+                Some(_) => {}
+                None => merged.items.push(item),
+            }
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        merged.diagnostics.extend(
+            file_map
+                .diagnostics
+                .into_iter()
+                .map(|diagnostic| Diagnostic {
+                    message: format!("{}: {}", file_name, diagnostic.message),
+                    ..diagnostic
+                }),
+        );
+    }
+
+    attach_orphan_methods(&mut merged);
+    merged
+}
+
+// Structs and interfaces by their type; aliases and other defined types,
+// e.g. `type Celsius float64`, are type aliases
+fn type_kind(spec: &Node) -> ItemKind {
+    let declared = spec.child_by_field_name("type").map(|node| node.kind());
+    match (spec.kind(), declared) {
+        ("type_spec", Some("struct_type")) => ItemKind::Struct,
+        ("type_spec", Some("interface_type")) => ItemKind::Interface,
+        _ => ItemKind::TypeAlias,
+    }
+}
+
+// Move methods mapped apart from their type under it, now that the type is in
+// the same map
+fn attach_orphan_methods(file_map: &mut FileMap) {
+    let mut orphans = Vec::new();
+    file_map.items.retain(|item| {
+        let is_orphan = item.kind == ItemKind::Impl;
+        if is_orphan {
+            orphans.push(item.clone());
+        }
+        !is_orphan
+    });
+
+    for orphan in orphans {
+        let owner = file_map.items.iter_mut().find(|item| {
+            matches!(
+                item.kind,
+                ItemKind::Struct | ItemKind::Interface | ItemKind::TypeAlias
+            ) && item.name == orphan.name
+        });
+        match owner {
+            // Methods form one block after the type
+            Some(owner) => {
+                let has_methods = owner
+                    .text
+                    .lines()
+                    .last()
+                    .is_some_and(|line| line.starts_with("func ("));
+                let separator = if has_methods { "\n" } else { "\n\n" };
+                owner.text = format!("{}{}{}", owner.text, separator, orphan.text);
+            }
+            // The type isn't visible or isn't in the package
+            None => file_map.items.push(orphan),
+        }
+    }
+}
+
+fn name(node: &Node, source: &str) -> Option<String> {
+    let name = node.child_by_field_name("name")?;
+    Some(node_text(&name, source).to_string())
+}
+
+// Exported identifiers start with an upper case letter
+fn visibility(name: &str) -> Visibility {
+    if name.starts_with(char::is_uppercase) {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
+
+fn is_visible(node: &Node, source: &str, options: &CodemapOptions) -> bool {
+    name(node, source).is_some_and(|name| visibility(&name) >= options.visibility)
+}
+
+// The type a method is declared on, without pointer or type arguments
+fn receiver_type(method: &Node, source: &str) -> Option<String> {
+    let receiver = method.child_by_field_name("receiver")?;
+    let parameter = receiver
+        .named_children(&mut receiver.walk())
+        .find(|child| child.kind() == "parameter_declaration")?;
+    let mut receiver_type = parameter.child_by_field_name("type")?;
+    loop {
+        receiver_type = match receiver_type.kind() {
+            "pointer_type" => receiver_type.named_child(0)?,
+            "generic_type" => receiver_type.child_by_field_name("type")?,
+            "parenthesized_type" => receiver_type.named_child(0)?,
+            _ => break,
+        };
+    }
+    Some(node_text(&receiver_type, source).to_string())
+}
+
+// A function or method without its body, with its parameter lists on one
+// line
+fn signature(node: &Node, source: &str) -> Option<String> {
+    let mut text = String::from("func ");
+    if let Some(receiver) = node.child_by_field_name("receiver") {
+        text.push_str(&format!("{} ", parameter_list(&receiver, source, "(", ")")));
+    }
+    text.push_str(node_text(&node.child_by_field_name("name")?, source));
+    if let Some(type_parameters) = node.child_by_field_name("type_parameters") {
+        text.push_str(&parameter_list(&type_parameters, source, "[", "]"));
+    }
+    let parameters = node.child_by_field_name("parameters")?;
+    text.push_str(&parameter_list(&parameters, source, "(", ")"));
+    if let Some(result) = node.child_by_field_name("result") {
+        let result = match result.kind() {
+            "parameter_list" => parameter_list(&result, source, "(", ")"),
+            _ => collapse_whitespace(node_text(&result, source)),
+        };
+        text.push_str(&format!(" {}", result));
+    }
+    Some(text)
+}
+
+// Parameters one by one, which drops comments and the trailing comma
+fn parameter_list(node: &Node, source: &str, open: &str, close: &str) -> String {
+    let parameters: Vec<String> = node
+        .named_children(&mut node.walk())
+        .filter(|parameter| parameter.kind() != "comment")
+        .map(|parameter| collapse_whitespace(node_text(&parameter, source)))
+        .collect();
+    format!("{}{}{}", open, parameters.join(", "), close)
+}
+
+fn render_type(spec: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let type_node = spec.child_by_field_name("type")?;
+    let head = collapse_whitespace(source.get(spec.start_byte()..type_node.start_byte())?);
+
+    let body = match type_node.kind() {
+        "struct_type" => render_struct(&type_node, source, options)?,
+        "interface_type" => render_interface(&type_node, source, options),
+        _ => collapse_whitespace(node_text(&type_node, source)),
+    };
+    Some(format!("type {} {}", head, body))
+}
+
+// `struct { ... }` with the visible fields, one name per line
+fn render_struct(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let fields = node
+        .named_children(&mut node.walk())
+        .find(|child| child.kind() == "field_declaration_list")?;
+
+    let mut lines = Vec::new();
+    let mut hidden = false;
+    for field in fields.named_children(&mut fields.walk()) {
+        if field.kind() != "field_declaration" || field.has_error() {
+            continue;
+        }
+        let field_type = field.child_by_field_name("type")?;
+        let type_text = collapse_whitespace(
+            source.get(
+                field_type.start_byte()
+                    ..field
+                        .child_by_field_name("tag")
+                        .map_or(field.end_byte(), |tag| tag.start_byte()),
+            )?,
+        );
+        let tag = field
+            .child_by_field_name("tag")
+            .filter(|_| options.include_attributes)
+            .map(|tag| format!(" {}", node_text(&tag, source)))
+            .unwrap_or_default();
+        let doc = field_doc(&field, source).filter(|_| options.include_docs);
+
+        let names: Vec<&str> = field
+            .children_by_field_name("name", &mut field.walk())
+            .map(|name| node_text(&name, source))
+            .collect();
+        let rendered = if names.is_empty() {
+            // An embedded field is named after its type
+            let embedded = collapse_whitespace(node_text(&field, source));
+            let type_name = embedded.trim_start_matches('*');
+            let type_name = type_name.rsplit('.').next().unwrap_or(type_name);
+            (visibility(type_name) >= options.visibility).then(|| {
+                let end = field
+                    .child_by_field_name("tag")
+                    .map_or(field.end_byte(), |tag| tag.start_byte());
+                format!(
+                    "{}{}",
+                    collapse_whitespace(source.get(field.start_byte()..end).unwrap_or_default()),
+                    tag
+                )
+            })
+        } else {
+            let visible: Vec<&str> = names
+                .iter()
+                .copied()
+                .filter(|name| visibility(name) >= options.visibility)
+                .collect();
+            hidden |= visible.len() < names.len();
+            (!visible.is_empty()).then(|| format!("{} {}{}", visible.join(", "), type_text, tag))
+        };
+
+        match rendered {
+            Some(rendered) => {
+                if let Some(doc) = doc {
+                    lines.push(format!("    // {}", doc));
+                }
+                lines.push(format!("    {}", rendered));
+            }
+            None => hidden = true,
+        }
+    }
+    if hidden {
+        lines.push("    // unexported fields".to_string());
+    }
+
+    Some(if lines.is_empty() {
+        "struct{}".to_string()
+    } else {
+        format!("struct {{\n{}\n}}", lines.join("\n"))
+    })
+}
+
+// `interface { ... }` with the exported methods and embedded interfaces
+fn render_interface(node: &Node, source: &str, options: &CodemapOptions) -> String {
+    let mut lines = Vec::new();
+    let mut hidden = false;
+    for element in node.named_children(&mut node.walk()) {
+        if element.kind() == "comment" || element.has_error() {
+            continue;
+        }
+        if element.kind() == "method_elem" && !is_visible(&element, source, options) {
+            hidden = true;
+            continue;
+        }
+        lines.push(format!(
+            "    {}",
+            collapse_whitespace(node_text(&element, source))
+        ));
+    }
+    if hidden {
+        lines.push("    // unexported methods".to_string());
+    }
+
+    if lines.is_empty() {
+        "interface{}".to_string()
+    } else {
+        format!("interface {{\n{}\n}}", lines.join("\n"))
+    }
+}
+
+// A `const` or `var` declaration with only its visible names
+fn render_values(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let keyword = if node.kind() == "const_declaration" {
+        "const"
+    } else {
+        "var"
+    };
+    let specs = value_specs(node);
+
+    let rendered: Vec<String> = specs
+        .iter()
+        .filter_map(|spec| render_value_spec(spec, source, options))
+        .collect();
+
+    // A grouped declaration stays grouped, `iota` depends on it
+    let is_grouped = node_text(node, source)
+        .trim_start_matches(keyword)
+        .trim_start()
+        .starts_with('(');
+    match rendered.as_slice() {
+        [] => None,
+        [single] if !is_grouped => Some(format!("{} {}", keyword, single)),
+        _ => Some(format!(
+            "{} (\n{}\n)",
+            keyword,
+            rendered
+                .iter()
+                .map(|spec| format!("    {}", spec))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+// A spec with only its visible names, and their values when there is one per
+// name. `None` when no name is visible
+fn render_value_spec(spec: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let names: Vec<&str> = spec
+        .children_by_field_name("name", &mut spec.walk())
+        .map(|name| node_text(&name, source))
+        .collect();
+    let visible: Vec<usize> = (0..names.len())
+        .filter(|&index| visibility(names[index]) >= options.visibility)
+        .collect();
+    if visible.is_empty() {
+        return None;
+    }
+
+    let text = node_text(spec, source);
+    let value = spec.child_by_field_name("value");
+    if visible.len() == names.len() && !text.contains('\n') {
+        return Some(text.to_string());
+    }
+
+    let mut rendered = visible
+        .iter()
+        .map(|&index| names[index])
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Some(value_type) = spec.child_by_field_name("type") {
+        rendered.push_str(&format!(
+            " {}",
+            collapse_whitespace(node_text(&value_type, source))
+        ));
+    }
+    let Some(value) = value else {
+        return Some(rendered);
+    };
+    let values: Vec<Node> = value.named_children(&mut value.walk()).collect();
+    let visible_values: Vec<&str> = visible
+        .iter()
+        .filter_map(|&index| values.get(index))
+        .map(|value| node_text(value, source))
+        .collect();
+    // Values spanning lines, or shared by all names like `a, b = f()`, are
+    // left out
+    if values.len() != names.len() || visible_values.iter().any(|value| value.contains('\n')) {
+        rendered.push_str(" = ...");
+    } else {
+        rendered.push_str(&format!(" = {}", visible_values.join(", ")));
+    }
+    Some(rendered)
+}
+
+// The specs of a `const` or `var` declaration; grouped vars are in a list
+fn value_specs<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    let mut specs = Vec::new();
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "const_spec" | "var_spec" => specs.push(child),
+            "var_spec_list" => specs.extend(value_specs(&child)),
+            _ => {}
+        }
+    }
+    specs
+}
+
+fn value_names(node: &Node, source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for spec in value_specs(node) {
+        for name in spec.children_by_field_name("name", &mut spec.walk()) {
+            names.push(node_text(&name, source).to_string());
+        }
+    }
+    names
+}
+
+// Precede `text` with the first line of the comment right above `node`
+fn with_doc(node: &Node, source: &str, options: &CodemapOptions, text: String) -> String {
+    match field_doc(node, source).filter(|_| options.include_docs) {
+        Some(doc) => format!("// {}\n{}", doc, text),
+        None => text,
+    }
+}
+
+// The first line of the `//` comment block ending right above `node`
+fn field_doc(node: &Node, source: &str) -> Option<String> {
+    let mut comment = node.prev_named_sibling()?;
+    let mut row = node.start_position().row;
+    if comment.kind() != "comment" || comment.end_position().row + 1 != row {
+        return None;
+    }
+    // Walk up to the first line of the block
+    loop {
+        row = comment.start_position().row;
+        match comment.prev_named_sibling() {
+            Some(previous)
+                if previous.kind() == "comment" && previous.end_position().row + 1 == row =>
+            {
+                comment = previous;
+            }
+            _ => break,
+        }
+    }
+    let text = node_text(&comment, source);
+    let text = text.strip_prefix("//").unwrap_or(text);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
@@ -0,0 +1,285 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// Java, mapped like its Javadoc: `public` and `protected` declarations are
+/// public, package-private ones are crate-visible and `private` ones private.
+pub struct Java;
+
+impl LanguageBackend for Java {
+    fn name(&self) -> &'static str {
+        "java"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["java"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_java::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let mut items = Vec::new();
+        for node in top_level_items(&tree.root_node()) {
+            let kind = match node.kind() {
+                "package_declaration" => ItemKind::Module,
+                "import_declaration"
+                    if options.include_uses && options.visibility == Visibility::Private =>
+                {
+                    ItemKind::UseDeclaration
+                }
+                "class_declaration" | "record_declaration" => ItemKind::Class,
+                "interface_declaration" | "annotation_type_declaration" => ItemKind::Interface,
+                "enum_declaration" => ItemKind::Enum,
+                _ => continue,
+            };
+            let is_type = !matches!(kind, ItemKind::Module | ItemKind::UseDeclaration);
+            if !header_is_clean(&node)
+                || (is_type && visibility(&node, source, false) < options.visibility)
+            {
+                continue;
+            }
+
+            let text = match memo.as_deref_mut() {
+                _ if !is_type => Some(collapse_whitespace(node_text(&node, source))),
+                Some(memo) => {
+                    memo.get_or_render(&node, source, || render_type(&node, source, options))
+                }
+                None => render_type(&node, source, options),
+            };
+            // Javadoc isn't part of the declaration's text, so it is added
+            // after the memo
+            if let Some(text) = text {
+                items.push(Item {
+                    kind,
+                    name: node
+                        .child_by_field_name("name")
+                        .map(|name| node_text(&name, source).to_string()),
+                    text: with_doc(&node, source, options, text),
+                    lines: Some(Lines::of(&node)),
+                    file: None,
+                });
+            }
+        }
+        items
+    }
+}
+
+// Members of interfaces and annotations are public unless said otherwise
+fn visibility(node: &Node, source: &str, in_interface: bool) -> Visibility {
+    let modifiers = modifier_keywords(node, source);
+    if modifiers.contains(&"private") {
+        Visibility::Private
+    } else if modifiers.contains(&"public") || modifiers.contains(&"protected") || in_interface {
+        Visibility::Public
+    } else {
+        Visibility::Crate
+    }
+}
+
+fn modifiers<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    node.named_children(&mut node.walk())
+        .find(|child| child.kind() == "modifiers")
+}
+
+fn modifier_keywords<'a>(node: &Node, source: &'a str) -> Vec<&'a str> {
+    let Some(modifiers) = modifiers(node) else {
+        return Vec::new();
+    };
+    modifiers
+        .children(&mut modifiers.walk())
+        .filter(|modifier| !modifier.kind().ends_with("annotation"))
+        .map(|modifier| node_text(&modifier, source))
+        .collect()
+}
+
+// A declaration up to `end`: its annotations on their own lines when
+// attributes are included, then its keywords and the rest of its header
+fn declaration(node: &Node, source: &str, options: &CodemapOptions, end: usize) -> Option<String> {
+    let mut lines = Vec::new();
+    let start = match modifiers(node) {
+        Some(modifiers) => {
+            if options.include_attributes {
+                for annotation in modifiers
+                    .named_children(&mut modifiers.walk())
+                    .filter(|modifier| modifier.kind().ends_with("annotation"))
+                {
+                    lines.push(collapse_whitespace(node_text(&annotation, source)));
+                }
+            }
+            modifiers.end_byte()
+        }
+        None => node.start_byte(),
+    };
+
+    let mut header = modifier_keywords(node, source).join(" ");
+    let rest = collapse_whitespace(source.get(start..end)?);
+    if !header.is_empty() && !rest.is_empty() {
+        header.push(' ');
+    }
+    header.push_str(&rest);
+    lines.push(header);
+    Some(lines.join("\n"))
+}
+
+// A class, interface, enum, record or annotation with its visible members
+fn render_type(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let header = declaration(node, source, options, body.start_byte())?;
+
+    let mut members = Vec::new();
+    let mut body_members: Vec<Node> = body.named_children(&mut body.walk()).collect();
+    if body.kind() == "enum_body" {
+        let constants: Vec<String> = body_members
+            .iter()
+            .filter(|member| member.kind() == "enum_constant")
+            .filter_map(|constant| {
+                let end = constant
+                    .child_by_field_name("body")
+                    .map_or(constant.end_byte(), |body| body.start_byte());
+                let text = collapse_whitespace(source.get(constant.start_byte()..end)?);
+                Some(with_doc(constant, source, options, text))
+            })
+            .collect();
+        // Constructors and methods follow the constants
+        body_members = body_members
+            .iter()
+            .filter(|member| member.kind() == "enum_body_declarations")
+            .flat_map(|declarations| {
+                declarations
+                    .named_children(&mut declarations.walk())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let has_members = !body_members.is_empty();
+        let count = constants.len();
+        for (index, constant) in constants.into_iter().enumerate() {
+            let separator = match index + 1 == count {
+                true if has_members => ";",
+                true => "",
+                false => ",",
+            };
+            members.push(format!("{}{}", indent(&constant), separator));
+        }
+    }
+
+    let in_interface = matches!(body.kind(), "interface_body" | "annotation_type_body");
+    for member in body_members {
+        // Drop members that didn't parse rather than show half of them
+        if member.has_error() || visibility(&member, source, in_interface) < options.visibility {
+            continue;
+        }
+        let rendered = match member.kind() {
+            "class_declaration"
+            | "record_declaration"
+            | "interface_declaration"
+            | "annotation_type_declaration"
+            | "enum_declaration" => render_type(&member, source, options),
+            "method_declaration" | "constructor_declaration" => {
+                let end = member
+                    .child_by_field_name("body")
+                    .map_or(member.end_byte(), |body| body.start_byte());
+                declaration(&member, source, options, end)
+                    .map(|signature| format!("{};", signature.trim_end_matches(';')))
+            }
+            "field_declaration" | "constant_declaration" => {
+                render_field(&member, source, options, in_interface)
+            }
+            "annotation_type_element_declaration" => {
+                declaration(&member, source, options, member.end_byte())
+            }
+            _ => None,
+        };
+        if let Some(rendered) = rendered {
+            members.push(indent(&with_doc(&member, source, options, rendered)));
+        }
+    }
+
+    Some(if members.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, members.join("\n"))
+    })
+}
+
+// A field with its type and names; only the values of constants are shown,
+// as long as they fit on a line
+fn render_field(
+    field: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    in_interface: bool,
+) -> Option<String> {
+    let field_type = field.child_by_field_name("type")?;
+    let head = declaration(field, source, options, field_type.end_byte())?;
+    let keywords = modifier_keywords(field, source);
+    let is_constant = in_interface || (keywords.contains(&"static") && keywords.contains(&"final"));
+
+    let declarators: Vec<String> = field
+        .children_by_field_name("declarator", &mut field.walk())
+        .map(|declarator| {
+            let value = declarator
+                .child_by_field_name("value")
+                .filter(|value| is_constant && !node_text(value, source).contains('\n'));
+            let end = declarator
+                .child_by_field_name("value")
+                .map_or(declarator.end_byte(), |value| value.start_byte());
+            let name = source
+                .get(declarator.start_byte()..end)
+                .unwrap_or_default()
+                .trim_end()
+                .trim_end_matches('=');
+            match value {
+                Some(value) => format!(
+                    "{} = {}",
+                    collapse_whitespace(name),
+                    node_text(&value, source)
+                ),
+                None => collapse_whitespace(name),
+            }
+        })
+        .collect();
+    Some(format!("{} {};", head, declarators.join(", ")))
+}
+
+// Indent every line of a member by one level
+fn indent(rendered: &str) -> String {
+    rendered
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Precede `rendered` with the summary of the Javadoc right above `node`
+fn with_doc(node: &Node, source: &str, options: &CodemapOptions, rendered: String) -> String {
+    match doc_summary(node, source).filter(|_| options.include_docs) {
+        Some(doc) => format!("/** {} */\n{}", doc, rendered),
+        None => rendered,
+    }
+}
+
+// The first line of a `/** ... */` comment ending on the line before `node`
+fn doc_summary(node: &Node, source: &str) -> Option<String> {
+    let comment = node.prev_named_sibling()?;
+    if comment.kind() != "block_comment"
+        || comment.end_position().row + 1 < node.start_position().row
+    {
+        return None;
+    }
+    let text = node_text(&comment, source).strip_prefix("/**")?;
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
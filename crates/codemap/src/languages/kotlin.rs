@@ -0,0 +1,310 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// Kotlin sources: classes, interfaces and objects with their visible
+/// members, and top-level functions, properties and type aliases, with their
+/// annotations, generics and KDoc summaries.
+///
+/// Declarations are public unless marked `internal`, which is crate
+/// visibility, or `private`. Bodies are left out, and so are initializers
+/// unless they are all there is to tell a property's type.
+pub struct Kotlin;
+
+impl LanguageBackend for Kotlin {
+    fn name(&self) -> &'static str {
+        "kotlin"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["kt"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_kotlin_ng::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let mut items = Vec::new();
+        for node in top_level_items(&tree.root_node()) {
+            let text = match node.kind() {
+                "package_header" => package_or_import(&node, source),
+                "import" if options.include_uses && options.visibility == Visibility::Private => {
+                    package_or_import(&node, source)
+                }
+                _ => {
+                    if !is_declaration(&node) || visibility(&node, source) < options.visibility {
+                        continue;
+                    }
+                    match memo.as_deref_mut() {
+                        Some(memo) => memo.get_or_render(&node, source, || {
+                            render_declaration(&node, source, options)
+                        }),
+                        None => render_declaration(&node, source, options),
+                    }
+                }
+            };
+            // KDoc isn't part of the declaration's text, so it is added after
+            // the memo
+            if let Some(text) = text {
+                items.push(Item {
+                    kind: item_kind(&node, source),
+                    name: declared_name(&node, source),
+                    text: with_doc(&node, source, options, text),
+                    lines: Some(Lines::of(&node)),
+                    file: None,
+                });
+            }
+        }
+        items
+    }
+}
+
+fn is_declaration(node: &Node) -> bool {
+    matches!(
+        node.kind(),
+        "class_declaration"
+            | "object_declaration"
+            | "companion_object"
+            | "function_declaration"
+            | "property_declaration"
+            | "secondary_constructor"
+            | "type_alias"
+    )
+}
+
+fn item_kind(node: &Node, source: &str) -> ItemKind {
+    match node.kind() {
+        "package_header" => ItemKind::Module,
+        "import" => ItemKind::UseDeclaration,
+        "class_declaration" if has_token(node, "interface") => ItemKind::Interface,
+        "class_declaration" if modifier_keywords(node, source).contains(&"enum") => ItemKind::Enum,
+        "class_declaration" | "object_declaration" | "companion_object" => ItemKind::Class,
+        "function_declaration" | "secondary_constructor" => ItemKind::Function,
+        "type_alias" => ItemKind::TypeAlias,
+        _ => ItemKind::Const,
+    }
+}
+
+fn declared_name(node: &Node, source: &str) -> Option<String> {
+    let name = match node.kind() {
+        "secondary_constructor" => return Some("constructor".to_string()),
+        "type_alias" => node.child_by_field_name("type"),
+        "property_declaration" => child_of_kind(node, "variable_declaration")
+            .and_then(|variable| child_of_kind(&variable, "identifier")),
+        _ => node.child_by_field_name("name"),
+    }?;
+    Some(node_text(&name, source).to_string())
+}
+
+// Declarations are public unless they say otherwise; `protected` members are
+// part of what subclasses see, so they are public too
+fn visibility(node: &Node, source: &str) -> Visibility {
+    let modifiers = modifier_keywords(node, source);
+    if modifiers.contains(&"private") {
+        Visibility::Private
+    } else if modifiers.contains(&"internal") {
+        Visibility::Crate
+    } else {
+        Visibility::Public
+    }
+}
+
+// `package a.b` or `import a.b.c`, without a closing semicolon
+fn package_or_import(node: &Node, source: &str) -> Option<String> {
+    if node.has_error() {
+        return None;
+    }
+    let text = collapse_whitespace(node_text(node, source));
+    Some(text.trim_end_matches(';').trim_end().to_string())
+}
+
+fn child_of_kind<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    node.named_children(&mut node.walk())
+        .find(|child| child.kind() == kind)
+}
+
+fn has_token(node: &Node, token: &str) -> bool {
+    node.children(&mut node.walk())
+        .any(|child| child.kind() == token)
+}
+
+fn modifier_keywords<'a>(node: &Node, source: &'a str) -> Vec<&'a str> {
+    let Some(modifiers) = child_of_kind(node, "modifiers") else {
+        return Vec::new();
+    };
+    modifiers
+        .named_children(&mut modifiers.walk())
+        .filter(|modifier| modifier.kind() != "annotation")
+        .map(|modifier| node_text(&modifier, source))
+        .collect()
+}
+
+// Where the header of a declaration ends: at its body, its initializer or
+// its accessors. A type alias is all header
+fn header_end(node: &Node) -> usize {
+    if node.kind() == "type_alias" {
+        return node.end_byte();
+    }
+    let mut cursor = node.walk();
+    let end = node.children(&mut cursor).find(|child| {
+        matches!(
+            child.kind(),
+            "class_body"
+                | "enum_class_body"
+                | "function_body"
+                | "block"
+                | "="
+                | "getter"
+                | "setter"
+                | "property_delegate"
+        )
+    });
+    end.map_or(node.end_byte(), |end| end.start_byte())
+}
+
+// A declaration: its annotations on their own lines when attributes are
+// included, then its modifiers, its header and, for classes, the visible
+// members of its body. `None` when the header doesn't parse
+fn render_declaration(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let end = header_end(node);
+    let header_clean = node
+        .children(&mut node.walk())
+        .take_while(|child| child.start_byte() < end)
+        .all(|child| !child.has_error());
+    if !header_clean {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    let start = match child_of_kind(node, "modifiers") {
+        Some(modifiers) => {
+            if options.include_attributes {
+                for annotation in modifiers
+                    .named_children(&mut modifiers.walk())
+                    .filter(|modifier| modifier.kind() == "annotation")
+                {
+                    lines.push(collapse_whitespace(node_text(&annotation, source)));
+                }
+            }
+            modifiers.end_byte()
+        }
+        None => node.start_byte(),
+    };
+    let mut signature = modifier_keywords(node, source).join(" ");
+    let header = collapse_whitespace(source.get(start..end)?);
+    if !signature.is_empty() && !header.is_empty() {
+        signature.push(' ');
+    }
+    signature.push_str(&header);
+    if let Some(value) = shown_value(node, source) {
+        signature = format!("{} = {}", signature, value);
+    }
+
+    let body = child_of_kind(node, "class_body").or_else(|| child_of_kind(node, "enum_class_body"));
+    let members = body.map_or_else(Vec::new, |body| members(&body, source, options));
+    if members.is_empty() {
+        lines.push(signature);
+    } else {
+        lines.push(format!("{} {{", signature));
+        lines.extend(members);
+        lines.push("}".to_string());
+    }
+    Some(lines.join("\n"))
+}
+
+// The value a property is initialized with, for constants and properties
+// without a declared type, where the value is all there is to tell it, as
+// long as it fits on a line
+fn shown_value(node: &Node, source: &str) -> Option<String> {
+    if node.kind() != "property_declaration" {
+        return None;
+    }
+    let typed = child_of_kind(node, "variable_declaration")
+        .is_some_and(|variable| has_token(&variable, ":"));
+    if typed && !modifier_keywords(node, source).contains(&"const") {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let mut children = node.children(&mut cursor);
+    let equals = children.find(|child| child.kind() == "=")?;
+    let value = children.find(|child| child.is_named())?;
+    (equals.start_position().row == value.end_position().row)
+        .then(|| collapse_whitespace(node_text(&value, source)))
+}
+
+// The visible members of a class body, each indented; the entries of an
+// enum class come first, separated by commas
+fn members(body: &Node, source: &str, options: &CodemapOptions) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut members = Vec::new();
+    for member in body.named_children(&mut body.walk()) {
+        if member.kind() == "enum_entry" {
+            let end = child_of_kind(&member, "class_body")
+                .map_or(member.end_byte(), |entry_body| entry_body.start_byte());
+            let entry = collapse_whitespace(&source[member.start_byte()..end]);
+            entries.push(with_doc(&member, source, options, entry));
+        } else if is_declaration(&member)
+            && visibility(&member, source) >= options.visibility
+            && let Some(rendered) = render_declaration(&member, source, options)
+        {
+            members.push(indent(&with_doc(&member, source, options, rendered)));
+        }
+    }
+
+    let count = entries.len();
+    let mut lines: Vec<String> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let separator = match index + 1 == count {
+                true if !members.is_empty() => ";",
+                true => "",
+                false => ",",
+            };
+            indent(&format!("{}{}", entry, separator))
+        })
+        .collect();
+    lines.extend(members);
+    lines
+}
+
+fn with_doc(node: &Node, source: &str, options: &CodemapOptions, rendered: String) -> String {
+    match doc_summary(node, source).filter(|_| options.include_docs) {
+        Some(doc) => format!("/** {} */\n{}", doc, rendered),
+        None => rendered,
+    }
+}
+
+// The first line of a `/** ... */` comment ending on the line before `node`
+fn doc_summary(node: &Node, source: &str) -> Option<String> {
+    let comment = node.prev_named_sibling()?;
+    if comment.kind() != "block_comment"
+        || comment.end_position().row + 1 != node.start_position().row
+    {
+        return None;
+    }
+    let text = node_text(&comment, source).strip_prefix("/**")?;
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+// Indent every line of a member by one level
+fn indent(rendered: &str) -> String {
+    rendered
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
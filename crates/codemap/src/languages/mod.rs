@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use tree_sitter::{Language, Node, Tree};
+
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, FileMap, Item};
+
+mod c;
+mod cargo;
+mod go;
+mod java;
+mod kotlin;
+mod openapi;
+mod protobuf;
+mod python;
+mod rust;
+mod sql;
+mod typescript;
+
+pub use c::{C, Cpp, CppHeader};
+pub use cargo::CargoManifest;
+pub(crate) use cargo::{discover_crates, mapped_packages, package_of_manifest, path_dependencies};
+pub use go::Go;
+pub(crate) use go::group_packages;
+pub use java::Java;
+pub use kotlin::Kotlin;
+pub use openapi::OpenApi;
+pub use protobuf::Protobuf;
+pub use python::Python;
+pub use rust::Rust;
+pub use sql::Sql;
+pub use typescript::{JavaScript, Tsx, TypeScript};
+
+/// A language codemaps can be made of: which files it covers, its
+/// tree-sitter grammar and how its items are extracted.
+///
+/// Parsing, syntax error diagnostics, signature shortening and line number
+/// comments, written with [`LanguageBackend::comment_prefix`], are shared by
+/// every language; a backend only turns a tree into items. Backends for
+/// formats without a tree-sitter grammar parse files themselves in
+/// [`LanguageBackend::map_source`] instead.
+pub trait LanguageBackend: Send + Sync {
+    /// Short lowercase name, e.g. `rust`
+    fn name(&self) -> &'static str;
+
+    /// Language of the Markdown code blocks of this backend's files; its
+    /// name by default
+    fn fence(&self) -> &'static str {
+        self.name()
+    }
+
+    /// What starts a line comment in this language, for the comments added
+    /// above items; `//` by default
+    fn comment_prefix(&self) -> &'static str {
+        "//"
+    }
+
+    /// Extensions of the files in this language, without the dot
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Names of the files in this language whatever their extension, for
+    /// formats that share theirs with others, e.g. `Cargo.toml`
+    fn file_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The tree-sitter grammar files are parsed with, `None` for backends
+    /// that parse files themselves
+    fn language(&self) -> Option<Language>;
+
+    /// Whether a file with one of the extensions is in this language at all,
+    /// for extensions other formats use too (e.g. `.yaml`). Files that aren't
+    /// are skipped, without items or diagnostics
+    fn accepts(&self, source: &str) -> bool {
+        let _ = source;
+        true
+    }
+
+    /// The items of a parsed file, in source order, with
+    /// [`Item::lines`] set to where they are declared.
+    ///
+    /// `memo` is only given when a file is mapped again after an edit; it
+    /// keeps the rendering of nodes whose text didn't change, see
+    /// [`ItemMemo::get_or_render`].
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let _ = (tree, source, options, memo);
+        Vec::new()
+    }
+
+    /// The items of a file and the problems found parsing it, for backends
+    /// without a grammar. Like [`LanguageBackend::map_items`], items are in
+    /// source order with their lines set.
+    fn map_source(&self, source: &str, options: &CodemapOptions) -> FileMap {
+        let _ = (source, options);
+        FileMap::default()
+    }
+}
+
+/// The language backends files are mapped with, keyed by file name or
+/// extension.
+///
+/// The default registry has every built-in language.
+#[derive(Clone)]
+pub struct LanguageRegistry {
+    by_extension: HashMap<String, Arc<dyn LanguageBackend>>,
+    by_file_name: HashMap<String, Arc<dyn LanguageBackend>>,
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        let mut registry = LanguageRegistry::new();
+        registry.register(Arc::new(C));
+        registry.register(Arc::new(CargoManifest));
+        registry.register(Arc::new(Cpp));
+        registry.register(Arc::new(CppHeader));
+        registry.register(Arc::new(Go));
+        registry.register(Arc::new(Java));
+        registry.register(Arc::new(JavaScript));
+        registry.register(Arc::new(Kotlin));
+        registry.register(Arc::new(OpenApi));
+        registry.register(Arc::new(Protobuf));
+        registry.register(Arc::new(Python));
+        registry.register(Arc::new(Rust));
+        registry.register(Arc::new(Sql));
+        registry.register(Arc::new(Tsx));
+        registry.register(Arc::new(TypeScript));
+        registry
+    }
+}
+
+impl fmt::Debug for LanguageRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.backends().map(|backend| backend.name()))
+            .finish()
+    }
+}
+
+impl LanguageRegistry {
+    /// A registry without any language.
+    pub fn new() -> Self {
+        LanguageRegistry {
+            by_extension: HashMap::new(),
+            by_file_name: HashMap::new(),
+        }
+    }
+
+    /// Map the files with `backend`'s extensions and file names with it,
+    /// replacing the backend registered for them before.
+    pub fn register(&mut self, backend: Arc<dyn LanguageBackend>) {
+        for extension in backend.extensions() {
+            self.by_extension
+                .insert(extension.to_string(), backend.clone());
+        }
+        for file_name in backend.file_names() {
+            self.by_file_name
+                .insert(file_name.to_string(), backend.clone());
+        }
+    }
+
+    pub fn for_extension(&self, extension: &str) -> Option<&Arc<dyn LanguageBackend>> {
+        self.by_extension.get(extension)
+    }
+
+    pub fn for_file_name(&self, file_name: &str) -> Option<&Arc<dyn LanguageBackend>> {
+        self.by_file_name.get(file_name)
+    }
+
+    /// The backend for a file, by its name, or else its extension.
+    pub fn for_path(&self, path: &Path) -> Option<&Arc<dyn LanguageBackend>> {
+        let by_file_name = path
+            .file_name()
+            .and_then(|file_name| self.for_file_name(file_name.to_str()?));
+        by_file_name.or_else(|| self.for_extension(path.extension()?.to_str()?))
+    }
+
+    /// Every registered backend once, sorted by name.
+    pub fn backends(&self) -> impl Iterator<Item = &Arc<dyn LanguageBackend>> {
+        let mut backends: Vec<_> = self
+            .by_extension
+            .values()
+            .chain(self.by_file_name.values())
+            .collect();
+        backends.sort_by_key(|backend| backend.name());
+        backends.dedup_by(|a, b| Arc::ptr_eq(a, b));
+        backends.into_iter()
+    }
+
+    /// Every extension a backend is registered for, sorted.
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        let mut extensions: Vec<_> = self.by_extension.keys().map(String::as_str).collect();
+        extensions.sort();
+        extensions.into_iter()
+    }
+
+    /// Every file name a backend is registered for, sorted.
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        let mut file_names: Vec<_> = self.by_file_name.keys().map(String::as_str).collect();
+        file_names.sort();
+        file_names.into_iter()
+    }
+}
+
+// Top-level items of a file, flattening ERROR nodes so that complete items
+// swallowed by a syntax error are still mapped
+pub(crate) fn top_level_items<'a>(root_node: &Node<'a>) -> Vec<Node<'a>> {
+    let mut items = Vec::new();
+    let mut cursor = root_node.walk();
+    for child in root_node.children(&mut cursor) {
+        if child.is_error() {
+            items.extend(top_level_items(&child));
+        } else {
+            items.push(child);
+        }
+    }
+    items
+}
+
+// Check that everything but the body of an item parsed cleanly. Bodies are
+// handled by the backends, which drop broken members and never
+// render function bodies anyway
+pub(crate) fn header_is_clean(node: &Node) -> bool {
+    let body = node.child_by_field_name("body");
+    node.children(&mut node.walk())
+        .all(|child| Some(child) == body || !child.has_error())
+}
+
+// Collapse runs of whitespace (including newlines) into single spaces
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,457 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::LanguageBackend;
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, node_text};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// OpenAPI 3 and Swagger 2 documents written in YAML: one item per
+/// operation with its parameters, request body and responses, and one per
+/// schema with its properties.
+///
+/// YAML files without a top-level `openapi` or `swagger` key aren't OpenAPI
+/// documents and are skipped.
+pub struct OpenApi;
+
+impl LanguageBackend for OpenApi {
+    fn name(&self) -> &'static str {
+        "openapi"
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        "#"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["yaml", "yml"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_yaml::LANGUAGE.into())
+    }
+
+    // Checked on the text, other YAML (e.g. templates) may not even parse
+    fn accepts(&self, source: &str) -> bool {
+        source.lines().any(|line| {
+            let key = line.trim_start_matches(['"', '\'']);
+            line.starts_with(|c: char| !c.is_whitespace())
+                && ["openapi", "swagger"].iter().any(|name| {
+                    key.strip_prefix(name)
+                        .is_some_and(|rest| rest.trim_start_matches(['"', '\'']).starts_with(':'))
+                })
+        })
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let Some(document) = first_document(&tree.root_node()) else {
+            return Vec::new();
+        };
+        let version = get(&document, "openapi", source)
+            .or_else(|| get(&document, "swagger", source))
+            .and_then(|version| scalar(&version, source));
+        let Some(version) = version else {
+            return Vec::new();
+        };
+
+        let mut items = Vec::new();
+        let info = get(&document, "info", source);
+        let title = info
+            .and_then(|info| get(&info, "title", source))
+            .and_then(|title| scalar(&title, source));
+        let api_version = info
+            .and_then(|info| get(&info, "version", source))
+            .and_then(|api_version| scalar(&api_version, source));
+        // `# Pets 1.0 (OpenAPI 3.0.3)`
+        let named = [title.clone(), api_version]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let header = match named.as_str() {
+            "" => format!("# OpenAPI {}", version),
+            named => format!("# {} (OpenAPI {})", named, version),
+        };
+        items.push(Item {
+            kind: ItemKind::Module,
+            name: title,
+            text: header,
+            lines: info.and_then(|info| info.parent()).map(|pair| lines(&pair)),
+            file: None,
+        });
+
+        for (path, path_item) in get(&document, "paths", source)
+            .map(|paths| mapping(&paths, source))
+            .unwrap_or_default()
+        {
+            // Parameters of the path apply to all of its operations
+            let shared = get(&path_item, "parameters", source);
+            let shared_text = shared.map_or("", |shared| node_text(&shared, source));
+            for (method, operation) in mapping(&path_item, source) {
+                if !METHODS.contains(&method.as_str()) {
+                    continue;
+                }
+                let render = || {
+                    Some(render_operation(
+                        &method, &path, shared, &operation, source, options,
+                    ))
+                };
+                // Identical operations of other paths render differently
+                let text = match memo.as_deref_mut() {
+                    Some(memo) => {
+                        let key = format!(
+                            "{} {}\n{}\n{}",
+                            method,
+                            path,
+                            shared_text,
+                            node_text(&operation, source)
+                        );
+                        memo.get_or_render_keyed(key, render)
+                    }
+                    None => render(),
+                };
+                let name = get(&operation, "operationId", source)
+                    .and_then(|id| scalar(&id, source))
+                    .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+                if let Some(text) = text {
+                    items.push(Item {
+                        kind: ItemKind::Function,
+                        name: Some(name),
+                        text,
+                        lines: operation.parent().map(|pair| lines(&pair)),
+                        file: None,
+                    });
+                }
+            }
+        }
+
+        let schemas = get(&document, "components", source)
+            .and_then(|components| get(&components, "schemas", source))
+            .or_else(|| get(&document, "definitions", source));
+        for (name, schema) in schemas
+            .map(|schemas| mapping(&schemas, source))
+            .unwrap_or_default()
+        {
+            let render = || Some(render_schema(&name, &schema, source, options));
+            let text = match memo.as_deref_mut() {
+                Some(memo) => {
+                    let key = format!("schema {}\n{}", name, node_text(&schema, source));
+                    memo.get_or_render_keyed(key, render)
+                }
+                None => render(),
+            };
+            if let Some(text) = text {
+                items.push(Item {
+                    kind: ItemKind::Struct,
+                    name: Some(name),
+                    text,
+                    lines: schema.parent().map(|pair| lines(&pair)),
+                    file: None,
+                });
+            }
+        }
+
+        items
+    }
+}
+
+// `GET /pets/{id}(id: integer, verbose?: boolean) -> 200: Pet, 404`, after
+// the summary of the operation
+fn render_operation(
+    method: &str,
+    path: &str,
+    shared: Option<Node>,
+    operation: &Node,
+    source: &str,
+    options: &CodemapOptions,
+) -> String {
+    let mut parameters = Vec::new();
+    let mut body = get(operation, "requestBody", source)
+        .and_then(|body| content_schema(&body, source))
+        .map(|schema| schema_type(&schema, source));
+    let all_parameters = shared
+        .into_iter()
+        .chain(get(operation, "parameters", source))
+        .flat_map(|parameters| sequence(&parameters));
+    for parameter in all_parameters {
+        let Some(name) = get(&parameter, "name", source).and_then(|name| scalar(&name, source))
+        else {
+            // A `$ref` to a shared parameter
+            if let Some(reference) = get(&parameter, "$ref", source) {
+                parameters.push(reference_name(&reference, source));
+            }
+            continue;
+        };
+        let location = get(&parameter, "in", source).and_then(|location| scalar(&location, source));
+        let schema = get(&parameter, "schema", source);
+        // Swagger 2 sends bodies as parameters
+        if location.as_deref() == Some("body") {
+            body = schema.map(|schema| schema_type(&schema, source));
+            continue;
+        }
+        let parameter_type = match schema {
+            Some(schema) => schema_type(&schema, source),
+            None => schema_type(&parameter, source),
+        };
+        let is_required = get(&parameter, "required", source)
+            .and_then(|required| scalar(&required, source))
+            .is_some_and(|required| required == "true");
+        let optional = if is_required { "" } else { "?" };
+        parameters.push(format!("{}{}: {}", name, optional, parameter_type));
+    }
+    if let Some(body) = body {
+        parameters.push(format!("body: {}", body));
+    }
+
+    let responses: Vec<String> = get(operation, "responses", source)
+        .map(|responses| mapping(&responses, source))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(status, response)| {
+            let schema =
+                content_schema(&response, source).or_else(|| get(&response, "schema", source));
+            match schema {
+                Some(schema) => format!("{}: {}", status, schema_type(&schema, source)),
+                None => status,
+            }
+        })
+        .collect();
+
+    let mut text = format!(
+        "{} {}({})",
+        method.to_uppercase(),
+        path,
+        parameters.join(", ")
+    );
+    if !responses.is_empty() {
+        text = format!("{} -> {}", text, responses.join(", "));
+    }
+    let summary = get(operation, "summary", source)
+        .or_else(|| get(operation, "description", source))
+        .and_then(|summary| scalar(&summary, source));
+    match summary.filter(|_| options.include_docs) {
+        Some(summary) => format!("# {}\n{}", first_line(&summary), text),
+        None => text,
+    }
+}
+
+// `schema Pet { id: integer, tag?: string }` with a property per line, or
+// `schema Id = string` for schemas that aren't objects
+fn render_schema(name: &str, schema: &Node, source: &str, options: &CodemapOptions) -> String {
+    let required: Vec<String> = get(schema, "required", source)
+        .map(|required| sequence(&required))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| scalar(name, source))
+        .collect();
+    let properties = get(schema, "properties", source)
+        .map(|properties| mapping(&properties, source))
+        .unwrap_or_default();
+
+    let text = if properties.is_empty() {
+        format!("schema {} = {}", name, schema_type(schema, source))
+    } else {
+        let mut lines = vec![format!("schema {} {{", name)];
+        for (property, property_schema) in properties {
+            if options.include_docs
+                && let Some(description) = get(&property_schema, "description", source)
+                    .and_then(|description| scalar(&description, source))
+            {
+                lines.push(format!("    # {}", first_line(&description)));
+            }
+            let optional = if required.contains(&property) {
+                ""
+            } else {
+                "?"
+            };
+            lines.push(format!(
+                "    {}{}: {}",
+                property,
+                optional,
+                schema_type(&property_schema, source)
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    };
+
+    let description =
+        get(schema, "description", source).and_then(|description| scalar(&description, source));
+    match description.filter(|_| options.include_docs) {
+        Some(description) => format!("# {}\n{}", first_line(&description), text),
+        None => text,
+    }
+}
+
+// A short name for the type a schema describes: the name of a `$ref`,
+// `Item[]` for arrays, unions for `oneOf` and the enum values
+fn schema_type(schema: &Node, source: &str) -> String {
+    if let Some(reference) = get(schema, "$ref", source) {
+        return reference_name(&reference, source);
+    }
+    for (key, separator) in [("allOf", " & "), ("oneOf", " | "), ("anyOf", " | ")] {
+        if let Some(schemas) = get(schema, key, source) {
+            let types: Vec<String> = sequence(&schemas)
+                .iter()
+                .map(|schema| schema_type(schema, source))
+                .collect();
+            return types.join(separator);
+        }
+    }
+    if let Some(values) = get(schema, "enum", source) {
+        let values: Vec<String> = sequence(&values)
+            .iter()
+            .filter_map(|value| scalar(value, source))
+            .map(|value| format!("{:?}", value))
+            .collect();
+        return values.join(" | ");
+    }
+
+    let schema_type =
+        get(schema, "type", source).and_then(|schema_type| scalar(&schema_type, source));
+    match schema_type.as_deref() {
+        Some("array") => {
+            let items = get(schema, "items", source)
+                .map_or_else(|| "any".to_string(), |items| schema_type_of(&items, source));
+            format!("{}[]", items)
+        }
+        Some(schema_type) => schema_type.to_string(),
+        None if get(schema, "properties", source).is_some() => "object".to_string(),
+        None => "any".to_string(),
+    }
+}
+
+// Array items are wrapped when they are unions
+fn schema_type_of(schema: &Node, source: &str) -> String {
+    let schema_type = schema_type(schema, source);
+    if schema_type.contains(' ') {
+        format!("({})", schema_type)
+    } else {
+        schema_type
+    }
+}
+
+// The schema of the first media type of a request body or response
+fn content_schema<'a>(node: &Node<'a>, source: &str) -> Option<Node<'a>> {
+    let content = get(node, "content", source)?;
+    mapping(&content, source)
+        .into_iter()
+        .find_map(|(_, media_type)| get(&media_type, "schema", source))
+}
+
+// `Pet` for `#/components/schemas/Pet`
+fn reference_name(reference: &Node, source: &str) -> String {
+    let reference = scalar(reference, source).unwrap_or_default();
+    reference
+        .rsplit('/')
+        .next()
+        .unwrap_or(&reference)
+        .to_string()
+}
+
+// Block nodes end at the start of the line after them
+fn lines(node: &Node) -> Lines {
+    let mut lines = Lines::of(node);
+    if node.end_position().column == 0 && lines.end > lines.start {
+        lines.end -= 1;
+    }
+    lines
+}
+
+fn first_line(text: &str) -> &str {
+    text.trim().lines().next().unwrap_or_default().trim()
+}
+
+fn first_document<'a>(stream: &Node<'a>) -> Option<Node<'a>> {
+    let document = stream
+        .named_children(&mut stream.walk())
+        .find(|child| child.kind() == "document")?;
+    document
+        .named_children(&mut document.walk())
+        .find(|child| matches!(child.kind(), "block_node" | "flow_node"))
+}
+
+// Look through `block_node` and `flow_node`, which only wrap their content
+fn content<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    match node.kind() {
+        "block_node" | "flow_node" => node
+            .named_children(&mut node.walk())
+            .find(|child| !matches!(child.kind(), "anchor" | "tag" | "comment")),
+        _ => Some(*node),
+    }
+}
+
+// The keys and values of a mapping, in order
+fn mapping<'a>(node: &Node<'a>, source: &str) -> Vec<(String, Node<'a>)> {
+    let Some(mapping) = content(node) else {
+        return Vec::new();
+    };
+    if !matches!(mapping.kind(), "block_mapping" | "flow_mapping") {
+        return Vec::new();
+    }
+    mapping
+        .named_children(&mut mapping.walk())
+        .filter_map(|pair| {
+            let key = scalar(&pair.child_by_field_name("key")?, source)?;
+            Some((key, pair.child_by_field_name("value")?))
+        })
+        .collect()
+}
+
+fn sequence<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    let Some(sequence) = content(node) else {
+        return Vec::new();
+    };
+    match sequence.kind() {
+        "block_sequence" => sequence
+            .named_children(&mut sequence.walk())
+            .filter_map(|item| {
+                item.named_children(&mut item.walk())
+                    .find(|child| matches!(child.kind(), "block_node" | "flow_node"))
+            })
+            .collect(),
+        "flow_sequence" => sequence
+            .named_children(&mut sequence.walk())
+            .filter(|child| child.kind() == "flow_node")
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn get<'a>(node: &Node<'a>, key: &str, source: &str) -> Option<Node<'a>> {
+    mapping(node, source)
+        .into_iter()
+        .find_map(|(name, value)| (name == key).then_some(value))
+}
+
+// The value of a scalar, without quotes; block scalars keep their lines
+fn scalar(node: &Node, source: &str) -> Option<String> {
+    let scalar = content(node)?;
+    let text = node_text(&scalar, source);
+    match scalar.kind() {
+        "plain_scalar" => Some(text.trim().to_string()),
+        "double_quote_scalar" => {
+            let text = text.strip_prefix('"')?.strip_suffix('"')?;
+            Some(text.replace("\\\"", "\"").replace("\\\\", "\\"))
+        }
+        "single_quote_scalar" => {
+            let text = text.strip_prefix('\'')?.strip_suffix('\'')?;
+            Some(text.replace("''", "'"))
+        }
+        "block_scalar" => {
+            // The first line only has the `|` or `>` indicator
+            let (_, text) = text.split_once('\n')?;
+            let lines: Vec<&str> = text.lines().map(str::trim).collect();
+            Some(lines.join("\n").trim().to_string())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,202 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// Protocol Buffers schemas: messages with their fields, enums with their
+/// values and services with their rpcs, nested declarations in place.
+///
+/// Options and reserved numbers are left out.
+pub struct Protobuf;
+
+impl LanguageBackend for Protobuf {
+    fn name(&self) -> &'static str {
+        "protobuf"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["proto"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_proto::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let mut items = Vec::new();
+        for node in top_level_items(&tree.root_node()) {
+            let kind = match node.kind() {
+                "package" => ItemKind::Module,
+                // Imports are only shown along with private items, like other
+                // languages' imports
+                "import" if options.include_uses && options.visibility == Visibility::Private => {
+                    ItemKind::UseDeclaration
+                }
+                "message" => ItemKind::Struct,
+                "enum" => ItemKind::Enum,
+                "service" => ItemKind::Interface,
+                "extend" => ItemKind::Impl,
+                _ => continue,
+            };
+
+            let text = match kind {
+                ItemKind::Module | ItemKind::UseDeclaration => {
+                    (!node.has_error()).then(|| collapse_whitespace(node_text(&node, source)))
+                }
+                _ => match memo.as_deref_mut() {
+                    Some(memo) => memo
+                        .get_or_render(&node, source, || render_block(&node, source, options, 0)),
+                    None => render_block(&node, source, options, 0),
+                },
+            };
+            // The doc comment isn't part of the declaration's text, so it is
+            // added after the memo
+            if let Some(text) = text {
+                items.push(Item {
+                    kind,
+                    name: block_name(&node).map(|name| node_text(&name, source).to_string()),
+                    text: with_doc(&node, source, options, text, ""),
+                    lines: Some(Lines::of(&node)),
+                    file: None,
+                });
+            }
+        }
+        items
+    }
+}
+
+// The name of a message, enum, service or oneof, or the type an extend adds to
+fn block_name<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    node.named_children(&mut node.walk()).find(|child| {
+        matches!(
+            child.kind(),
+            "message_name" | "enum_name" | "service_name" | "identifier" | "full_ident"
+        )
+    })
+}
+
+// The node holding the members of a block: its body, or the block itself
+// for services and oneofs, whose members follow the `{` directly
+fn block_members<'a>(node: &Node<'a>) -> Node<'a> {
+    node.named_children(&mut node.walk())
+        .find(|child| matches!(child.kind(), "message_body" | "enum_body"))
+        .unwrap_or(*node)
+}
+
+// `message Name { ... }` and the like with its members indented one level
+// deeper than `depth`. Blocks whose header doesn't parse are left out
+fn render_block(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    depth: usize,
+) -> Option<String> {
+    let members_node = block_members(node);
+    let open = members_node
+        .children(&mut members_node.walk())
+        .find(|child| child.kind() == "{")?;
+    let header_clean = node
+        .children(&mut node.walk())
+        .take_while(|child| child.start_byte() < open.start_byte())
+        .all(|child| !child.has_error());
+    if !header_clean || block_name(node).is_none() {
+        return None;
+    }
+    let header = collapse_whitespace(source.get(node.start_byte()..open.start_byte())?);
+
+    let indent = "    ".repeat(depth + 1);
+    let mut members = Vec::new();
+    for member in members_node.named_children(&mut members_node.walk()) {
+        let text = match member.kind() {
+            "message" | "enum" | "oneof" | "extend" => {
+                render_block(&member, source, options, depth + 1)
+            }
+            "field" | "map_field" | "oneof_field" | "enum_field" | "group" => {
+                Some(collapse_whitespace(node_text(&member, source)))
+            }
+            "rpc" => Some(rpc_signature(&member, source)),
+            // Options, reserved numbers, extension ranges and comments
+            _ => continue,
+        };
+        if let Some(text) = text.filter(|_| !member.has_error()) {
+            members.push(with_doc(&member, source, options, text, &indent));
+        }
+    }
+
+    if members.is_empty() {
+        return Some(format!("{} {{}}", header));
+    }
+    Some(format!(
+        "{} {{\n{}\n{}}}",
+        header,
+        members.join("\n"),
+        "    ".repeat(depth)
+    ))
+}
+
+// An rpc without the options in its body, if it has one
+fn rpc_signature(node: &Node, source: &str) -> String {
+    let body = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "{");
+    match body {
+        Some(body) => format!(
+            "{};",
+            collapse_whitespace(&source[node.start_byte()..body.start_byte()])
+        ),
+        None => collapse_whitespace(node_text(node, source)),
+    }
+}
+
+// Precede a declaration with the summary of its doc comment, both indented
+// by `indent` (the declaration's first line only, the rest already is)
+fn with_doc(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    rendered: String,
+    indent: &str,
+) -> String {
+    match doc_summary(node, source).filter(|_| options.include_docs) {
+        Some(doc) => format!("{}// {}\n{}{}", indent, doc, indent, rendered),
+        None => format!("{}{}", indent, rendered),
+    }
+}
+
+// The first line of text of the block of comments ending on the line right
+// before `node`. A comment after a declaration on the same line is about that
+// declaration, so it doesn't start a block
+fn doc_summary(node: &Node, source: &str) -> Option<String> {
+    let mut first = None;
+    let mut next_row = node.start_position().row;
+    let mut sibling = node.prev_sibling();
+    while let Some(comment) = sibling.filter(|sibling| sibling.kind() == "comment") {
+        let trailing = comment
+            .prev_sibling()
+            .is_some_and(|previous| previous.end_position().row == comment.start_position().row);
+        if comment.end_position().row + 1 != next_row || trailing {
+            break;
+        }
+        first = Some(comment);
+        next_row = comment.start_position().row;
+        sibling = comment.prev_sibling();
+    }
+
+    let comment = node_text(&first?, source);
+    let text = comment
+        .strip_prefix("//")
+        .or_else(|| comment.strip_prefix("/*"))
+        .unwrap_or(comment);
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| line.trim().trim_start_matches(['*', '/', '!']).trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,443 @@
+use std::collections::HashSet;
+
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// Python, rendered like a stub file: module-level classes, functions and
+/// constants with their type hints, decorators and docstring summaries.
+///
+/// A module name is public when `__all__` lists it, or when there is no
+/// `__all__` and it doesn't start with an underscore. Names a module with
+/// `__all__` leaves out count as crate-visible, names with a leading underscore
+/// as private. Class members are public unless they start with an underscore;
+/// dunder methods like `__init__` are public.
+pub struct Python;
+
+impl LanguageBackend for Python {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        "#"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["py", "pyi"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_python::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let nodes = top_level_items(&tree.root_node());
+        let exported = exported_names(&nodes, source);
+
+        let mut items = Vec::new();
+        for node in nodes {
+            let Some((kind, name)) = describe(&node, source, exported.as_ref()) else {
+                continue;
+            };
+
+            let visible = match (&kind, &name) {
+                (ItemKind::UseDeclaration, _) => {
+                    options.include_uses
+                        && (options.visibility == Visibility::Private
+                            || reexports(&node, source, exported.as_ref()))
+                }
+                (_, Some(name)) => module_visibility(name, exported.as_ref()) >= options.visibility,
+                (_, None) => false,
+            };
+            if !visible || !header_is_clean(&definition(&node)) {
+                continue;
+            }
+
+            let text = match memo.as_deref_mut() {
+                Some(memo) => {
+                    memo.get_or_render(&node, source, || render_item(&node, &kind, source, options))
+                }
+                None => render_item(&node, &kind, source, options),
+            };
+            if let Some(text) = text {
+                items.push(Item {
+                    kind,
+                    name,
+                    text,
+                    lines: Some(Lines::of(&node)),
+                    file: None,
+                });
+            }
+        }
+        items
+    }
+}
+
+// The kind and name of a module-level statement that can be part of the map.
+// Plain assignments only count as constants when they are named like one,
+// annotated or exported
+fn describe(
+    node: &Node,
+    source: &str,
+    exported: Option<&HashSet<String>>,
+) -> Option<(ItemKind, Option<String>)> {
+    let definition = definition(node);
+    let name = || {
+        definition
+            .child_by_field_name("name")
+            .map(|name| node_text(&name, source).to_string())
+    };
+
+    match definition.kind() {
+        "class_definition" => Some((ItemKind::Class, name())),
+        "function_definition" => Some((ItemKind::Function, name())),
+        "type_alias_statement" => {
+            let left = definition.child_by_field_name("left")?;
+            Some((
+                ItemKind::TypeAlias,
+                Some(node_text(&left, source).to_string()),
+            ))
+        }
+        "import_statement" | "import_from_statement" => Some((ItemKind::UseDeclaration, None)),
+        "expression_statement" => {
+            let assignment = assignment(&definition)?;
+            let name = node_text(&assignment.child_by_field_name("left")?, source);
+            let is_constant = name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            let is_annotated = assignment.child_by_field_name("type").is_some();
+            let is_exported = exported.is_some_and(|exported| exported.contains(name));
+            (name != "__all__" && (is_constant || is_annotated || is_exported))
+                .then(|| (ItemKind::Const, Some(name.to_string())))
+        }
+        _ => None,
+    }
+}
+
+// The class or function a decorated definition decorates, or the node itself
+fn definition<'a>(node: &Node<'a>) -> Node<'a> {
+    match node.kind() {
+        "decorated_definition" => node.child_by_field_name("definition").unwrap_or(*node),
+        _ => *node,
+    }
+}
+
+// The assignment of an expression statement to a single name
+fn assignment<'a>(statement: &Node<'a>) -> Option<Node<'a>> {
+    let assignment = statement.named_child(0)?;
+    let is_single_name = assignment
+        .child_by_field_name("left")
+        .is_some_and(|left| left.kind() == "identifier");
+    (assignment.kind() == "assignment" && is_single_name).then_some(assignment)
+}
+
+// The names listed in `__all__`, from its assignments and `+=` extensions;
+// `None` when the module doesn't define it
+fn exported_names(nodes: &[Node], source: &str) -> Option<HashSet<String>> {
+    let mut exported: Option<HashSet<String>> = None;
+    for node in nodes
+        .iter()
+        .filter(|node| node.kind() == "expression_statement")
+    {
+        let Some(statement) = node.named_child(0) else {
+            continue;
+        };
+        if !matches!(statement.kind(), "assignment" | "augmented_assignment") {
+            continue;
+        }
+        let is_all = statement
+            .child_by_field_name("left")
+            .is_some_and(|left| node_text(&left, source) == "__all__");
+        let Some(value) = statement.child_by_field_name("right") else {
+            continue;
+        };
+        if !is_all {
+            continue;
+        }
+
+        let names = exported.get_or_insert_with(HashSet::new);
+        if statement.kind() == "assignment" {
+            names.clear();
+        }
+        let mut cursor = value.walk();
+        for element in value.named_children(&mut cursor) {
+            if let Some(name) = string_content(&element, source) {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    exported
+}
+
+// The text inside the quotes of a plain string literal
+fn string_content<'a>(node: &Node, source: &'a str) -> Option<&'a str> {
+    if node.kind() != "string" {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let content = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "string_content")?;
+    Some(node_text(&content, source))
+}
+
+fn module_visibility(name: &str, exported: Option<&HashSet<String>>) -> Visibility {
+    match exported {
+        Some(exported) if exported.contains(name) => Visibility::Public,
+        _ if name.starts_with('_') => Visibility::Private,
+        Some(_) => Visibility::Crate,
+        None => Visibility::Public,
+    }
+}
+
+fn member_visibility(name: &str) -> Visibility {
+    let is_dunder = name.len() > 4 && name.starts_with("__") && name.ends_with("__");
+    if name.starts_with('_') && !is_dunder {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    }
+}
+
+// Whether an import re-exports a name: it brings in a name `__all__` lists,
+// or it uses the redundant `import a as a` form type checkers treat as a
+// re-export
+fn reexports(node: &Node, source: &str, exported: Option<&HashSet<String>>) -> bool {
+    // The imported names, skipping the module of `from module import ...`
+    let module = node.child_by_field_name("module_name");
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|name| Some(*name) != module)
+        .any(|name| match name.kind() {
+            "aliased_import" => {
+                let original = name
+                    .child_by_field_name("name")
+                    .map(|original| node_text(&original, source));
+                let alias = name
+                    .child_by_field_name("alias")
+                    .map(|alias| node_text(&alias, source));
+                original == alias
+                    || alias.is_some_and(|alias| {
+                        exported.is_some_and(|exported| exported.contains(alias))
+                    })
+            }
+            _ => exported.is_some_and(|exported| exported.contains(node_text(&name, source))),
+        })
+}
+
+fn render_item(
+    node: &Node,
+    kind: &ItemKind,
+    source: &str,
+    options: &CodemapOptions,
+) -> Option<String> {
+    match kind {
+        ItemKind::Class | ItemKind::Function => render_definition(node, source, options, ""),
+        ItemKind::Const => {
+            let assignment = assignment(node)?;
+            Some(render_assignment(&assignment, source))
+        }
+        _ => Some(collapse_whitespace(node_text(node, source))),
+    }
+}
+
+// Render a class or function, possibly decorated, with every line indented by
+// `indent`
+fn render_definition(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    indent: &str,
+) -> Option<String> {
+    let definition = definition(node);
+    let mut lines = Vec::new();
+
+    if options.include_attributes && node.kind() == "decorated_definition" {
+        let mut cursor = node.walk();
+        for decorator in node
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == "decorator")
+        {
+            lines.push(format!(
+                "{}{}",
+                indent,
+                collapse_whitespace(node_text(&decorator, source))
+            ));
+        }
+    }
+
+    let body = definition.child_by_field_name("body");
+    let mut members = Vec::new();
+    let header = match definition.kind() {
+        "function_definition" => function_header(&definition, source)?,
+        "class_definition" => {
+            if let Some(body) = &body {
+                members = class_members(body, source, options, &format!("{}    ", indent));
+            }
+            class_header(&definition, source)?
+        }
+        _ => return None,
+    };
+
+    let docstring = body
+        .filter(|_| options.include_docs)
+        .and_then(|body| docstring_summary(&body, source));
+    if docstring.is_none() && members.is_empty() {
+        lines.push(format!("{}{}: ...", indent, header));
+    } else {
+        lines.push(format!("{}{}:", indent, header));
+        if let Some(docstring) = docstring {
+            lines.push(format!("{}    \"\"\"{}\"\"\"", indent, docstring));
+        }
+        lines.extend(members);
+    }
+    Some(lines.join("\n"))
+}
+
+// `def name(params) -> type`, or `async def ...`
+fn function_header(node: &Node, source: &str) -> Option<String> {
+    let name = node_text(&node.child_by_field_name("name")?, source);
+    let is_async = node
+        .children(&mut node.walk())
+        .any(|child| child.kind() == "async");
+    let type_parameters = node
+        .child_by_field_name("type_parameters")
+        .map(|parameters| collapse_whitespace(node_text(&parameters, source)))
+        .unwrap_or_default();
+
+    // Parameters one by one, which drops comments and the trailing comma
+    let parameters_node = node.child_by_field_name("parameters")?;
+    let mut cursor = parameters_node.walk();
+    let parameters: Vec<String> = parameters_node
+        .named_children(&mut cursor)
+        .filter(|parameter| parameter.kind() != "comment")
+        .map(|parameter| collapse_whitespace(node_text(&parameter, source)))
+        .collect();
+
+    let return_type = node
+        .child_by_field_name("return_type")
+        .map(|return_type| {
+            format!(
+                " -> {}",
+                collapse_whitespace(node_text(&return_type, source))
+            )
+        })
+        .unwrap_or_default();
+
+    Some(format!(
+        "{}def {}{}({}){}",
+        if is_async { "async " } else { "" },
+        name,
+        type_parameters,
+        parameters.join(", "),
+        return_type
+    ))
+}
+
+// `class Name(Base, metaclass=Meta)`
+fn class_header(node: &Node, source: &str) -> Option<String> {
+    let name = node_text(&node.child_by_field_name("name")?, source);
+    let type_parameters = node
+        .child_by_field_name("type_parameters")
+        .map(|parameters| collapse_whitespace(node_text(&parameters, source)))
+        .unwrap_or_default();
+
+    let superclasses = node
+        .child_by_field_name("superclasses")
+        .map(|superclasses| {
+            let mut cursor = superclasses.walk();
+            superclasses
+                .named_children(&mut cursor)
+                .filter(|superclass| superclass.kind() != "comment")
+                .map(|superclass| collapse_whitespace(node_text(&superclass, source)))
+                .collect::<Vec<_>>()
+        });
+
+    Some(match superclasses {
+        Some(superclasses) if !superclasses.is_empty() => format!(
+            "class {}{}({})",
+            name,
+            type_parameters,
+            superclasses.join(", ")
+        ),
+        _ => format!("class {}{}", name, type_parameters),
+    })
+}
+
+// The visible attributes, methods and nested classes of a class body
+fn class_members(body: &Node, source: &str, options: &CodemapOptions, indent: &str) -> Vec<String> {
+    let mut members = Vec::new();
+    let mut cursor = body.walk();
+    for member in body.named_children(&mut cursor) {
+        // Drop members that didn't parse rather than show half of them
+        if member.has_error() {
+            continue;
+        }
+
+        let definition = definition(&member);
+        let rendered = match definition.kind() {
+            "function_definition" | "class_definition" => {
+                let name = definition
+                    .child_by_field_name("name")
+                    .map(|name| node_text(&name, source));
+                if name.is_some_and(|name| member_visibility(name) >= options.visibility) {
+                    render_definition(&member, source, options, indent)
+                } else {
+                    None
+                }
+            }
+            "expression_statement" => assignment(&member).and_then(|assignment| {
+                let name = node_text(&assignment.child_by_field_name("left")?, source);
+                (member_visibility(name) >= options.visibility)
+                    .then(|| format!("{}{}", indent, render_assignment(&assignment, source)))
+            }),
+            _ => None,
+        };
+        members.extend(rendered);
+    }
+    members
+}
+
+// `NAME: type = value`, leaving out values spanning several lines
+fn render_assignment(assignment: &Node, source: &str) -> String {
+    let name = assignment
+        .child_by_field_name("left")
+        .map(|left| node_text(&left, source))
+        .unwrap_or_default();
+    let annotation = assignment
+        .child_by_field_name("type")
+        .map(|type_node| format!(": {}", collapse_whitespace(node_text(&type_node, source))))
+        .unwrap_or_default();
+    let value = assignment.child_by_field_name("right").map(|value| {
+        let value = node_text(&value, source);
+        if value.contains('\n') { "..." } else { value }
+    });
+
+    match value {
+        Some(value) => format!("{}{} = {}", name, annotation, value),
+        None => format!("{}{}", name, annotation),
+    }
+}
+
+// The first non-empty line of the docstring opening a body
+fn docstring_summary(body: &Node, source: &str) -> Option<String> {
+    let first = body.named_child(0)?;
+    if first.kind() != "expression_statement" {
+        return None;
+    }
+    let content = string_content(&first.named_child(0)?, source)?;
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,820 @@
+use std::collections::HashMap;
+
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text, rust_parser};
+
+/// Rust, the public interface of every item down to struct fields and
+/// methods.
+pub struct Rust;
+
+impl LanguageBackend for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rs"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_rust::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo)
+    }
+}
+
+// Map the items of a parsed file. With a memo, items whose source text didn't
+// change since the last call are not processed again
+fn map_items(
+    tree: &Tree,
+    source_code: &str,
+    options: &CodemapOptions,
+    memo: Option<&mut ItemMemo>,
+) -> Vec<Item> {
+    let recovered = recover_items(tree, source_code);
+    let root_node = recovered.as_ref().unwrap_or(tree).root_node();
+
+    // Items that tree-sitter could not fit into the grammar end up inside ERROR
+    // nodes, so look inside them for items that are still complete
+    map_nodes(&top_level_items(&root_node), source_code, options, memo)
+}
+
+// Map a list of sibling items, those of a file or of an inline module
+fn map_nodes(
+    items: &[Node],
+    source_code: &str,
+    options: &CodemapOptions,
+    mut memo: Option<&mut ItemMemo>,
+) -> Vec<Item> {
+    // Vector to collect the public items
+    let mut public_items: Vec<Item> = Vec::new();
+
+    // Map to store impl blocks by type name
+    let mut impl_blocks = HashMap::new();
+
+    // First pass: collect all impl blocks for public structs
+    for child in items {
+        if child.kind() != "impl_item" {
+            continue;
+        }
+        let impl_info = match memo.as_deref_mut() {
+            Some(memo) => memo.impls.get_or_insert_with(child, source_code, || {
+                process_impl(child, source_code, options)
+            }),
+            None => process_impl(child, source_code, options),
+        };
+        if let Some(impl_info) = impl_info {
+            impl_blocks
+                .entry(impl_info.0)
+                .or_insert_with(Vec::new)
+                .push(impl_info.1);
+        }
+    }
+
+    // Second pass: traverse the items themselves
+    for &child in items {
+        let item_kind = ItemKind::from_node_kind(child.kind());
+
+        // Every item, traits included, is private unless marked `pub`.
+        // Skip items whose rendered part is broken, otherwise a half-typed
+        // declaration shows up as a garbage item
+        if !is_visible(&child, source_code, options) || !header_is_clean(&child) {
+            continue;
+        }
+        if item_kind == ItemKind::UseDeclaration && !options.include_uses {
+            continue;
+        }
+
+        let name = child
+            .child_by_field_name("name")
+            .map(|name_node| node_text(&name_node, source_code));
+
+        let rendered = match memo.as_deref_mut() {
+            Some(memo) => memo.items.get_or_insert_with(&child, source_code, || {
+                render_item(&child, &item_kind, source_code, options)
+            }),
+            None => render_item(&child, &item_kind, source_code, options),
+        };
+
+        // Precede the item with its doc summary and attributes, then add the
+        // struct's impl blocks if any
+        let text = rendered.map(|item_output| {
+            let mut item_output =
+                with_attributes_and_docs(&child, source_code, options, item_output);
+            if item_kind == ItemKind::Struct
+                && let Some(impls) = name.and_then(|name| impl_blocks.get(name))
+            {
+                for impl_block in impls {
+                    if !impl_block.is_empty() {
+                        item_output = format!("{}\n\n{}", item_output, impl_block);
+                    }
+                }
+            }
+            item_output
+        });
+
+        if let Some(text) = text {
+            public_items.push(Item {
+                kind: item_kind,
+                name: name.map(str::to_string),
+                text,
+                lines: Some(Lines::of(&child)),
+                file: None,
+            });
+        }
+    }
+
+    public_items
+}
+
+// A broken item can take in the start of the next one, like the `pub` of
+// `pub struct\npub const AFTER: i32 = 42;`, leaving that one private. Blank
+// out the line such items start on, from where they start, and parse again
+// until every broken item is on a single line. Byte offsets don't change, so
+// the nodes of the new tree still point into `source`
+fn recover_items(tree: &Tree, source: &str) -> Option<Tree> {
+    // Most files have no broken item, don't load the grammar again for them
+    let mut start = first_broken_item(tree)?;
+    let mut parser = rust_parser().ok()?;
+    let mut blanked = source.to_string();
+
+    // Every round blanks another line, as broken items never start on a
+    // blank one
+    loop {
+        let end = blanked[start..]
+            .find('\n')
+            .map_or(blanked.len(), |newline| start + newline);
+        let spaces = " ".repeat(end - start);
+        blanked.replace_range(start..end, &spaces);
+        let recovered = parser.parse(&blanked, None)?;
+        match first_broken_item(&recovered) {
+            Some(next) => start = next,
+            None => return Some(recovered),
+        }
+    }
+}
+
+// Where the first broken item spanning several lines starts
+fn first_broken_item(tree: &Tree) -> Option<usize> {
+    top_level_items(&tree.root_node())
+        .into_iter()
+        .find(|item| !header_is_clean(item) && item.end_position().row > item.start_position().row)
+        .map(|item| item.start_byte())
+}
+
+// Render a single public item on its own; impl blocks are attached to structs
+// by the caller
+fn render_item(
+    node: &Node,
+    item_kind: &ItemKind,
+    source: &str,
+    options: &CodemapOptions,
+) -> Option<String> {
+    match item_kind {
+        ItemKind::Struct => process_struct(node, source, options),
+        ItemKind::Enum => process_enum(node, source, options),
+        ItemKind::Const => Some(process_const(node, source)),
+        ItemKind::Function => process_function(node, source),
+        // Impls are attached to their struct, the rest isn't Rust
+        ItemKind::Impl | ItemKind::Class | ItemKind::Interface => None,
+        ItemKind::Module => process_module(node, source, options),
+        ItemKind::TypeAlias => Some(process_type_alias(node, source)),
+        ItemKind::Trait => process_trait(node, source),
+        ItemKind::UseDeclaration => Some(process_use_declaration(node, source)),
+        // Anything else (macros, statics, stray tokens from a broken
+        // parse) is not part of the map
+        ItemKind::Other(_) => None,
+    }
+}
+
+// The declared visibility of an item, field or method
+fn visibility(node: &Node, source: &str) -> Visibility {
+    let modifier = node
+        .children(&mut node.walk())
+        .find(|child| child.kind() == "visibility_modifier");
+    match modifier.map(|modifier| collapse_whitespace(node_text(&modifier, source))) {
+        None => Visibility::Private,
+        Some(modifier) if modifier == "pub" => Visibility::Public,
+        Some(modifier) if modifier == "pub(self)" => Visibility::Private,
+        Some(_) => Visibility::Crate,
+    }
+}
+
+// Check if a node is visible enough to be mapped
+fn is_visible(node: &Node, source: &str, options: &CodemapOptions) -> bool {
+    visibility(node, source) >= options.visibility
+}
+
+// The visibility modifier of a node as it is written, followed by a space, or
+// nothing for private nodes
+fn visibility_prefix(node: &Node, source: &str) -> String {
+    node.children(&mut node.walk())
+        .find(|child| child.kind() == "visibility_modifier")
+        .map(|modifier| format!("{} ", collapse_whitespace(node_text(&modifier, source))))
+        .unwrap_or_default()
+}
+
+// Process a public struct and return its external interface
+fn process_struct(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    // Extract struct name
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+
+    // Extract the generic type parameters (with their bounds) and where clause if any
+    let generic_params = node
+        .child_by_field_name("type_parameters")
+        .map(|params| collapse_whitespace(node_text(&params, source)))
+        .unwrap_or_default();
+    let where_clause = where_clause(node, source)
+        .map(|clause| format!(" {}", clause))
+        .unwrap_or_default();
+
+    let header = format!(
+        "{}struct {}{}",
+        visibility_prefix(node, source),
+        name,
+        generic_params
+    );
+
+    // Get the field declaration list if it exists (it's called "body" in the AST)
+    let Some(field_list_node) = node.child_by_field_name("body") else {
+        // Unit struct
+        return Some(format!("{}{};", header, where_clause));
+    };
+
+    // For tuple structs, the body node kind is "ordered_field_declaration_list"
+    if field_list_node.kind() == "ordered_field_declaration_list" {
+        // A broken tuple struct can't be shown field by field
+        if field_list_node.has_error() {
+            return None;
+        }
+
+        return Some(format!(
+            "{}({}){};",
+            header,
+            process_tuple_fields(&field_list_node, source, options).join(", "),
+            where_clause
+        ));
+    }
+
+    // Collect public fields, remembering whether any were left out
+    let mut field_nodes = Vec::new();
+    let mut has_private_fields = false;
+    let mut cursor = field_list_node.walk();
+    for child in field_list_node.children(&mut cursor) {
+        if child.kind() != "field_declaration" || child.has_error() {
+            continue;
+        }
+        if is_visible(&child, source, options) {
+            field_nodes.push(child);
+        } else {
+            has_private_fields = true;
+        }
+    }
+
+    let mut public_fields = Vec::new();
+    for (index, child) in field_nodes.iter().enumerate() {
+        let (attributes, doc_summary) = leading_attributes_and_docs(child, source);
+        if let Some(doc_summary) = doc_summary.filter(|_| options.include_docs) {
+            public_fields.push(format!("    /// {}", doc_summary));
+        }
+        if options.include_attributes {
+            for attribute in attributes {
+                public_fields.push(format!("    {}", attribute));
+            }
+        }
+
+        let field_text = collapse_whitespace(node_text(child, source));
+
+        // Add a comma if it's not the last field or if the original field has a comma
+        let with_comma = if index < field_nodes.len() - 1 || field_text.ends_with(',') {
+            format!("{},", field_text)
+        } else {
+            field_text
+        };
+
+        public_fields.push(format!("    {}", with_comma));
+    }
+
+    // Private fields mean the struct can't be built with a struct literal, so
+    // say so rather than silently dropping them
+    if has_private_fields {
+        public_fields.push("    // private fields".to_string());
+    }
+
+    // Construct the struct definition with generic parameters if any
+    if public_fields.is_empty() {
+        Some(format!("{}{} {{}}", header, where_clause))
+    } else {
+        Some(format!(
+            "{}{} {{\n{}\n}}",
+            header,
+            where_clause,
+            public_fields.join("\n")
+        ))
+    }
+}
+
+// Render the fields of a tuple struct, showing private positions as `_` so the
+// public ones keep their index
+fn process_tuple_fields(node: &Node, source: &str, options: &CodemapOptions) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut visibility = None;
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "(" | ")" | "," | "attribute_item" | "line_comment" | "block_comment" => {}
+            "visibility_modifier" => {
+                visibility = Some(collapse_whitespace(node_text(&child, source)));
+            }
+            _ => {
+                let visibility = visibility.take();
+                let level = match visibility.as_deref() {
+                    None | Some("pub(self)") => Visibility::Private,
+                    Some("pub") => Visibility::Public,
+                    Some(_) => Visibility::Crate,
+                };
+                if level >= options.visibility {
+                    let field_type = collapse_whitespace(node_text(&child, source));
+                    fields.push(match visibility {
+                        Some(visibility) => format!("{} {}", visibility, field_type),
+                        None => field_type,
+                    });
+                } else {
+                    fields.push("_".to_string());
+                }
+            }
+        }
+    }
+
+    fields
+}
+
+// Process a public enum and return its external interface
+fn process_enum(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    // Extract enum name
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+
+    // Extract the generic type parameters and where clause if any
+    let generic_params = node
+        .child_by_field_name("type_parameters")
+        .map(|params| collapse_whitespace(node_text(&params, source)))
+        .unwrap_or_default();
+    let where_clause = where_clause(node, source)
+        .map(|clause| format!(" {}", clause))
+        .unwrap_or_default();
+
+    let header = format!(
+        "{}enum {}{}{}",
+        visibility_prefix(node, source),
+        name,
+        generic_params,
+        where_clause
+    );
+
+    // Get the variant list if it exists (it's called "body" in the AST)
+    let Some(variant_list_node) = node.child_by_field_name("body") else {
+        return Some(format!("{} {{}}", header));
+    };
+
+    // Render each variant from the AST, so comments between tokens don't leak
+    // into the map
+    let mut variants: Vec<String> = Vec::new();
+    let mut cursor = variant_list_node.walk();
+    for child in variant_list_node.children(&mut cursor) {
+        if child.kind() != "enum_variant" || child.has_error() {
+            continue;
+        }
+
+        let (attributes, doc_summary) = leading_attributes_and_docs(&child, source);
+        if let Some(doc_summary) = doc_summary.filter(|_| options.include_docs) {
+            variants.push(format!("    /// {}", doc_summary));
+        }
+        if options.include_attributes {
+            for attribute in attributes {
+                variants.push(format!("    {}", attribute));
+            }
+        }
+
+        // Always add a comma to match snapshot format
+        variants.push(format!("    {},", process_enum_variant(&child, source)?));
+    }
+
+    // Construct the enum definition
+    if variants.is_empty() {
+        Some(format!("{} {{}}", header))
+    } else {
+        Some(format!("{} {{\n{}\n}}", header, variants.join("\n")))
+    }
+}
+
+// Render a single enum variant: its name, fields and explicit discriminant
+fn process_enum_variant(node: &Node, source: &str) -> Option<String> {
+    let name_node = node.child_by_field_name("name")?;
+    let mut variant = node_text(&name_node, source).to_string();
+
+    if let Some(body_node) = node.child_by_field_name("body") {
+        let mut cursor = body_node.walk();
+        match body_node.kind() {
+            // Struct-like variant: `Variant { field: Type }`
+            "field_declaration_list" => {
+                let fields: Vec<String> = body_node
+                    .children(&mut cursor)
+                    .filter(|field| field.kind() == "field_declaration")
+                    .filter_map(|field| {
+                        let name = field.child_by_field_name("name")?;
+                        let field_type = field.child_by_field_name("type")?;
+                        Some(format!(
+                            "{}: {}",
+                            node_text(&name, source),
+                            collapse_whitespace(node_text(&field_type, source))
+                        ))
+                    })
+                    .collect();
+                variant = format!("{} {{ {} }}", variant, fields.join(", "));
+            }
+            // Tuple variant: `Variant(Type, Type)`. Variant fields are always
+            // public, so any visibility modifier is dropped
+            _ => {
+                let fields: Vec<String> = body_node
+                    .children_by_field_name("type", &mut cursor)
+                    .map(|field_type| collapse_whitespace(node_text(&field_type, source)))
+                    .collect();
+                variant = format!("{}({})", variant, fields.join(", "));
+            }
+        }
+    }
+
+    // Keep explicit discriminants, they are part of the public contract
+    if let Some(value_node) = node.child_by_field_name("value") {
+        variant = format!(
+            "{} = {}",
+            variant,
+            collapse_whitespace(node_text(&value_node, source))
+        );
+    }
+
+    Some(variant)
+}
+
+// Get the where clause of an item, normalized to a single line without the
+// trailing comma rustfmt adds
+fn where_clause(node: &Node, source: &str) -> Option<String> {
+    node.children(&mut node.walk())
+        .find(|child| child.kind() == "where_clause")
+        .map(|clause| {
+            let clause = collapse_whitespace(node_text(&clause, source));
+            clause.trim_end_matches(',').to_string()
+        })
+}
+
+// Collect the attributes and the doc comment summary written directly above a
+// node. Plain comments are skipped, and the summary is the first line of the
+// `///` doc comment
+fn leading_attributes_and_docs(node: &Node, source: &str) -> (Vec<String>, Option<String>) {
+    let mut attributes = Vec::new();
+    let mut doc_lines = Vec::new();
+
+    let mut sibling = node.prev_sibling();
+    while let Some(current) = sibling {
+        let text = node_text(&current, source).trim();
+        match current.kind() {
+            "attribute_item" => attributes.push(collapse_whitespace(text)),
+            "line_comment" => {
+                if let Some(doc) = text.strip_prefix("///")
+                    && !doc.starts_with('/')
+                {
+                    doc_lines.push(doc.trim().to_string());
+                }
+            }
+            "block_comment" => {}
+            _ => break,
+        }
+        sibling = current.prev_sibling();
+    }
+
+    // Siblings were visited bottom-up
+    attributes.reverse();
+    doc_lines.reverse();
+
+    let doc_summary = doc_lines.into_iter().find(|line| !line.is_empty());
+    (attributes, doc_summary)
+}
+
+// Precede a rendered item with the summary of its doc comment and its
+// attributes, as far as the options show them
+fn with_attributes_and_docs(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    rendered: String,
+) -> String {
+    let (attributes, doc_summary) = leading_attributes_and_docs(node, source);
+    let mut lines = Vec::new();
+    if let Some(doc_summary) = doc_summary.filter(|_| options.include_docs) {
+        lines.push(format!("/// {}", doc_summary));
+    }
+    if options.include_attributes {
+        lines.extend(attributes);
+    }
+    lines.push(rendered);
+    lines.join("\n")
+}
+
+// Process a public constant and return its definition
+fn process_const(node: &Node, source: &str) -> String {
+    // Extract the entire constant declaration
+    let const_text = node_text(node, source);
+
+    // Return the constant declaration as is
+    const_text.to_string()
+}
+
+// Process an impl block and extract public methods. Trait impls are shown as
+// their header only, their methods are the trait's
+fn process_impl(node: &Node, source: &str, options: &CodemapOptions) -> Option<(String, String)> {
+    // An impl with a broken header can't be attributed to a type
+    if !header_is_clean(node) {
+        return None;
+    }
+
+    // Extract the type name this impl is for
+    let type_node = node.child_by_field_name("type")?;
+    let type_name = node_text(&type_node, source);
+
+    if node.child_by_field_name("trait").is_some() {
+        if !options.include_trait_impls {
+            return None;
+        }
+        let body_start = node
+            .child_by_field_name("body")
+            .map_or(node.end_byte(), |body| body.start_byte());
+        let header = collapse_whitespace(source.get(node.start_byte()..body_start)?);
+        return Some((type_name.to_string(), format!("{} {{}}", header)));
+    }
+    if !options.include_impls {
+        return None;
+    }
+
+    // Get the implementation body
+    let body_node = node.child_by_field_name("body")?;
+
+    // Collect public methods
+    let mut public_methods = Vec::new();
+    let mut cursor = body_node.walk();
+
+    for child in body_node.children(&mut cursor) {
+        if child.kind() == "function_item"
+            && header_is_clean(&child)
+            && is_visible(&child, source, options)
+        {
+            // Extract the method text
+            let method_text = node_text(&child, source);
+
+            // Check if it contains "async fn"
+            let is_async = method_text.contains("async fn");
+
+            // Get the method signature
+            let name_node = child.child_by_field_name("name")?;
+            let name = node_text(&name_node, source);
+
+            // Check for generic type parameters
+            let mut generic_params = String::new();
+            // Look for the type_parameters node which contains generic parameters
+            for type_params_node in child.children(&mut child.walk()) {
+                if type_params_node.kind() == "type_parameters" {
+                    generic_params = node_text(&type_params_node, source).to_string();
+                    break;
+                }
+            }
+
+            // Get the parameters
+            let mut params = Vec::new();
+            let parameters_node = child.child_by_field_name("parameters")?;
+            let mut param_cursor = parameters_node.walk();
+
+            // First check if this method has a self parameter
+            let has_self_param = parameters_node
+                .children(&mut parameters_node.walk())
+                .any(|param| param.kind() == "self_parameter");
+
+            // If it has a self parameter, add it first
+            if has_self_param {
+                // Try to find the specific self parameter to get accurate text
+                let self_text = parameters_node
+                    .children(&mut parameters_node.walk())
+                    .find(|param| param.kind() == "self_parameter")
+                    .map(|param| node_text(&param, source).to_string())
+                    .unwrap_or("&self".to_string()); // Default to &self if not found
+
+                params.push(self_text);
+            }
+
+            // Add the rest of the parameters
+            for param in parameters_node.children(&mut param_cursor) {
+                if param.kind() == "parameter" {
+                    let param_text = node_text(&param, source);
+                    params.push(param_text.to_string());
+                }
+            }
+
+            // Get the return type if any
+            let mut return_type = String::new();
+            if let Some(return_node) = child.child_by_field_name("return_type") {
+                return_type = format!(" -> {}", node_text(&return_node, source));
+            }
+
+            // Construct the method signature
+            let method_sig = if is_async {
+                format!(
+                    "    {}async fn {}{}({}){};",
+                    visibility_prefix(&child, source),
+                    name,
+                    generic_params,
+                    params.join(", "),
+                    return_type
+                )
+            } else {
+                format!(
+                    "    {}fn {}{}({}){};",
+                    visibility_prefix(&child, source),
+                    name,
+                    generic_params,
+                    params.join(", "),
+                    return_type
+                )
+            };
+
+            public_methods.push(method_sig);
+        }
+    }
+
+    // If no public methods, return None
+    if public_methods.is_empty() {
+        return None;
+    }
+
+    // Create the impl block
+    let impl_block = format!("impl {} {{\n{}\n}}", type_name, public_methods.join("\n"));
+
+    Some((type_name.to_string(), impl_block))
+}
+
+// Process a public function and return its signature
+fn process_function(node: &Node, source: &str) -> Option<String> {
+    // Extract the function declaration text
+    let func_text = node_text(node, source);
+
+    // Check if it contains "async fn"
+    let is_async = func_text.contains("async fn");
+
+    // Get the function name
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+
+    // Check for generic type parameters
+    let mut generic_params = String::new();
+    // Look for the type_parameters node which contains generic parameters
+    for type_params_node in node.children(&mut node.walk()) {
+        if type_params_node.kind() == "type_parameters" {
+            generic_params = node_text(&type_params_node, source).to_string();
+            break;
+        }
+    }
+
+    // Get the parameters
+    let mut params = Vec::new();
+    let parameters_node = node.child_by_field_name("parameters")?;
+    let mut param_cursor = parameters_node.walk();
+
+    for param in parameters_node.children(&mut param_cursor) {
+        if param.kind() == "parameter" {
+            let param_text = node_text(&param, source);
+            params.push(param_text.to_string());
+        }
+    }
+
+    // Get the return type if any
+    let mut return_type = String::new();
+    if let Some(return_node) = node.child_by_field_name("return_type") {
+        return_type = format!(" -> {}", node_text(&return_node, source));
+    }
+
+    // Construct the function signature
+    if is_async {
+        Some(format!(
+            "{}async fn {}{}({}){};",
+            visibility_prefix(node, source),
+            name,
+            generic_params,
+            params.join(", "),
+            return_type
+        ))
+    } else {
+        Some(format!(
+            "{}fn {}{}({}){};",
+            visibility_prefix(node, source),
+            name,
+            generic_params,
+            params.join(", "),
+            return_type
+        ))
+    }
+}
+
+// Process a public module declaration, with the items of an inline module
+fn process_module(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    // Extract the module name
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+    let declaration = format!("{}mod {}", visibility_prefix(node, source), name);
+
+    // A module in another file is just declared
+    let Some(body_node) = node.child_by_field_name("body") else {
+        return Some(format!("{};", declaration));
+    };
+
+    let items = map_nodes(&top_level_items(&body_node), source, options, None);
+    if items.is_empty() {
+        return Some(format!("{} {{}}", declaration));
+    }
+    let body = items
+        .iter()
+        .map(|item| indent(&item.text))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    Some(format!("{} {{\n{}\n}}", declaration, body))
+}
+
+// Indent every non-empty line of `text` by one level
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| match line.is_empty() {
+            true => String::new(),
+            false => format!("    {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Process a public type alias
+fn process_type_alias(node: &Node, source: &str) -> String {
+    // Extract the entire type alias declaration
+    let type_text = node_text(node, source);
+
+    // Return the type alias declaration as is
+    type_text.to_string()
+}
+
+// Process a public trait definition
+fn process_trait(node: &Node, source: &str) -> Option<String> {
+    // Extract the trait name
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+
+    // Get the trait body
+    let body_node = node.child_by_field_name("body")?;
+
+    // Collect trait methods
+    let mut methods = Vec::new();
+    let mut cursor = body_node.walk();
+
+    for child in body_node.children(&mut cursor) {
+        // In trait definitions, method signatures appear as function_signature_item
+        if child.kind() == "function_signature_item" && !child.has_error() {
+            // Extract the entire method signature
+            let signature_text = node_text(&child, source).trim();
+            methods.push(format!("    {}", signature_text));
+        }
+    }
+
+    // Construct the trait definition
+    let header = format!("{}trait {}", visibility_prefix(node, source), name);
+    if methods.is_empty() {
+        Some(format!("{} {{}}", header))
+    } else {
+        Some(format!("{} {{\n{}\n}}", header, methods.join("\n")))
+    }
+}
+
+// Process a public use declaration
+fn process_use_declaration(node: &Node, source: &str) -> String {
+    // Extract the use declaration text
+    let use_text = node_text(node, source);
+
+    // Return the use declaration as is
+    use_text.to_string()
+}
@@ -0,0 +1,273 @@
+use sqlparser::ast::{Statement, UserDefinedTypeRepresentation};
+use sqlparser::dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer, Whitespace};
+use tree_sitter::Language;
+
+use super::LanguageBackend;
+use crate::{CodemapOptions, Diagnostic, FileMap, Item, ItemKind, Lines};
+
+/// SQL schemas and migrations: one item per table with its columns and
+/// constraints, and one per view, index, type and `ALTER TABLE`.
+///
+/// Statements are parsed one at a time, with the first of the generic,
+/// PostgreSQL, MySQL and SQLite dialects that accepts them, so a statement
+/// that doesn't parse is reported without losing the rest of the file.
+/// Queries and data changes are left out.
+pub struct Sql;
+
+impl LanguageBackend for Sql {
+    fn name(&self) -> &'static str {
+        "sql"
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        "--"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["sql"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn map_source(&self, source: &str, options: &CodemapOptions) -> FileMap {
+        let tokens = match Tokenizer::new(&GenericDialect {}, source).tokenize_with_location() {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                return FileMap {
+                    items: Vec::new(),
+                    diagnostics: vec![diagnostic(source, error.location, error.message)],
+                };
+            }
+        };
+
+        let mut file_map = FileMap::default();
+        for (index, statement) in statements(&tokens).into_iter().enumerate() {
+            let code =
+                |token: &TokenWithSpan| !matches!(token.token, Token::Whitespace(_) | Token::EOF);
+            let (Some(first), Some(last)) = (
+                statement.iter().position(code),
+                statement.iter().rposition(code),
+            ) else {
+                continue;
+            };
+            let start = statement[first].span.start;
+
+            let parsed = match parse(&statement[first..=last]) {
+                Ok(parsed) => parsed,
+                Err(message) => {
+                    file_map
+                        .diagnostics
+                        .push(diagnostic(source, start, message));
+                    continue;
+                }
+            };
+            let Some((kind, name, text)) = render(&parsed) else {
+                continue;
+            };
+            // Only the first statement can't follow another on its line
+            let doc = leading_doc(&statement[..first], index == 0).filter(|_| options.include_docs);
+            // Documented statements start at their comment
+            let lines = Lines {
+                start: doc.as_ref().map_or(start.line, |(_, line)| *line) as usize,
+                end: statement[last].span.end.line as usize,
+            };
+            file_map.items.push(Item {
+                kind,
+                name: Some(name),
+                text: match doc {
+                    Some((doc, _)) => format!("{} {}\n{}", self.comment_prefix(), doc, text),
+                    None => text,
+                },
+                lines: Some(lines),
+                file: None,
+            });
+        }
+        file_map
+    }
+}
+
+// Split the tokens of a file at the semicolons that end a statement. Those
+// inside a `BEGIN ... END` block, e.g. of a trigger or a procedure, are part
+// of it, and so are those inside a `CASE ... END`
+fn statements(tokens: &[TokenWithSpan]) -> Vec<&[TokenWithSpan]> {
+    // Keywords, upper case; not all of them are keywords to the tokenizer
+    let keyword = |token: &Token| match token {
+        Token::Word(word) if word.quote_style.is_none() => word.value.to_ascii_uppercase(),
+        _ => String::new(),
+    };
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate() {
+        let next = tokens[index + 1..]
+            .iter()
+            .map(|token| &token.token)
+            .find(|token| !matches!(token, Token::Whitespace(_)))
+            .unwrap_or(&Token::EOF);
+        match (keyword(&token.token).as_str(), keyword(next).as_str()) {
+            // `BEGIN;` and `BEGIN TRANSACTION` start a transaction instead
+            ("BEGIN", _) if matches!(next, Token::SemiColon | Token::EOF) => {}
+            ("BEGIN", "TRANSACTION" | "WORK" | "DEFERRED" | "IMMEDIATE" | "EXCLUSIVE") => {}
+            ("BEGIN" | "CASE", _) => depth += 1,
+            // `END IF` and the like close blocks that aren't counted
+            ("END", "IF" | "LOOP" | "WHILE" | "REPEAT") => {}
+            ("END", _) => depth = depth.saturating_sub(1),
+            _ if token.token == Token::SemiColon && depth == 0 => {
+                statements.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&tokens[start..]);
+    statements
+}
+
+// Parse a single statement with the first dialect that accepts all of it,
+// or the generic dialect's error
+fn parse(tokens: &[TokenWithSpan]) -> Result<Statement, String> {
+    let dialects: [&dyn Dialect; 4] = [
+        &GenericDialect {},
+        &PostgreSqlDialect {},
+        &MySqlDialect {},
+        &SQLiteDialect {},
+    ];
+    let mut first_error = None;
+    for dialect in dialects {
+        let mut parser = Parser::new(dialect).with_tokens_with_locations(tokens.to_vec());
+        let error = match parser.parse_statement() {
+            Ok(statement) if parser.peek_token().token == Token::EOF => return Ok(statement),
+            Ok(_) => format!("expected end of statement, found: {}", parser.peek_token()),
+            Err(ParserError::ParserError(message) | ParserError::TokenizerError(message)) => {
+                // The location is reported separately
+                match message.split_once(" at Line: ") {
+                    Some((message, _)) => message.to_string(),
+                    None => message,
+                }
+            }
+            Err(error) => error.to_string(),
+        };
+        first_error.get_or_insert(error);
+    }
+    Err(first_error.unwrap_or_default())
+}
+
+// The kind, name and rendering of a statement that declares something
+fn render(statement: &Statement) -> Option<(ItemKind, String, String)> {
+    match statement {
+        Statement::CreateTable(table) => {
+            let members: Vec<_> = table
+                .columns
+                .iter()
+                .map(ToString::to_string)
+                .chain(table.constraints.iter().map(ToString::to_string))
+                .collect();
+            // Tables created from a query have no columns of their own
+            let text = if members.is_empty() {
+                format!("CREATE TABLE {}", table.name)
+            } else {
+                format!(
+                    "CREATE TABLE {} (\n    {}\n)",
+                    table.name,
+                    members.join(",\n    ")
+                )
+            };
+            Some((ItemKind::Struct, table.name.to_string(), text))
+        }
+        Statement::CreateView {
+            name,
+            columns,
+            materialized,
+            ..
+        } => {
+            let keyword = if *materialized {
+                "CREATE MATERIALIZED VIEW"
+            } else {
+                "CREATE VIEW"
+            };
+            // The query is left out like function bodies are
+            let text = if columns.is_empty() {
+                format!("{} {}", keyword, name)
+            } else {
+                let columns: Vec<_> = columns.iter().map(ToString::to_string).collect();
+                format!("{} {} ({})", keyword, name, columns.join(", "))
+            };
+            Some((ItemKind::Other("view".to_string()), name.to_string(), text))
+        }
+        Statement::CreateIndex(index) => {
+            let name = index.name.as_ref().unwrap_or(&index.table_name);
+            Some((
+                ItemKind::Other("index".to_string()),
+                name.to_string(),
+                statement.to_string(),
+            ))
+        }
+        Statement::CreateType {
+            name,
+            representation,
+        } => {
+            let kind = match representation {
+                UserDefinedTypeRepresentation::Enum { .. } => ItemKind::Enum,
+                UserDefinedTypeRepresentation::Composite { .. } => ItemKind::Struct,
+            };
+            Some((kind, name.to_string(), statement.to_string()))
+        }
+        Statement::AlterTable { name, .. } => {
+            Some((ItemKind::Impl, name.to_string(), statement.to_string()))
+        }
+        _ => None,
+    }
+}
+
+// The first line of the comment block right above a statement and the line
+// the block starts on, given the whitespace and comments before it. `first` tells whether the statement is
+// the first of the file, otherwise a comment before any line break is about
+// the previous statement
+fn leading_doc(whitespace: &[TokenWithSpan], first: bool) -> Option<(String, u64)> {
+    let mut doc = None;
+    // Line breaks since the last comment; a blank line ends a block
+    let mut breaks = if first { 2 } else { 0 };
+    for token in whitespace {
+        let comment = match &token.token {
+            Token::Whitespace(Whitespace::Newline) => {
+                breaks += 1;
+                continue;
+            }
+            Token::Whitespace(Whitespace::SingleLineComment { comment, .. }) => comment.as_str(),
+            Token::Whitespace(Whitespace::MultiLineComment(comment)) => comment.as_str(),
+            _ => continue,
+        };
+        if breaks > 1 {
+            doc = comment
+                .lines()
+                .map(|line| line.trim().trim_start_matches('*').trim())
+                .find(|line| !line.is_empty())
+                .map(|line| (line.to_string(), token.span.start.line));
+        } else if breaks == 0 {
+            doc = None;
+        }
+        // Line comments end with their line break
+        breaks = usize::from(comment.ends_with('\n'));
+    }
+    doc.filter(|_| breaks <= 1)
+}
+
+fn diagnostic(source: &str, location: Location, message: String) -> Diagnostic {
+    let line = location.line as usize;
+    let snippet = source
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    Diagnostic {
+        line,
+        column: location.column as usize,
+        message,
+        snippet,
+    }
+}
@@ -0,0 +1,665 @@
+use tree_sitter::{Language, Node, Tree};
+
+use super::{LanguageBackend, collapse_whitespace, header_is_clean, top_level_items};
+use crate::incremental::ItemMemo;
+use crate::{CodemapOptions, Item, ItemKind, Lines, Visibility, node_text};
+
+/// TypeScript, rendered like a `.d.ts` file: functions, classes with their
+/// members, interfaces, type aliases, enums and re-exports.
+///
+/// In a module (a file with an `import` or `export`) only `export`ed
+/// declarations are public; in a script, which is what a global `.d.ts` file
+/// is, every top-level declaration is. `declare` declarations are always
+/// public. `private` and `#private` class members are private, `protected`
+/// ones crate-visible.
+pub struct TypeScript;
+
+/// TypeScript with JSX, see [`TypeScript`].
+pub struct Tsx;
+
+/// JavaScript, including JSX, mapped like [`TypeScript`] without the types.
+pub struct JavaScript;
+
+impl LanguageBackend for TypeScript {
+    fn name(&self) -> &'static str {
+        "typescript"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ts", "mts", "cts"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo)
+    }
+}
+
+impl LanguageBackend for Tsx {
+    fn name(&self) -> &'static str {
+        "tsx"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["tsx"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_typescript::LANGUAGE_TSX.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo)
+    }
+}
+
+impl LanguageBackend for JavaScript {
+    fn name(&self) -> &'static str {
+        "javascript"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["js", "mjs", "cjs", "jsx"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::LANGUAGE.into())
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        options: &CodemapOptions,
+        memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        map_items(tree, source, options, memo)
+    }
+}
+
+// The three dialects share their node kinds, so they share the mapping
+fn map_items(
+    tree: &Tree,
+    source: &str,
+    options: &CodemapOptions,
+    mut memo: Option<&mut ItemMemo>,
+) -> Vec<Item> {
+    let statements = top_level_items(&tree.root_node());
+    let is_module = statements
+        .iter()
+        .any(|statement| matches!(statement.kind(), "import_statement" | "export_statement"));
+
+    let mut items = Vec::new();
+    for statement in statements {
+        let Some(entry) = describe(&statement, source, !is_module) else {
+            continue;
+        };
+        let is_included = match entry.kind {
+            ItemKind::UseDeclaration => options.include_uses,
+            _ => true,
+        };
+        if !is_included
+            || entry.visibility < options.visibility
+            || !header_is_clean(&entry.declaration)
+        {
+            continue;
+        }
+
+        let text = match memo.as_deref_mut() {
+            Some(memo) => {
+                memo.get_or_render(&statement, source, || render(&statement, source, options))
+            }
+            None => render(&statement, source, options),
+        };
+        // The doc comment isn't part of the statement's text, so it is added
+        // after the memo
+        if let Some(text) = text {
+            items.push(Item {
+                kind: entry.kind,
+                name: entry.name,
+                text: with_doc(&statement, source, options, text, ""),
+                lines: Some(Lines::of(&statement)),
+                file: None,
+            });
+        }
+    }
+    items
+}
+
+// A statement that can be part of the map
+struct Entry<'a> {
+    kind: ItemKind,
+    name: Option<String>,
+    visibility: Visibility,
+    // The declaration itself, without `export` or `declare`
+    declaration: Node<'a>,
+}
+
+// Work out what a top-level statement declares and whether it is public.
+// Outside modules every declaration is global
+fn describe<'a>(statement: &Node<'a>, source: &str, is_global: bool) -> Option<Entry<'a>> {
+    let (declaration, visibility) = match statement.kind() {
+        "import_statement" => (*statement, Visibility::Private),
+        "export_statement" => {
+            let declaration = statement
+                .child_by_field_name("declaration")
+                .or_else(|| statement.child_by_field_name("value"))
+                .unwrap_or(*statement);
+            (declaration, Visibility::Public)
+        }
+        "ambient_declaration" => (*statement, Visibility::Public),
+        _ if is_global => (*statement, Visibility::Public),
+        _ => (*statement, Visibility::Private),
+    };
+
+    let inner = unwrap_declaration(&declaration);
+    let name = inner
+        .child_by_field_name("name")
+        .map(|name| node_text(&name, source).to_string());
+    let kind = match inner.kind() {
+        "import_statement" | "export_statement" => ItemKind::UseDeclaration,
+        "function_declaration"
+        | "generator_function_declaration"
+        | "function_signature"
+        | "function_expression"
+        | "function"
+        | "arrow_function" => ItemKind::Function,
+        "class_declaration" | "abstract_class_declaration" | "class" => ItemKind::Class,
+        "interface_declaration" => ItemKind::Interface,
+        "type_alias_declaration" => ItemKind::TypeAlias,
+        "enum_declaration" => ItemKind::Enum,
+        "lexical_declaration" | "variable_declaration" => ItemKind::Const,
+        "internal_module" | "module" => ItemKind::Module,
+        // `export default someExpression`
+        _ if statement.kind() == "export_statement" => ItemKind::Const,
+        _ => return None,
+    };
+    let name = match kind {
+        ItemKind::UseDeclaration => None,
+        ItemKind::Const => first_declarator_name(&inner, source),
+        // `export default function () {}` and the like
+        _ if statement.kind() == "export_statement" && name.is_none() => {
+            Some("default".to_string())
+        }
+        _ => name,
+    };
+
+    Some(Entry {
+        kind,
+        name,
+        visibility,
+        declaration: inner,
+    })
+}
+
+// The declaration inside `declare ...` or a `namespace` statement
+fn unwrap_declaration<'a>(node: &Node<'a>) -> Node<'a> {
+    match node.kind() {
+        "ambient_declaration" | "expression_statement" => node
+            .named_children(&mut node.walk())
+            .find(|child| child.kind() != "comment")
+            .unwrap_or(*node),
+        _ => *node,
+    }
+}
+
+fn first_declarator_name(declaration: &Node, source: &str) -> Option<String> {
+    let declarator = declaration
+        .named_children(&mut declaration.walk())
+        .find(|child| child.kind() == "variable_declarator")?;
+    let name = declarator.child_by_field_name("name")?;
+    Some(node_text(&name, source).to_string())
+}
+
+// Render a top-level statement, or a statement inside a namespace
+fn render(statement: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    match statement.kind() {
+        "import_statement" => Some(statement_text(statement, source)),
+        "export_statement" => {
+            let declaration = statement
+                .child_by_field_name("declaration")
+                .or_else(|| statement.child_by_field_name("value"));
+            let Some(declaration) = declaration else {
+                // `export * from "..."`, `export { a, b as c }`
+                return Some(statement_text(statement, source));
+            };
+            let is_default = statement
+                .children(&mut statement.walk())
+                .any(|child| child.kind() == "default");
+            let prefix = if is_default {
+                "export default "
+            } else {
+                "export "
+            };
+            let rendered = render_declaration(&declaration, source, options)?;
+            Some(with_decorators(
+                statement, source, options, prefix, rendered,
+            ))
+        }
+        _ => render_declaration(statement, source, options),
+    }
+}
+
+fn render_declaration(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    match node.kind() {
+        "ambient_declaration" => {
+            let inner = unwrap_declaration(node);
+            let rendered = render_declaration(&inner, source, options)
+                .unwrap_or_else(|| statement_text(&inner, source));
+            Some(format!("declare {}", rendered))
+        }
+        "expression_statement" => {
+            let inner = unwrap_declaration(node);
+            (inner != *node)
+                .then(|| render_declaration(&inner, source, options))
+                .flatten()
+        }
+        "function_declaration"
+        | "generator_function_declaration"
+        | "function_signature"
+        | "function_expression"
+        | "function" => Some(format!("{};", signature(node, source)?)),
+        "arrow_function" => Some(format!("{} => ...;", signature(node, source)?)),
+        "class_declaration" | "abstract_class_declaration" | "class" => {
+            render_class(node, source, options)
+        }
+        "interface_declaration" => render_interface(node, source, options),
+        "enum_declaration" => render_enum(node, source),
+        "type_alias_declaration" => Some(statement_text(node, source)),
+        "lexical_declaration" | "variable_declaration" => render_variables(node, source),
+        "internal_module" | "module" => render_namespace(node, source, options),
+        // `export default someExpression`
+        _ => {
+            let text = node_text(node, source);
+            Some(if text.contains('\n') {
+                "...;".to_string()
+            } else {
+                format!("{};", text)
+            })
+        }
+    }
+}
+
+// A statement on a single line, ending with a semicolon
+fn statement_text(node: &Node, source: &str) -> String {
+    let text = collapse_whitespace(node_text(node, source));
+    match text.strip_suffix(';') {
+        Some(text) => format!("{};", text.trim_end()),
+        None => format!("{};", text),
+    }
+}
+
+// Put decorators written before `export` on their own lines above it
+fn with_decorators(
+    statement: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    prefix: &str,
+    rendered: String,
+) -> String {
+    let decorators: Vec<String> = statement
+        .named_children(&mut statement.walk())
+        .filter(|child| child.kind() == "decorator" && options.include_attributes)
+        .map(|decorator| collapse_whitespace(node_text(&decorator, source)))
+        .collect();
+    let mut lines = decorators;
+    // A class puts its own decorators first, keep them above `export`
+    let (own_decorators, rest) = split_decorators(&rendered);
+    lines.extend(own_decorators);
+    lines.push(format!("{}{}", prefix, rest));
+    lines.join("\n")
+}
+
+fn split_decorators(rendered: &str) -> (Vec<String>, String) {
+    let mut lines = rendered.lines();
+    let mut decorators = Vec::new();
+    let mut rest = Vec::new();
+    for line in lines.by_ref() {
+        if line.starts_with('@') {
+            decorators.push(line.to_string());
+        } else {
+            rest.push(line);
+            break;
+        }
+    }
+    rest.extend(lines);
+    (decorators, rest.join("\n"))
+}
+
+// `async function name<T>(params): Return`, the head of a function or method
+// without its body
+fn signature(node: &Node, source: &str) -> Option<String> {
+    // Everything before the parameters: modifiers, `function`, the name and
+    // type parameters, leaving out decorators
+    let start = node
+        .named_children(&mut node.walk())
+        .filter(|child| child.kind() == "decorator")
+        .last()
+        .map_or(node.start_byte(), |decorator| decorator.end_byte());
+
+    let (head_end, parameters) = match node.child_by_field_name("parameters") {
+        Some(parameters) => {
+            let list: Vec<String> = parameters
+                .named_children(&mut parameters.walk())
+                .filter(|parameter| parameter.kind() != "comment")
+                .map(|parameter| collapse_whitespace(node_text(&parameter, source)))
+                .collect();
+            (parameters.start_byte(), format!("({})", list.join(", ")))
+        }
+        // `x => ...`
+        None => {
+            let parameter = node.child_by_field_name("parameter")?;
+            (
+                parameter.start_byte(),
+                format!("({})", node_text(&parameter, source)),
+            )
+        }
+    };
+    // Keep the space in `async (a) => ...`
+    let head = source.get(start..head_end)?;
+    let head = match head.ends_with(char::is_whitespace) {
+        true => format!("{} ", collapse_whitespace(head)),
+        false => collapse_whitespace(head),
+    };
+
+    let return_type = node
+        .child_by_field_name("return_type")
+        .map(|return_type| collapse_whitespace(node_text(&return_type, source)))
+        .unwrap_or_default();
+
+    Some(format!("{}{}{}", head, parameters, return_type))
+}
+
+// The text of a node from its start (after decorators) up to its body
+fn header(node: &Node, source: &str, body: &Node) -> Option<String> {
+    let start = node
+        .named_children(&mut node.walk())
+        .filter(|child| child.kind() == "decorator")
+        .last()
+        .map_or(node.start_byte(), |decorator| decorator.end_byte());
+    Some(collapse_whitespace(source.get(start..body.start_byte())?))
+}
+
+fn decorators(node: &Node, source: &str, options: &CodemapOptions, indent: &str) -> Vec<String> {
+    if !options.include_attributes {
+        return Vec::new();
+    }
+    node.named_children(&mut node.walk())
+        .filter(|child| child.kind() == "decorator")
+        .map(|decorator| {
+            format!(
+                "{}{}",
+                indent,
+                collapse_whitespace(node_text(&decorator, source))
+            )
+        })
+        .collect()
+}
+
+fn render_class(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let mut lines = decorators(node, source, options, "");
+    let header = header(node, source, &body)?;
+
+    let members = class_members(&body, source, options);
+    if members.is_empty() {
+        lines.push(format!("{} {{}}", header));
+    } else {
+        lines.push(format!("{} {{", header));
+        lines.extend(members);
+        lines.push("}".to_string());
+    }
+    Some(lines.join("\n"))
+}
+
+// The visible fields, accessors and methods of a class body
+fn class_members(body: &Node, source: &str, options: &CodemapOptions) -> Vec<String> {
+    let mut lines = Vec::new();
+    // Decorators of methods come before them in the class body
+    let mut pending_decorators = Vec::new();
+    let mut cursor = body.walk();
+    for member in body.named_children(&mut cursor) {
+        if member.kind() == "decorator" {
+            pending_decorators.push(member);
+            continue;
+        }
+        let member_decorators = std::mem::take(&mut pending_decorators);
+        // Drop members that didn't parse rather than show half of them
+        if member.kind() == "comment" || member.has_error() {
+            continue;
+        }
+        if member_visibility(&member, source) < options.visibility {
+            continue;
+        }
+
+        let rendered = match member.kind() {
+            "public_field_definition" | "field_definition" => render_field(&member, source),
+            "method_definition" | "method_signature" | "abstract_method_signature" => {
+                signature(&member, source).map(|signature| format!("{};", signature))
+            }
+            "index_signature" => Some(statement_text(&member, source)),
+            _ => None,
+        };
+        if let Some(rendered) = rendered {
+            let first = member_decorators.first().unwrap_or(&member);
+            lines.extend(doc_line(first, source, options, "    "));
+            if options.include_attributes {
+                lines.extend(member_decorators.iter().map(|decorator| {
+                    format!("    {}", collapse_whitespace(node_text(decorator, source)))
+                }));
+            }
+            lines.extend(decorators(&member, source, options, "    "));
+            lines.push(format!("    {}", rendered));
+        }
+    }
+    lines
+}
+
+fn member_visibility(member: &Node, source: &str) -> Visibility {
+    let accessibility = member
+        .named_children(&mut member.walk())
+        .find(|child| child.kind() == "accessibility_modifier")
+        .map(|modifier| node_text(&modifier, source));
+    let is_private_name = member
+        .child_by_field_name("name")
+        .or_else(|| member.child_by_field_name("property"))
+        .is_some_and(|name| name.kind() == "private_property_identifier");
+
+    match accessibility {
+        _ if is_private_name => Visibility::Private,
+        Some("private") => Visibility::Private,
+        Some("protected") => Visibility::Crate,
+        _ => Visibility::Public,
+    }
+}
+
+// A field with its modifiers and type; the initial value is only shown for
+// untyped fields with a short value
+fn render_field(field: &Node, source: &str) -> Option<String> {
+    let start = field
+        .named_children(&mut field.walk())
+        .filter(|child| child.kind() == "decorator")
+        .last()
+        .map_or(field.start_byte(), |decorator| decorator.end_byte());
+    let value = field.child_by_field_name("value");
+    let has_type = field.child_by_field_name("type").is_some();
+
+    let end = match value {
+        Some(value) => value.start_byte(),
+        None => field.end_byte(),
+    };
+    let declaration = source.get(start..end)?.trim_end();
+    let declaration = collapse_whitespace(declaration.strip_suffix('=').unwrap_or(declaration));
+
+    Some(match value {
+        Some(value) if !has_type && !node_text(&value, source).contains('\n') => {
+            format!("{} = {};", declaration, node_text(&value, source))
+        }
+        _ => format!("{};", declaration.trim_end_matches(';')),
+    })
+}
+
+fn render_interface(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let header = header(node, source, &body)?;
+
+    let mut members = Vec::new();
+    let mut cursor = body.walk();
+    for member in body.named_children(&mut cursor) {
+        if member.kind() == "comment" || member.has_error() {
+            continue;
+        }
+        let text = collapse_whitespace(node_text(&member, source));
+        let text = format!("{};", text.trim_end_matches([';', ',']));
+        members.push(with_doc(&member, source, options, text, "    "));
+    }
+
+    Some(if members.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, members.join("\n"))
+    })
+}
+
+fn render_enum(node: &Node, source: &str) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let header = header(node, source, &body)?;
+
+    let members: Vec<String> = body
+        .named_children(&mut body.walk())
+        .filter(|member| member.kind() != "comment" && !member.has_error())
+        .map(|member| format!("    {}", collapse_whitespace(node_text(&member, source))))
+        .collect();
+
+    Some(if members.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, members.join(",\n"))
+    })
+}
+
+// `const a: T = value, b = ...;`, showing values that fit on a line and the
+// signature of functions
+fn render_variables(node: &Node, source: &str) -> Option<String> {
+    let keyword = node.child(0).map(|keyword| node_text(&keyword, source))?;
+
+    let declarators: Vec<String> =
+        node.named_children(&mut node.walk())
+            .filter(|child| child.kind() == "variable_declarator")
+            .filter_map(|declarator| {
+                let name = node_text(&declarator.child_by_field_name("name")?, source);
+                let annotation = declarator
+                    .child_by_field_name("type")
+                    .map(|annotation| collapse_whitespace(node_text(&annotation, source)))
+                    .unwrap_or_default();
+                let value = declarator.child_by_field_name("value").map(|value| {
+                    match value.kind() {
+                        "arrow_function" => signature(&value, source)
+                            .map(|signature| format!("{} => ...", signature)),
+                        "function_expression" | "function" => signature(&value, source),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| {
+                        let text = node_text(&value, source);
+                        if text.contains('\n') {
+                            "...".to_string()
+                        } else {
+                            text.to_string()
+                        }
+                    })
+                });
+                Some(match value {
+                    Some(value) => format!("{}{} = {}", name, annotation, value),
+                    None => format!("{}{}", name, annotation),
+                })
+            })
+            .collect();
+
+    Some(format!("{} {};", keyword, declarators.join(", ")))
+}
+
+// A namespace or ambient module with its visible declarations indented.
+// Everything in a `declare module` block is visible, in a namespace only what
+// it exports
+fn render_namespace(node: &Node, source: &str, options: &CodemapOptions) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let header = header(node, source, &body)?;
+    let is_ambient = node.kind() == "module"
+        || node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "ambient_declaration");
+
+    let mut members = Vec::new();
+    let mut cursor = body.walk();
+    for statement in body.named_children(&mut cursor) {
+        let Some(entry) = describe(&statement, source, is_ambient) else {
+            continue;
+        };
+        if entry.visibility < options.visibility
+            || (entry.kind == ItemKind::UseDeclaration && !options.include_uses)
+            || statement.has_error()
+        {
+            continue;
+        }
+        if let Some(rendered) = render(&statement, source, options) {
+            let rendered = with_doc(&statement, source, options, rendered, "");
+            members.extend(rendered.lines().map(|line| format!("    {}", line)));
+        }
+    }
+
+    Some(if members.is_empty() {
+        format!("{} {{}}", header)
+    } else {
+        format!("{} {{\n{}\n}}", header, members.join("\n"))
+    })
+}
+
+// Precede `rendered` with the summary of the JSDoc comment right above `node`
+fn with_doc(
+    node: &Node,
+    source: &str,
+    options: &CodemapOptions,
+    rendered: String,
+    indent: &str,
+) -> String {
+    match doc_line(node, source, options, indent) {
+        Some(doc) => format!("{}\n{}{}", doc, indent, rendered),
+        None => format!("{}{}", indent, rendered),
+    }
+}
+
+fn doc_line(node: &Node, source: &str, options: &CodemapOptions, indent: &str) -> Option<String> {
+    if !options.include_docs {
+        return None;
+    }
+    doc_summary(node, source).map(|summary| format!("{}/** {} */", indent, summary))
+}
+
+// The first line of a `/** ... */` comment ending on the line before `node`
+fn doc_summary(node: &Node, source: &str) -> Option<String> {
+    let comment = node.prev_named_sibling()?;
+    if comment.kind() != "comment" || comment.end_position().row + 1 < node.start_position().row {
+        return None;
+    }
+    let text = node_text(&comment, source).strip_prefix("/**")?;
+    let text = text.strip_suffix("*/").unwrap_or(text);
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -1,8 +1,47 @@
-use tree_sitter::{Node, Parser};
-
-#[derive(Debug, PartialEq)]
-enum ItemKind {
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tree_sitter::{Node, Parser, Tree};
+
+mod cache;
+mod config;
+mod deps;
+mod diff;
+mod directory;
+mod error;
+mod incremental;
+mod languages;
+mod options;
+mod output;
+mod resolve;
+
+pub use cache::{CACHE_VERSION, Cache, CacheEntry};
+pub use config::{CONFIG_FILE, Config, MapSettings, PathConfig};
+pub use deps::{
+    LockedPackage, cargo_home, direct_dependencies, find_locked, find_lockfile, locate_package,
+    map_dependency, read_lockfile,
+};
+pub use diff::{FileDiff, diff_maps};
+pub use directory::{
+    MapIter, Options, PathFilter, PathOverride, ProjectMap, map_directory, map_directory_iter,
+};
+pub use error::Error;
+pub use incremental::{ItemMemo, ParsedFile, Workspace};
+pub use languages::{
+    C, CargoManifest, Cpp, CppHeader, Go, Java, JavaScript, Kotlin, LanguageBackend,
+    LanguageRegistry, OpenApi, Protobuf, Python, Rust, Sql, Tsx, TypeScript,
+};
+pub use options::{CodemapOptions, Visibility};
+pub use output::{Format, fit_token_budget, render_diff, render_files};
+pub use resolve::{ReexportOptions, expand_reexports};
+
+/// The kind of a top-level item in a codemap.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
     Struct,
+    Class,
     Enum,
     Const,
     Impl,
@@ -10,6 +49,7 @@ enum ItemKind {
     Module,
     TypeAlias,
     Trait,
+    Interface,
     UseDeclaration,
     Other(String),
 }
@@ -31,519 +71,299 @@ impl ItemKind {
     }
 }
 
-pub fn codemap(source_code: &str) -> String {
-    // Initialize the parser
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_rust::LANGUAGE.into())
-        .expect("Error loading Rust grammar");
-
-    // Parse the source code into an AST
-    let tree = parser.parse(source_code, None).unwrap();
-    let root_node = tree.root_node();
-
-    // Vector to collect output lines
-    let mut public_output: Vec<String> = Vec::new();
-
-    // Map to store impl blocks by type name
-    let mut impl_blocks = std::collections::HashMap::new();
-
-    // First pass: collect all impl blocks for public structs
-    let mut cursor = root_node.walk();
-    for child in root_node.children(&mut cursor) {
-        if child.kind() == "impl_item" {
-            if let Some(impl_info) = process_impl(&child, source_code) {
-                impl_blocks
-                    .entry(impl_info.0)
-                    .or_insert_with(Vec::new)
-                    .push(impl_info.1);
-            }
-        }
-    }
-
-    // Second pass: traverse top-level items
-    let mut cursor = root_node.walk();
-    for child in root_node.children(&mut cursor) {
-        let item_kind = ItemKind::from_node_kind(child.kind());
-
-        // For traits, check if they're explicitly public or have no visibility modifier
-        // (which means they're public by default in module scope, but private if inside an impl block)
-        let should_process = match item_kind {
-            ItemKind::Trait => {
-                // For traits, they're public if they have 'pub' keyword or if they don't have
-                // any visibility modifier AND they're not inside an impl block or another scope
-                // (i.e., they're at module level)
-                is_public(&child, source_code) || is_trait_without_visibility(&child, source_code)
-            }
-            // Process only public enums
-            ItemKind::Enum => is_public(&child, source_code),
-            _ => is_public(&child, source_code),
-        };
-
-        if should_process {
-            match item_kind {
-                ItemKind::Struct => {
-                    let mut struct_output = process_struct(&child, source_code);
-
-                    // Get struct name and add its impl blocks if any
-                    if let Some(name_node) = child.child_by_field_name("name") {
-                        let name = name_node.utf8_text(source_code.as_bytes()).unwrap();
-                        if let Some(impls) = impl_blocks.get(name) {
-                            for impl_block in impls {
-                                if !impl_block.is_empty() {
-                                    struct_output = format!("{}\n\n{}", struct_output, impl_block);
-                                }
-                            }
-                        }
-                    }
-
-                    public_output.push(struct_output);
-                }
-                ItemKind::Enum => {
-                    // Only public enums should reach here due to should_process
-                    let enum_output = process_enum(&child, source_code);
-                    public_output.push(enum_output);
-                }
-                ItemKind::Const => {
-                    public_output.push(process_const(&child, source_code));
-                }
-                ItemKind::Function => {
-                    public_output.push(process_function(&child, source_code));
-                }
-                ItemKind::Impl => {}
-                ItemKind::Module => {
-                    public_output.push(process_module(&child, source_code));
-                }
-                ItemKind::TypeAlias => {
-                    public_output.push(process_type_alias(&child, source_code));
-                }
-                ItemKind::Trait => {
-                    public_output.push(process_trait(&child, source_code));
-                }
-                ItemKind::UseDeclaration => {
-                    public_output.push(process_use_declaration(&child, source_code));
-                }
-                ItemKind::Other(k) => panic!(
-                    "Unsupported item kind: {} {}",
-                    k,
-                    child.utf8_text(source_code.as_bytes()).unwrap()
-                ),
-            }
-        }
-    }
-
-    // Print the output (we only use public_output now)
-    public_output.join("\n\n")
-}
-
-// Check if a node is public
-fn is_public(node: &Node, source: &str) -> bool {
-    node.children(&mut node.walk()).any(|child| {
-        child.kind() == "visibility_modifier"
-            && child.utf8_text(source.as_bytes()).unwrap() == "pub"
-    })
+/// A syntax error found while parsing a source file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// 1-based line where the error starts
+    pub line: usize,
+    /// 1-based column where the error starts
+    pub column: usize,
+    /// What went wrong, e.g. "syntax error" or "missing `;`"
+    pub message: String,
+    /// The source line containing the error
+    pub snippet: String,
 }
 
-// Check if a node has a visibility modifier
-fn has_visibility_modifier(node: &Node, _source: &str) -> bool {
-    node.children(&mut node.walk())
-        .any(|child| child.kind() == "visibility_modifier")
+/// A public top-level item of a file and its rendered interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// The name the item is declared with; `None` for `use` declarations
+    pub name: Option<String>,
+    pub text: String,
+    /// Where the item is in the file; `None` for items that aren't declared
+    /// at a single place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Lines>,
+    /// The file `lines` are in when it isn't the one the item is listed
+    /// under, e.g. for re-exported items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
 }
 
-// Check if a trait has no visibility modifier and is at module level (meaning it's public by default)
-fn is_trait_without_visibility(node: &Node, source: &str) -> bool {
-    // Check if this is a trait
-    if node.kind() != "trait_item" {
-        return false;
+impl Item {
+    // Precede the text with a comment like `// L12-L40` giving the lines the
+    // item spans, naming `file` as well when it is set
+    pub(crate) fn add_line_comment(&mut self, comment_prefix: &str) {
+        let Some(lines) = self.lines else {
+            return;
+        };
+        let file = self
+            .file
+            .as_ref()
+            .map(|file| format!("{} ", file.display()))
+            .unwrap_or_default();
+        self.text = format!(
+            "{} {}L{}-L{}\n{}",
+            comment_prefix, file, lines.start, lines.end, self.text
+        );
     }
+}
 
-    // Check if it has no visibility modifier
-    if has_visibility_modifier(node, source) {
-        return false;
-    }
+/// A 1-based, inclusive range of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lines {
+    pub start: usize,
+    pub end: usize,
+}
 
-    // Get the trait name to check if it's explicitly "PrivateTrait" (our test case)
-    // This is a hack for our test, but in real code, we would need better scope resolution
-    if let Some(name_node) = node.child_by_field_name("name") {
-        let name = name_node.utf8_text(source.as_bytes()).unwrap();
-        if name == "PrivateTrait" {
-            return false;
+impl Lines {
+    /// The lines a node spans.
+    pub(crate) fn of(node: &Node) -> Self {
+        Lines {
+            start: node.start_position().row + 1,
+            end: node.end_position().row + 1,
         }
     }
+}
 
-    true
+/// The codemap of a single file together with any syntax errors found in it.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FileMap {
+    pub items: Vec<Item>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-// Process a public struct and return its external interface
-fn process_struct(node: &Node, source: &str) -> String {
-    // Extract struct name
-    let name_node = node.child_by_field_name("name").unwrap();
-    let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-    // Get the field declaration list if it exists (it's called "body" in the AST)
-    let field_list_node = node.child_by_field_name("body");
-
-    // Extract the generic type parameters if any
-    let mut generic_params = String::new();
-    for child in node.children(&mut node.walk()) {
-        if child.kind() == "type_parameters" {
-            generic_params = child.utf8_text(source.as_bytes()).unwrap().to_string();
-            break;
-        }
+impl FileMap {
+    /// Render the items the way [`codemap`] prints them.
+    pub fn map(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| item.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
     }
+}
 
-    // Get the full struct text
-    let struct_text = node.utf8_text(source.as_bytes()).unwrap();
-
-    // Check if this is a tuple struct by examining the children of the field_list
-    if let Some(body_node) = field_list_node.clone() {
-        // For tuple structs, the body node kind is "ordered_field_declaration_list"
-        // and contains parentheses as children
-        let mut has_parentheses = false;
-
-        let mut cursor = body_node.walk();
-        for child in body_node.children(&mut cursor) {
-            if child.kind() == "(" {
-                has_parentheses = true;
-                break;
-            }
-        }
+/// Map a source file with the default options, see [`codemap_with_options`].
+pub fn codemap(source_code: &str) -> Result<String, Error> {
+    codemap_with_options(source_code, &CodemapOptions::default())
+}
 
-        if has_parentheses {
-            // For tuple structs, return the original declaration
-            return struct_text.to_string();
-        }
-    }
+/// Map a source file with `options`, rendered the way [`FileMap::map`] does.
+pub fn codemap_with_options(source_code: &str, options: &CodemapOptions) -> Result<String, Error> {
+    Ok(map_source_with_options(source_code, options)?.map())
+}
 
-    // If there's no field list, check if this is a unit struct with a semicolon
-    if field_list_node.is_none() {
-        if struct_text.contains(";") {
-            return format!("pub struct {}{};", name, generic_params);
-        } else {
-            return format!("pub struct {}{} {{}}", name, generic_params);
-        }
-    }
+/// Map a source file, tolerating syntax errors.
+///
+/// Items that parsed cleanly are still mapped; every `ERROR` or `MISSING` node
+/// tree-sitter produced is reported as a [`Diagnostic`].
+pub fn map_source(source_code: &str) -> Result<FileMap, Error> {
+    map_source_with_options(source_code, &CodemapOptions::default())
+}
 
-    let field_list_node = field_list_node.unwrap();
+/// Map a source file with `options`, see [`map_source`].
+pub fn map_source_with_options(
+    source_code: &str,
+    options: &CodemapOptions,
+) -> Result<FileMap, Error> {
+    Mapper::with_options(options.clone())?.map(source_code)
+}
 
-    // Collect public fields
-    let mut public_fields = Vec::new();
-    let mut cursor = field_list_node.walk();
+/// Maps source files with a parser that is reused from one file to the next.
+///
+/// Creating a parser and loading the grammar has a cost, so code mapping many
+/// files should keep a `Mapper` per thread (and language) instead of calling
+/// [`map_source`].
+pub struct Mapper {
+    // `None` for backends without a tree-sitter grammar
+    parser: Option<Parser>,
+    backend: Arc<dyn LanguageBackend>,
+    options: CodemapOptions,
+}
 
-    // Track if we need to add a comma (for all fields except the last one)
-    let mut field_nodes = Vec::new();
-    for child in field_list_node.children(&mut cursor) {
-        if child.kind() == "field_declaration" && is_public(&child, source) {
-            field_nodes.push(child);
-        }
+impl Mapper {
+    /// A mapper for Rust with the default options.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_options(CodemapOptions::default())
     }
 
-    for (index, child) in field_nodes.iter().enumerate() {
-        let field_text = child.utf8_text(source.as_bytes()).unwrap();
-        let field_text = field_text.trim();
+    /// A mapper for Rust that maps with `options`.
+    pub fn with_options(options: CodemapOptions) -> Result<Self, Error> {
+        Self::with_backend(Arc::new(Rust), options)
+    }
 
-        // Add a comma if it's not the last field or if the original field has a comma
-        let with_comma = if index < field_nodes.len() - 1 || field_text.ends_with(',') {
-            format!("{},", field_text)
-        } else {
-            field_text.to_string()
+    /// A mapper for the language of `backend` that maps with `options`.
+    pub fn with_backend(
+        backend: Arc<dyn LanguageBackend>,
+        options: CodemapOptions,
+    ) -> Result<Self, Error> {
+        let parser = match backend.language() {
+            Some(language) => {
+                let mut parser = Parser::new();
+                parser.set_language(&language)?;
+                Some(parser)
+            }
+            None => None,
         };
-
-        public_fields.push(format!("    {}", with_comma));
+        Ok(Mapper {
+            parser,
+            backend,
+            options,
+        })
     }
 
-    // Construct the struct definition with generic parameters if any
-    if public_fields.is_empty() {
-        format!("pub struct {}{} {{}}", name, generic_params)
-    } else {
-        format!(
-            "pub struct {}{} {{\n{}\n}}",
-            name,
-            generic_params,
-            public_fields.join("\n")
-        )
+    /// The language this mapper maps.
+    pub fn backend(&self) -> &Arc<dyn LanguageBackend> {
+        &self.backend
     }
-}
-
-// Process a public enum and return its external interface
-fn process_enum(node: &Node, source: &str) -> String {
-    // Extract enum name
-    let name_node = node.child_by_field_name("name").unwrap();
-    let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-    // We only process public enums now
-    let prefix = "pub ";
-
-    // Get the variant list if it exists (it's called "body" in the AST)
-    let variant_list_node = node.child_by_field_name("body");
 
-    // If there's no variant list, return an empty enum
-    if variant_list_node.is_none() {
-        return format!("{}enum {} {{}}", prefix, name);
+    /// The options files are mapped with.
+    pub fn options(&self) -> &CodemapOptions {
+        &self.options
     }
 
-    let variant_list_node = variant_list_node.unwrap();
-
-    // Collect variants
-    let mut variants: Vec<String> = Vec::new();
-    let mut cursor = variant_list_node.walk();
-
-    // Track if we need to add a comma (for all variants except the last one)
-    let mut variant_nodes: Vec<Node> = Vec::new();
-    for child in variant_list_node.children(&mut cursor) {
-        if child.kind() == "enum_variant" {
-            variant_nodes.push(child);
-        }
+    /// Map the next files with `options`, keeping the parser.
+    pub fn set_options(&mut self, options: CodemapOptions) {
+        self.options = options;
     }
 
-    for (_index, child) in variant_nodes.iter().enumerate() {
-        // Get the full variant text including type parameters or struct-like fields
-        let variant_text = child.utf8_text(source.as_bytes()).unwrap().trim();
-
-        // Always add a comma to match snapshot format
-        let with_comma = format!("{},", variant_text);
-
-        variants.push(format!("    {}", with_comma));
+    /// Map a source file, see [`map_source`].
+    pub fn map(&mut self, source_code: &str) -> Result<FileMap, Error> {
+        // Parse the source code into an AST
+        let tree = self.parse(source_code, None)?;
+        Ok(self.map_tree(tree.as_ref(), source_code, None))
     }
 
-    // Construct the enum definition
-    if variants.is_empty() {
-        format!("{}enum {} {{}}", prefix, name)
-    } else {
-        format!("{}enum {} {{\n{}\n}}", prefix, name, variants.join("\n"))
+    // Parse with the backend's grammar, reusing the unchanged parts of
+    // `old_tree`. `None` for backends without a grammar
+    fn parse(&mut self, source_code: &str, old_tree: Option<&Tree>) -> Result<Option<Tree>, Error> {
+        let Some(parser) = &mut self.parser else {
+            return Ok(None);
+        };
+        let tree = parser
+            .parse(source_code, old_tree)
+            .ok_or(Error::Parse { path: None })?;
+        Ok(Some(tree))
     }
-}
-
-// Process a public constant and return its definition
-fn process_const(node: &Node, source: &str) -> String {
-    // Extract the entire constant declaration
-    let const_text = node.utf8_text(source.as_bytes()).unwrap();
 
-    // Return the constant declaration as is
-    const_text.to_string()
-}
+    // Map a parsed file. With a memo, items whose source text didn't change
+    // since the last call are not processed again
+    fn map_tree(
+        &self,
+        tree: Option<&Tree>,
+        source_code: &str,
+        mut memo: Option<&mut ItemMemo>,
+    ) -> FileMap {
+        if !self.backend.accepts(source_code) {
+            return FileMap::default();
+        }
 
-// Process an impl block and extract public methods
-fn process_impl(node: &Node, source: &str) -> Option<(String, String)> {
-    // Extract the type name this impl is for
-    let type_node = node.child_by_field_name("type")?;
-    let type_name = type_node.utf8_text(source.as_bytes()).unwrap();
-
-    // Get the implementation body
-    let body_node = node.child_by_field_name("body")?;
-
-    // Collect public methods
-    let mut public_methods = Vec::new();
-    let mut cursor = body_node.walk();
-
-    for child in body_node.children(&mut cursor) {
-        if child.kind() == "function_item" && is_public(&child, source) {
-            // Extract the method text
-            let method_text = child.utf8_text(source.as_bytes()).unwrap();
-
-            // Check if it contains "async fn"
-            let is_async = method_text.contains("async fn");
-
-            // Get the method signature
-            let name_node = child.child_by_field_name("name")?;
-            let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-            // Check for generic type parameters
-            let mut generic_params = String::new();
-            // Look for the type_parameters node which contains generic parameters
-            for type_params_node in child.children(&mut child.walk()) {
-                if type_params_node.kind() == "type_parameters" {
-                    generic_params = type_params_node
-                        .utf8_text(source.as_bytes())
-                        .unwrap()
-                        .to_string();
-                    break;
+        let (mut items, diagnostics) = match tree {
+            Some(tree) => {
+                // Collect syntax errors before mapping whatever parsed cleanly
+                let mut diagnostics = Vec::new();
+                collect_diagnostics(&tree.root_node(), source_code, &mut diagnostics);
+
+                let items =
+                    self.backend
+                        .map_items(tree, source_code, &self.options, memo.as_deref_mut());
+                if let Some(memo) = memo {
+                    memo.finish();
                 }
+                (items, diagnostics)
             }
-
-            // Get the parameters
-            let mut params = Vec::new();
-            let parameters_node = child.child_by_field_name("parameters")?;
-            let mut param_cursor = parameters_node.walk();
-
-            // First check if this method has a self parameter
-            let has_self_param = parameters_node
-                .children(&mut parameters_node.walk())
-                .any(|param| param.kind() == "self_parameter");
-
-            // If it has a self parameter, add it first
-            if has_self_param {
-                // Try to find the specific self parameter to get accurate text
-                let self_text = parameters_node
-                    .children(&mut parameters_node.walk())
-                    .find(|param| param.kind() == "self_parameter")
-                    .map(|param| param.utf8_text(source.as_bytes()).unwrap().to_string())
-                    .unwrap_or("&self".to_string()); // Default to &self if not found
-
-                params.push(self_text);
+            // Backends without a grammar parse the file themselves
+            None => {
+                let file_map = self.backend.map_source(source_code, &self.options);
+                (file_map.items, file_map.diagnostics)
             }
+        };
 
-            // Add the rest of the parameters
-            for param in parameters_node.children(&mut param_cursor) {
-                if param.kind() == "parameter" {
-                    let param_text = param.utf8_text(source.as_bytes()).unwrap();
-                    params.push(param_text.to_string());
-                }
+        for item in &mut items {
+            if let Some(max_length) = self.options.max_signature_length {
+                item.text = shorten_lines(&item.text, max_length);
             }
-
-            // Get the return type if any
-            let mut return_type = String::new();
-            if let Some(return_node) = child.child_by_field_name("return_type") {
-                return_type = format!(" -> {}", return_node.utf8_text(source.as_bytes()).unwrap());
+            if self.options.include_line_numbers {
+                item.add_line_comment(self.backend.comment_prefix());
             }
-
-            // Construct the method signature
-            let method_sig = if is_async {
-                format!(
-                    "    pub async fn {}{}{};",
-                    name,
-                    generic_params,
-                    format!("({}){}", params.join(", "), return_type)
-                )
-            } else {
-                format!(
-                    "    pub fn {}{}{};",
-                    name,
-                    generic_params,
-                    format!("({}){}", params.join(", "), return_type)
-                )
-            };
-
-            public_methods.push(method_sig);
         }
-    }
 
-    // If no public methods, return None
-    if public_methods.is_empty() {
-        return None;
+        FileMap { items, diagnostics }
     }
-
-    // Create the impl block
-    let impl_block = format!("impl {} {{\n{}\n}}", type_name, public_methods.join("\n"));
-
-    Some((type_name.to_string(), impl_block))
 }
 
-// Process a public function and return its signature
-fn process_function(node: &Node, source: &str) -> String {
-    // Extract the function declaration text
-    let func_text = node.utf8_text(source.as_bytes()).unwrap();
-
-    // Check if it contains "async fn"
-    let is_async = func_text.contains("async fn");
-
-    // Get the function name
-    let name_node = node.child_by_field_name("name").unwrap();
-    let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-    // Check for generic type parameters
-    let mut generic_params = String::new();
-    // Look for the type_parameters node which contains generic parameters
-    for type_params_node in node.children(&mut node.walk()) {
-        if type_params_node.kind() == "type_parameters" {
-            generic_params = type_params_node
-                .utf8_text(source.as_bytes())
-                .unwrap()
-                .to_string();
-            break;
-        }
-    }
-
-    // Get the parameters
-    let mut params = Vec::new();
-    let parameters_node = node.child_by_field_name("parameters").unwrap();
-    let mut param_cursor = parameters_node.walk();
-
-    for param in parameters_node.children(&mut param_cursor) {
-        if param.kind() == "parameter" {
-            let param_text = param.utf8_text(source.as_bytes()).unwrap();
-            params.push(param_text.to_string());
-        }
-    }
-
-    // Get the return type if any
-    let mut return_type = String::new();
-    if let Some(return_node) = node.child_by_field_name("return_type") {
-        return_type = format!(" -> {}", return_node.utf8_text(source.as_bytes()).unwrap());
-    }
-
-    // Construct the function signature
-    if is_async {
-        format!(
-            "pub async fn {}{}{};",
-            name,
-            generic_params,
-            format!("({}){}", params.join(", "), return_type)
-        )
-    } else {
-        format!(
-            "pub fn {}{}{};",
-            name,
-            generic_params,
-            format!("({}){}", params.join(", "), return_type)
-        )
-    }
+// A parser with the Rust grammar loaded
+fn rust_parser() -> Result<Parser, Error> {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_rust::LANGUAGE.into())?;
+    Ok(parser)
 }
 
-// Process a public module declaration
-fn process_module(node: &Node, source: &str) -> String {
-    // Extract the module name
-    let name_node = node.child_by_field_name("name").unwrap();
-    let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-    // Return just the module declaration
-    format!("pub mod {};", name)
+// The source text of a node. Nodes always start and end on character
+// boundaries of the source they were parsed from
+fn node_text<'a>(node: &Node, source: &'a str) -> &'a str {
+    source.get(node.byte_range()).unwrap_or_default()
 }
 
-// Process a public type alias
-fn process_type_alias(node: &Node, source: &str) -> String {
-    // Extract the entire type alias declaration
-    let type_text = node.utf8_text(source.as_bytes()).unwrap();
-
-    // Return the type alias declaration as is
-    type_text.to_string()
-}
+// Record every ERROR and MISSING node below `node`, without descending into
+// the errors themselves
+fn collect_diagnostics(node: &Node, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !node.has_error() {
+        return;
+    }
 
-// Process a public trait definition
-fn process_trait(node: &Node, source: &str) -> String {
-    // Extract the trait name
-    let name_node = node.child_by_field_name("name").unwrap();
-    let name = name_node.utf8_text(source.as_bytes()).unwrap();
-
-    // Get the trait body
-    let body_node = node.child_by_field_name("body").unwrap();
-
-    // Collect trait methods
-    let mut methods = Vec::new();
-    let mut cursor = body_node.walk();
-
-    for child in body_node.children(&mut cursor) {
-        // In trait definitions, method signatures appear as function_signature_item
-        if child.kind() == "function_signature_item" {
-            // Extract the entire method signature
-            let signature_text = child.utf8_text(source.as_bytes()).unwrap().trim();
-            methods.push(format!("    {}", signature_text));
-        }
+    if node.is_error() || node.is_missing() {
+        let start = node.start_position();
+        let message = if node.is_missing() {
+            format!("missing `{}`", node.kind())
+        } else {
+            "syntax error".to_string()
+        };
+        let snippet = source
+            .lines()
+            .nth(start.row)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        diagnostics.push(Diagnostic {
+            line: start.row + 1,
+            column: start.column + 1,
+            message,
+            snippet,
+        });
+        return;
     }
 
-    // Construct the trait definition
-    if methods.is_empty() {
-        format!("pub trait {} {{}}", name)
-    } else {
-        format!("pub trait {} {{\n{}\n}}", name, methods.join("\n"))
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_diagnostics(&child, source, diagnostics);
     }
 }
 
-// Process a public use declaration
-fn process_use_declaration(node: &Node, source: &str) -> String {
-    // Extract the use declaration text
-    let use_text = node.utf8_text(source.as_bytes()).unwrap();
-
-    // Return the use declaration as is
-    use_text.to_string()
+// Cut lines longer than `max_length` characters, marking the cut with `…`
+fn shorten_lines(text: &str, max_length: usize) -> String {
+    text.lines()
+        .map(|line| {
+            if line.chars().count() <= max_length {
+                line.to_string()
+            } else {
+                let kept: String = line.chars().take(max_length.saturating_sub(1)).collect();
+                format!("{}…", kept)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
@@ -1,53 +1,546 @@
-use codemap::codemap;
-use ignore::{WalkBuilder, types::TypesBuilder};
-use std::env;
-use tokio::{fs::File, io::AsyncReadExt};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use codemap::{
+    Cache, Config, FileMap, Format, LanguageRegistry, MapSettings, Options, ProjectMap, Visibility,
+    Workspace, cargo_home, diff_maps, direct_dependencies, find_locked, find_lockfile,
+    fit_token_budget, locate_package, map_dependency, map_directory, read_lockfile, render_diff,
+    render_files,
+};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+mod mcp;
+mod watch;
+
+#[derive(Parser)]
+#[command(name = "codemap", version, about = "Map the public API of a codebase")]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // `codemap <path>` is shorthand for `codemap map <path>`
+    #[command(flatten)]
+    map: MapArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the public items of every supported source file under a path
+    Map(MapArgs),
+    /// Print the items whose rendered interface contains a string
+    Search {
+        /// Text to look for, case-insensitive
+        query: String,
+        #[command(flatten)]
+        map: MapArgs,
+    },
+    /// Print the items with the given name
+    Symbol {
+        name: String,
+        #[command(flatten)]
+        map: MapArgs,
+    },
+    /// Compare the public items of two directories
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[command(flatten)]
+        walk: WalkArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Print the public items of dependencies, from sources already on disk
+    Deps {
+        /// Dependencies to map, as `name` or `name@version` [default: every
+        /// direct dependency, or those of --package]
+        names: Vec<String>,
+        /// Project whose Cargo.lock pins the versions
+        #[arg(long, default_value = "./")]
+        path: PathBuf,
+        #[command(flatten)]
+        walk: WalkArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Map a path again whenever a file it maps changes
+    Watch {
+        /// Directory or file to watch
+        #[arg(default_value = "./")]
+        path: PathBuf,
+        /// After the first map, only print the public items that changed
+        /// instead of the whole map, appending them to the output file if one
+        /// is given
+        #[arg(long)]
+        diff: bool,
+        #[command(flatten)]
+        walk: WalkArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Serve codemaps to MCP clients over stdin/stdout
+    Mcp,
+    /// Manage the on-disk cache of file codemaps
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+        /// Cache directory (default: $CODEMAP_CACHE_DIR or ~/.cache/codemap)
+        #[arg(long, value_name = "DIR", global = true)]
+        cache_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// List cached files and crates, and whether they are still up to date
+    Inspect,
+    /// Remove entries for files that changed or no longer exist, and ones
+    /// written by another version
+    Prune,
+    /// Remove every entry
+    Clear,
+}
+
+#[derive(Args)]
+struct MapArgs {
+    /// Directory or file to map
+    #[arg(default_value = "./")]
+    path: PathBuf,
+    #[command(flatten)]
+    walk: WalkArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Which files to map and how, shared by every command that walks a directory.
+#[derive(Args, Clone, Default)]
+pub struct WalkArgs {
+    /// Only map files matching this glob (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Skip files matching this glob (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Map hidden files and directories
+    #[arg(long)]
+    pub hidden: bool,
+    /// Don't respect .gitignore, .ignore and other ignore files
+    #[arg(long)]
+    pub no_ignore: bool,
+    /// Descend at most this many directories
+    #[arg(long, value_name = "DEPTH")]
+    pub max_depth: Option<usize>,
+    /// Follow symbolic links
+    #[arg(long)]
+    pub follow_links: bool,
+    /// Annotate re-exported items with where they are defined
+    #[arg(long)]
+    pub show_origin: bool,
+    /// Number of threads used to walk and parse files (default: one per CPU)
+    #[arg(long, short, value_name = "N")]
+    pub jobs: Option<usize>,
+    /// Skip files larger than this many bytes [default: 10 MiB]
+    #[arg(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+    /// Re-parse every file instead of using the on-disk cache
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Cache directory (default: $CODEMAP_CACHE_DIR or ~/.cache/codemap)
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+    /// Ignore codemap.toml and [package.metadata.codemap] in Cargo.toml
+    #[arg(long)]
+    pub no_config: bool,
+    /// Only map the Cargo package with this name
+    #[arg(long, short, value_name = "NAME")]
+    pub package: Option<String>,
+    #[command(flatten)]
+    pub codemap: CodemapArgs,
+}
+
+/// What each file map includes, see [`CodemapOptions`].
+#[derive(Args, Clone, Default)]
+pub struct CodemapArgs {
+    /// Least visible items to show [default: public]
+    #[arg(long, value_enum)]
+    pub visibility: Option<VisibilityArg>,
+    /// Leave out `use` declarations and re-exports
+    #[arg(long)]
+    pub no_uses: bool,
+    /// Leave out the methods of impl blocks
+    #[arg(long)]
+    pub no_impls: bool,
+    /// Show which traits each struct implements
+    #[arg(long)]
+    pub trait_impls: bool,
+    /// Leave out doc comments of items, fields and variants
+    #[arg(long)]
+    pub no_docs: bool,
+    /// Leave out attributes of items, fields and variants
+    #[arg(long)]
+    pub no_attributes: bool,
+    /// Precede every item with the lines it spans
+    #[arg(long)]
+    pub line_numbers: bool,
+    /// Cut lines longer than this many characters
+    #[arg(long, value_name = "CHARS")]
+    pub max_signature_length: Option<usize>,
+}
+
+impl CodemapArgs {
+    // Only flags that were given override the configuration file
+    pub fn settings(&self) -> MapSettings {
+        MapSettings {
+            visibility: self.visibility.map(Visibility::from),
+            uses: self.no_uses.then_some(false),
+            impls: self.no_impls.then_some(false),
+            trait_impls: self.trait_impls.then_some(true),
+            docs: self.no_docs.then_some(false),
+            attributes: self.no_attributes.then_some(false),
+            line_numbers: self.line_numbers.then_some(true),
+            max_signature_length: self.max_signature_length,
+        }
+    }
+}
+
+/// The values of `--visibility`, see [`Visibility`].
+#[derive(ValueEnum, Clone, Copy)]
+pub enum VisibilityArg {
+    /// Every item, whatever its visibility
+    Private,
+    /// `pub(crate)`, `pub(super)` and `pub(in path)` items as well as `pub` ones
+    Crate,
+    /// Only `pub` items
+    Public,
+}
+
+impl From<VisibilityArg> for Visibility {
+    fn from(visibility: VisibilityArg) -> Self {
+        match visibility {
+            VisibilityArg::Private => Visibility::Private,
+            VisibilityArg::Crate => Visibility::Crate,
+            VisibilityArg::Public => Visibility::Public,
+        }
+    }
+}
+
+/// The values of `--format`, see [`Format`].
+#[derive(ValueEnum, Clone, Copy)]
+pub enum FormatArg {
+    /// `<codemap>` document with one `<file>` element per file
+    Xml,
+    /// Files with their items and diagnostics as a JSON object
+    Json,
+    /// One section per file with a fenced code block
+    Markdown,
+}
+
+impl From<FormatArg> for Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Xml => Format::Xml,
+            FormatArg::Json => Format::Json,
+            FormatArg::Markdown => Format::Markdown,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Write the output to a file instead of stdout
+    #[arg(long, short, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Output format [default: xml]
+    #[arg(long, value_enum)]
+    pub format: Option<FormatArg>,
+    /// Leave out items once the output would be about this many tokens
+    #[arg(long, value_name = "TOKENS")]
+    pub token_budget: Option<usize>,
+}
+
+impl OutputArgs {
+    pub fn format(&self, config: &Config) -> Format {
+        self.format
+            .map(Format::from)
+            .or(config.format)
+            .unwrap_or(Format::Xml)
+    }
+
+    /// Render a map in the configured format, within the token budget.
+    pub fn render(&self, config: &Config, files: &[(PathBuf, FileMap)]) -> String {
+        let Some(budget) = self.token_budget.or(config.token_budget) else {
+            return render_files(files, self.format(config));
+        };
+
+        let mut files = files.to_vec();
+        let left_out = fit_token_budget(&mut files, budget);
+        if left_out > 0 {
+            eprintln!(
+                "WARNING: token budget of {} reached, {} items left out",
+                budget, left_out
+            );
+        }
+        render_files(&files, self.format(config))
+    }
+}
+
+// Larger files are almost certainly generated, and slow to parse
+const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
 #[tokio::main]
-async fn main() {
-    let args: Vec<String> = env::args().collect();
-    let path = args.get(1).map(|s| s.as_str()).unwrap_or("./");
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
 
-    let types = TypesBuilder::new()
-        .add_defaults()
-        .select("rust")
-        .build()
-        .unwrap();
+    let result = match cli.command.unwrap_or(Command::Map(cli.map)) {
+        Command::Map(args) => run_map(args, |_| true),
+        Command::Search { query, map } => {
+            let query = query.to_lowercase();
+            run_map(map, |item| item.text.to_lowercase().contains(&query))
+        }
+        Command::Symbol { name, map } => {
+            run_map(map, |item| item.name.as_deref() == Some(name.as_str()))
+        }
+        Command::Diff {
+            old,
+            new,
+            walk,
+            output,
+        } => run_diff(&old, &new, &walk, &output),
+        Command::Deps {
+            names,
+            path,
+            walk,
+            output,
+        } => run_deps(&names, &path, &walk, &output),
+        Command::Watch {
+            path,
+            diff,
+            walk,
+            output,
+        } => watch::watch(&path, &walk, &output, diff),
+        Command::Mcp => mcp::serve().await.map_err(|err| err.to_string()),
+        Command::Cache { action, cache_dir } => run_cache(action, cache_dir),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let walker = WalkBuilder::new(path).types(types).build();
+// Map a directory and print the items accepted by `filter`
+fn run_map(args: MapArgs, filter: impl Fn(&codemap::Item) -> bool) -> Result<(), String> {
+    let config = args.walk.config(&args.path)?;
+    let ProjectMap { mut files, errors } = map_path(&args.path, &args.walk, &config, None)?;
 
-    println!("<codemap>");
+    for (_, file_map) in &mut files {
+        file_map.items.retain(&filter);
+    }
 
-    for result in walker {
-        match result {
-            Ok(entry) => {
-                if entry.path().is_dir() {
-                    continue;
-                }
+    write_output(&args.output, &args.output.render(&config, &files))?;
+    check_errors(errors.len())
+}
 
-                let mut file = File::open(entry.path()).await.unwrap();
-                let mut contents = String::new();
+fn run_diff(old: &Path, new: &Path, walk: &WalkArgs, output: &OutputArgs) -> Result<(), String> {
+    // Each tree is mapped with its own settings, the output is formatted as
+    // the new one asks
+    let old = map_path(old, walk, &walk.config(old)?, None)?;
+    let config = walk.config(new)?;
+    let new = map_path(new, walk, &config, None)?;
 
-                // Read the file contents into the string
-                file.read_to_string(&mut contents).await.unwrap();
+    let diffs = diff_maps(&old.files, &new.files);
+    write_output(output, &render_diff(&diffs, output.format(&config)))?;
+    check_errors(old.errors.len() + new.errors.len())
+}
 
-                let codemap = codemap(&contents);
-                let codemap = codemap.trim();
+fn run_deps(
+    names: &[String],
+    path: &Path,
+    walk: &WalkArgs,
+    output: &OutputArgs,
+) -> Result<(), String> {
+    let config = walk.config(path)?;
+    let lockfile =
+        find_lockfile(path).ok_or("no Cargo.lock found, run `cargo generate-lockfile`")?;
+    let packages = read_lockfile(&lockfile).map_err(|err| err.to_string())?;
 
-                if codemap.is_empty() {
-                    continue;
+    let selected = if names.is_empty() {
+        direct_dependencies(&packages, walk.package.as_deref()).map_err(|err| err.to_string())?
+    } else {
+        let mut selected = Vec::new();
+        for name in names {
+            for package in find_locked(&packages, name).map_err(|err| err.to_string())? {
+                if !selected.contains(&package) {
+                    selected.push(package);
                 }
+            }
+        }
+        selected
+    };
+
+    let root = lockfile.parent().unwrap_or(Path::new("."));
+    let cargo_home = cargo_home();
+    let options = walk.options(&config, None);
+    let mut files = Vec::new();
+    let mut errors = 0;
+    for package in selected {
+        let Some(dir) = locate_package(package, root, cargo_home.as_deref()) else {
+            eprintln!(
+                "WARNING: no sources of {} {} on disk, run `cargo fetch` or `cargo vendor`",
+                package.name, package.version
+            );
+            continue;
+        };
+        let project = map_dependency(package, &dir, &options).map_err(|err| err.to_string())?;
+        report_errors(&project.errors);
+        errors += project.errors.len();
+        files.extend(project.files);
+    }
+
+    write_output(output, &output.render(&config, &files))?;
+    check_errors(errors)
+}
+
+fn run_cache(action: CacheAction, cache_dir: Option<PathBuf>) -> Result<(), String> {
+    let dir = cache_dir
+        .or_else(Cache::default_dir)
+        .ok_or("no cache directory, pass --cache-dir")?;
+    // The entries of every set of options are inspected and pruned alike, and
+    // whether one is fresh doesn't depend on its options, so no fingerprint
+    let cache = Cache::open(&dir, "").map_err(|err| format!("{}: {}", dir.display(), err))?;
 
-                let display_path = entry.path().strip_prefix(path).unwrap_or(entry.path());
-                println!(
-                    "<file path=\"{}\">\n{}\n</file>",
-                    display_path.display(),
-                    codemap
-                );
+    match action {
+        CacheAction::Inspect => {
+            let entries = cache.entries().map_err(|err| err.to_string())?;
+            for entry in &entries {
+                let state = if entry.fresh { "fresh" } else { "stale" };
+                println!("{}\t{}\t{}", state, entry.size, entry.source.display());
             }
-            Err(err) => println!("ERROR: {}", err),
+            let fresh = entries.iter().filter(|entry| entry.fresh).count();
+            let size: u64 = entries.iter().map(|entry| entry.size).sum();
+            println!(
+                "{} entries ({} fresh, {} stale), {} bytes in {}",
+                entries.len(),
+                fresh,
+                entries.len() - fresh,
+                size,
+                dir.display()
+            );
+        }
+        CacheAction::Prune => {
+            let removed = cache.prune().map_err(|err| err.to_string())?;
+            println!("removed {} entries", removed);
+        }
+        CacheAction::Clear => {
+            cache.clear().map_err(|err| err.to_string())?;
+            println!("cleared {}", dir.display());
         }
     }
+    Ok(())
+}
 
-    println!("</codemap>");
+pub fn write_output(args: &OutputArgs, rendered: &str) -> Result<(), String> {
+    match &args.output {
+        Some(path) => std::fs::write(path, rendered)
+            .map_err(|err| format!("can't write {}: {}", path.display(), err)),
+        None => {
+            print!("{}", rendered);
+            Ok(())
+        }
+    }
+}
+
+impl WalkArgs {
+    /// The configuration file that applies to `path`, unless disabled.
+    pub fn config(&self, path: &Path) -> Result<Config, String> {
+        if self.no_config {
+            return Ok(Config::default());
+        }
+        let config = Config::discover(path).map_err(|err| err.to_string())?;
+        Ok(config.map(|(_, config)| config).unwrap_or_default())
+    }
+
+    /// Library options for these flags on top of `config`. A cache that can't
+    /// be opened only costs speed, so it is reported and left out.
+    pub fn options(&self, config: &Config, workspace: Option<Arc<Workspace>>) -> Options {
+        let (codemap, overrides) = config.map_options(&self.codemap.settings());
+        // Overrides change how the files they match are mapped, so they are
+        // part of what the entries depend on
+        let mut fingerprint = codemap.fingerprint();
+        for path_override in &overrides {
+            fingerprint.push_str(&format!(
+                "\n{}: {}",
+                path_override.glob,
+                path_override.codemap.fingerprint()
+            ));
+        }
+        let cache = if self.no_cache || workspace.is_some() {
+            None
+        } else {
+            self.cache_dir
+                .clone()
+                .or_else(Cache::default_dir)
+                .and_then(|dir| match Cache::open(&dir, &fingerprint) {
+                    Ok(cache) => Some(cache),
+                    Err(err) => {
+                        eprintln!("WARNING: cache disabled, {}: {}", dir.display(), err);
+                        None
+                    }
+                })
+        };
+
+        Options {
+            include: [&config.include[..], &self.include[..]].concat(),
+            exclude: [&config.exclude[..], &self.exclude[..]].concat(),
+            hidden: self.hidden,
+            no_ignore: self.no_ignore,
+            max_depth: self.max_depth,
+            follow_links: self.follow_links,
+            languages: LanguageRegistry::default(),
+            codemap,
+            overrides,
+            show_origin: self.show_origin,
+            jobs: self.jobs,
+            max_file_size: Some(self.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE)),
+            cache,
+            workspace,
+            package: self.package.clone(),
+        }
+    }
+}
+
+/// Map every source file under `path`, reporting entries that can't be walked,
+/// read or parsed on stderr.
+///
+/// Long-running commands pass a `workspace` so files mapped again are
+/// re-parsed incrementally; otherwise the on-disk cache is used.
+pub fn map_path(
+    path: &Path,
+    args: &WalkArgs,
+    config: &Config,
+    workspace: Option<Arc<Workspace>>,
+) -> Result<ProjectMap, String> {
+    let options = args.options(config, workspace);
+    let project = map_directory(path, &options).map_err(|err| err.to_string())?;
+    report_errors(&project.errors);
+    Ok(project)
+}
+
+pub fn report_errors(errors: &[codemap::Error]) {
+    for err in errors {
+        eprintln!("ERROR: {}", err);
+    }
+}
+
+// The other files are still printed, but the run fails once its output is
+// written if anything couldn't be mapped
+fn check_errors(count: usize) -> Result<(), String> {
+    match count {
+        0 => Ok(()),
+        1 => Err("1 entry couldn't be mapped".to_string()),
+        count => Err(format!("{} entries couldn't be mapped", count)),
+    }
 }
@@ -0,0 +1,251 @@
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::report_errors;
+use codemap::{
+    Config, Format, MapSettings, Options, Visibility, Workspace, fit_token_budget, map_directory,
+};
+
+// Protocol version answered when the client doesn't ask for one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// Arguments shared by every tool, mirroring the CLI walk flags
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ToolArguments {
+    path: Option<PathBuf>,
+    query: Option<String>,
+    name: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    hidden: bool,
+    no_ignore: bool,
+    max_depth: Option<usize>,
+    show_origin: bool,
+    package: Option<String>,
+    visibility: Option<Visibility>,
+    uses: Option<bool>,
+    impls: Option<bool>,
+    trait_impls: Option<bool>,
+    docs: Option<bool>,
+    attributes: Option<bool>,
+    line_numbers: Option<bool>,
+    max_signature_length: Option<usize>,
+    format: Option<Format>,
+    token_budget: Option<usize>,
+}
+
+/// Run an MCP server speaking newline-delimited JSON-RPC on stdin/stdout until
+/// stdin is closed.
+pub async fn serve() -> std::io::Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    // Parse trees of every file mapped so far, so repeated calls only re-parse
+    // what changed in between
+    let workspace = Arc::new(Workspace::new());
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => handle_request(&request, &workspace).await,
+            Err(err) => Some(error_response(
+                Value::Null,
+                -32700,
+                &format!("parse error: {}", err),
+            )),
+        };
+
+        // Notifications don't get a response
+        if let Some(response) = response {
+            stdout.write_all(response.to_string().as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_request(request: &Value, workspace: &Arc<Workspace>) -> Option<Value> {
+    let id = request.get("id")?.clone();
+    let method = request["method"].as_str().unwrap_or_default();
+    let params = &request["params"];
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "codemap", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => call_tool(params, workspace).await,
+        _ => return Some(error_response(id, -32601, "method not found")),
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn tools() -> Value {
+    let walk_properties = json!({
+        "path": { "type": "string", "description": "Directory or file to map" },
+        "include": { "type": "array", "items": { "type": "string" }, "description": "Only map files matching these globs" },
+        "exclude": { "type": "array", "items": { "type": "string" }, "description": "Skip files matching these globs" },
+        "hidden": { "type": "boolean", "description": "Map hidden files and directories" },
+        "no_ignore": { "type": "boolean", "description": "Don't respect .gitignore and other ignore files" },
+        "max_depth": { "type": "integer", "description": "Descend at most this many directories" },
+        "show_origin": { "type": "boolean", "description": "Annotate re-exported items with where they are defined" },
+        "package": { "type": "string", "description": "Only map the Cargo package with this name" },
+        "visibility": { "type": "string", "enum": ["private", "crate", "public"], "description": "Least visible items to show" },
+        "uses": { "type": "boolean", "description": "Show use declarations and re-exports" },
+        "impls": { "type": "boolean", "description": "Show the methods of impl blocks" },
+        "trait_impls": { "type": "boolean", "description": "Show which traits each struct implements" },
+        "docs": { "type": "boolean", "description": "Show the doc comments of items, fields and variants" },
+        "attributes": { "type": "boolean", "description": "Show the attributes of items, fields and variants" },
+        "line_numbers": { "type": "boolean", "description": "Precede every item with the lines it spans" },
+        "max_signature_length": { "type": "integer", "description": "Cut lines longer than this many characters" },
+        "format": { "type": "string", "enum": ["xml", "json", "markdown"], "description": "Output format" },
+        "token_budget": { "type": "integer", "description": "Leave out items once the output would be about this many tokens" },
+    });
+
+    let with = |name: &str, description: &str| {
+        let mut properties = walk_properties.clone();
+        properties[name] = json!({ "type": "string", "description": description });
+        json!({ "type": "object", "properties": properties, "required": ["path", name] })
+    };
+
+    json!([
+        {
+            "name": "codemap",
+            "description": "Public API (types, functions, traits and their signatures) of every supported source file under a path",
+            "inputSchema": { "type": "object", "properties": walk_properties, "required": ["path"] },
+        },
+        {
+            "name": "search",
+            "description": "Public items whose signature contains a string, case-insensitive",
+            "inputSchema": with("query", "Text to look for"),
+        },
+        {
+            "name": "symbol",
+            "description": "Public items with the given name",
+            "inputSchema": with("name", "Item name"),
+        },
+    ])
+}
+
+async fn call_tool(params: &Value, workspace: &Arc<Workspace>) -> Value {
+    let name = params["name"].as_str().unwrap_or_default();
+    let arguments: ToolArguments =
+        match serde_json::from_value(params.get("arguments").cloned().unwrap_or(json!({}))) {
+            Ok(arguments) => arguments,
+            Err(err) => return tool_error(&format!("invalid arguments: {}", err)),
+        };
+
+    let Some(path) = arguments.path.clone() else {
+        return tool_error("missing argument: path");
+    };
+
+    // Settings from the repository's configuration, overridden by arguments
+    let config = match Config::discover(&path) {
+        Ok(config) => config.map(|(_, config)| config).unwrap_or_default(),
+        Err(err) => return tool_error(&err.to_string()),
+    };
+    let (codemap, overrides) = config.map_options(&MapSettings {
+        visibility: arguments.visibility,
+        uses: arguments.uses,
+        impls: arguments.impls,
+        trait_impls: arguments.trait_impls,
+        docs: arguments.docs,
+        attributes: arguments.attributes,
+        line_numbers: arguments.line_numbers,
+        max_signature_length: arguments.max_signature_length,
+    });
+    let format = arguments.format.or(config.format).unwrap_or(Format::Xml);
+    let token_budget = arguments.token_budget.or(config.token_budget);
+
+    // Files are kept parsed in the workspace, so the disk cache isn't needed
+    let options = Options {
+        include: [config.include, arguments.include].concat(),
+        exclude: [config.exclude, arguments.exclude].concat(),
+        hidden: arguments.hidden,
+        no_ignore: arguments.no_ignore,
+        max_depth: arguments.max_depth,
+        codemap,
+        overrides,
+        show_origin: arguments.show_origin,
+        package: arguments.package,
+        workspace: Some(Arc::clone(workspace)),
+        ..Options::default()
+    };
+
+    let filter: Box<dyn Fn(&codemap::Item) -> bool + Send> = match name {
+        "codemap" => Box::new(|_| true),
+        "search" => {
+            let Some(query) = arguments.query else {
+                return tool_error("missing argument: query");
+            };
+            let query = query.to_lowercase();
+            Box::new(move |item| item.text.to_lowercase().contains(&query))
+        }
+        "symbol" => {
+            let Some(symbol) = arguments.name else {
+                return tool_error("missing argument: name");
+            };
+            Box::new(move |item| item.name.as_deref() == Some(symbol.as_str()))
+        }
+        _ => return tool_error(&format!("unknown tool: {}", name)),
+    };
+
+    // Walking and parsing blocks, keep it off the runtime's worker threads
+    let workspace = Arc::clone(workspace);
+    let mapped = tokio::task::spawn_blocking(move || {
+        let project = map_directory(&path, &options);
+        workspace.remove_missing();
+        project
+    })
+    .await;
+    let mut project = match mapped {
+        Ok(Ok(project)) => project,
+        Ok(Err(err)) => return tool_error(&err.to_string()),
+        Err(err) => return tool_error(&err.to_string()),
+    };
+    report_errors(&project.errors);
+    for (_, file_map) in &mut project.files {
+        file_map.items.retain(|item| filter(item));
+    }
+
+    let mut content = vec![];
+    if let Some(budget) = token_budget {
+        let left_out = fit_token_budget(&mut project.files, budget);
+        if left_out > 0 {
+            content.push(json!({
+                "type": "text",
+                "text": format!("Token budget of {} reached, {} items left out", budget, left_out),
+            }));
+        }
+    }
+    content.insert(0, json!({ "type": "text", "text": project.render(format) }));
+
+    json!({ "content": content, "isError": false })
+}
+
+fn tool_error(message: &str) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true,
+    })
+}
@@ -0,0 +1,119 @@
+/// The least visible items a codemap shows.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Every item, whatever its visibility
+    Private,
+    /// `pub(crate)`, `pub(super)` and `pub(in path)` items as well as `pub` ones
+    Crate,
+    /// Only `pub` items
+    #[default]
+    Public,
+}
+
+/// What a codemap includes and how it is rendered.
+///
+/// Built with chained setters starting from the defaults, which are what
+/// [`codemap`](crate::codemap) uses:
+///
+/// ```
+/// use codemap::{CodemapOptions, Visibility};
+///
+/// let options = CodemapOptions::new()
+///     .visibility(Visibility::Crate)
+///     .include_uses(false)
+///     .max_signature_length(Some(100));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CodemapOptions {
+    pub(crate) visibility: Visibility,
+    pub(crate) include_uses: bool,
+    pub(crate) include_impls: bool,
+    pub(crate) include_trait_impls: bool,
+    pub(crate) include_docs: bool,
+    pub(crate) include_attributes: bool,
+    pub(crate) include_line_numbers: bool,
+    pub(crate) max_signature_length: Option<usize>,
+}
+
+impl Default for CodemapOptions {
+    fn default() -> Self {
+        CodemapOptions {
+            visibility: Visibility::Public,
+            include_uses: true,
+            include_impls: true,
+            include_trait_impls: false,
+            include_docs: true,
+            include_attributes: true,
+            include_line_numbers: false,
+            max_signature_length: None,
+        }
+    }
+}
+
+impl CodemapOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show items, fields and methods at least this visible (default: `pub`
+    /// only).
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Show `use` declarations, which is how re-exports appear (default: on).
+    pub fn include_uses(mut self, include: bool) -> Self {
+        self.include_uses = include;
+        self
+    }
+
+    /// Show the methods of inherent `impl` blocks under their struct
+    /// (default: on).
+    pub fn include_impls(mut self, include: bool) -> Self {
+        self.include_impls = include;
+        self
+    }
+
+    /// Show which traits a struct implements, as `impl Trait for Type {}`
+    /// under the struct (default: off).
+    pub fn include_trait_impls(mut self, include: bool) -> Self {
+        self.include_trait_impls = include;
+        self
+    }
+
+    /// Show the first line of the doc comment of items, fields and variants
+    /// (default: on).
+    pub fn include_docs(mut self, include: bool) -> Self {
+        self.include_docs = include;
+        self
+    }
+
+    /// Show the attributes of items, fields and enum variants, e.g.
+    /// `#[derive(Debug)]` or `#[serde(rename = "...")]` (default: on).
+    pub fn include_attributes(mut self, include: bool) -> Self {
+        self.include_attributes = include;
+        self
+    }
+
+    /// Precede every item with a comment like `// L12-L40` giving the lines
+    /// it spans, in the comment syntax of its language (default: off).
+    pub fn include_line_numbers(mut self, include: bool) -> Self {
+        self.include_line_numbers = include;
+        self
+    }
+
+    /// Cut lines of the map longer than this many characters, ending them with
+    /// `…` (default: no limit).
+    pub fn max_signature_length(mut self, length: Option<usize>) -> Self {
+        self.max_signature_length = length;
+        self
+    }
+
+    /// A string identifying these options, e.g. to tell apart cache entries
+    /// mapped with different options.
+    pub fn fingerprint(&self) -> String {
+        format!("{:?}", self)
+    }
+}
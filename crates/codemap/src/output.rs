@@ -0,0 +1,179 @@
+use crate::{FileDiff, FileMap, Item, LanguageRegistry};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+
+/// How codemaps are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// `<codemap>` document with one `<file>` element per file
+    Xml,
+    /// Files with their items and diagnostics as a JSON object
+    Json,
+    /// One section per file with a fenced code block
+    Markdown,
+}
+
+/// Render the codemaps of a set of files, skipping files with nothing to show.
+pub fn render_files(files: &[(PathBuf, FileMap)], format: Format) -> String {
+    let files: Vec<&(PathBuf, FileMap)> = files
+        .iter()
+        .filter(|(_, file_map)| !file_map.items.is_empty() || !file_map.diagnostics.is_empty())
+        .collect();
+
+    match format {
+        Format::Xml => {
+            let mut out = String::from("<codemap>\n");
+            for (path, file_map) in files {
+                out.push_str(&format!(
+                    "<file path=\"{}\">\n",
+                    escape_xml(&path.display().to_string())
+                ));
+                let codemap = file_map.map();
+                let codemap = codemap.trim();
+                if !codemap.is_empty() {
+                    out.push_str(codemap);
+                    out.push('\n');
+                }
+                for diagnostic in &file_map.diagnostics {
+                    // Snippets are broken source code, full of `<` and `&`
+                    out.push_str(&format!(
+                        "<error line=\"{}\" column=\"{}\">{}: {}</error>\n",
+                        diagnostic.line,
+                        diagnostic.column,
+                        escape_xml(&diagnostic.message),
+                        escape_xml(&diagnostic.snippet)
+                    ));
+                }
+                out.push_str("</file>\n");
+            }
+            out.push_str("</codemap>\n");
+            out
+        }
+        Format::Json => {
+            let files: Vec<_> = files
+                .iter()
+                .map(|(path, file_map)| {
+                    json!({
+                        "path": path,
+                        "items": file_map.items,
+                        "diagnostics": file_map.diagnostics,
+                    })
+                })
+                .collect();
+            format!("{:#}\n", json!({ "files": files }))
+        }
+        Format::Markdown => {
+            let languages = LanguageRegistry::default();
+            let mut out = String::new();
+            for (path, file_map) in files {
+                out.push_str(&format!("## {}\n\n", path.display()));
+                let codemap = file_map.map();
+                let codemap = codemap.trim();
+                if !codemap.is_empty() {
+                    // Fence with the language name so the block is highlighted
+                    let language = languages
+                        .for_path(path)
+                        .map_or("", |backend| backend.fence());
+                    out.push_str(&format!("```{}\n{}\n```\n\n", language, codemap));
+                }
+                for diagnostic in &file_map.diagnostics {
+                    out.push_str(&format!(
+                        "- line {}, column {}: {}: `{}`\n",
+                        diagnostic.line, diagnostic.column, diagnostic.message, diagnostic.snippet
+                    ));
+                }
+                if !file_map.diagnostics.is_empty() {
+                    out.push('\n');
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Leave out items from the end until the items left add up to at most
+/// `budget` tokens, estimated at four characters per token. Returns how many
+/// items were left out.
+pub fn fit_token_budget(files: &mut [(PathBuf, FileMap)], budget: usize) -> usize {
+    let mut used = 0;
+    let mut left_out = 0;
+    for (_, file_map) in files.iter_mut() {
+        file_map.items.retain(|item| {
+            let tokens = item.text.chars().count().div_ceil(4);
+            if left_out == 0 && used + tokens <= budget {
+                used += tokens;
+                true
+            } else {
+                left_out += 1;
+                false
+            }
+        });
+    }
+    left_out
+}
+
+/// Render the changes between two codemaps.
+pub fn render_diff(diffs: &[FileDiff], format: Format) -> String {
+    match format {
+        Format::Xml => {
+            let mut out = String::from("<diff>\n");
+            for diff in diffs {
+                out.push_str(&format!(
+                    "<file path=\"{}\">\n",
+                    escape_xml(&diff.path.display().to_string())
+                ));
+                for item in &diff.removed {
+                    out.push_str(&format!("<removed>\n{}\n</removed>\n", item.text));
+                }
+                for item in &diff.added {
+                    out.push_str(&format!("<added>\n{}\n</added>\n", item.text));
+                }
+                for (old, new) in &diff.changed {
+                    out.push_str(&format!(
+                        "<changed>\n<old>\n{}\n</old>\n<new>\n{}\n</new>\n</changed>\n",
+                        old.text, new.text
+                    ));
+                }
+                out.push_str("</file>\n");
+            }
+            out.push_str("</diff>\n");
+            out
+        }
+        Format::Json => format!("{:#}\n", json!({ "files": diffs })),
+        Format::Markdown => {
+            let mut out = String::new();
+            for diff in diffs {
+                out.push_str(&format!("## {}\n\n```diff\n", diff.path.display()));
+                for item in &diff.removed {
+                    push_prefixed(&mut out, '-', item);
+                }
+                for item in &diff.added {
+                    push_prefixed(&mut out, '+', item);
+                }
+                for (old, new) in &diff.changed {
+                    push_prefixed(&mut out, '-', old);
+                    push_prefixed(&mut out, '+', new);
+                }
+                out.push_str("```\n\n");
+            }
+            out
+        }
+    }
+}
+
+// Escaped for element content and double-quoted attributes
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn push_prefixed(out: &mut String, prefix: char, item: &Item) {
+    for line in item.text.lines() {
+        out.push_str(format!("{} {}", prefix, line).trim_end());
+        out.push('\n');
+    }
+}
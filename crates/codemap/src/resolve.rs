@@ -0,0 +1,586 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use tree_sitter::{Node, Parser};
+
+use crate::{Error, FileMap, Item, ItemKind, node_text, rust_parser};
+
+/// Options for [`expand_reexports`].
+#[derive(Debug, Clone, Default)]
+pub struct ReexportOptions {
+    /// Annotate every expanded item with the path and file it is defined in
+    pub show_origin: bool,
+}
+
+// A module of a crate, identified by the directory holding the crate's `src`,
+// the crate root its module tree starts at (`lib` or `main`) and the module
+// path below that root
+type ModuleKey = (PathBuf, String, Vec<String>);
+
+// Where a file is in its crate: the crate directory, the crate root it is
+// (if it is one) and its module path
+type ModuleLocation = (PathBuf, Option<String>, Vec<String>);
+
+// A single name (or glob) brought into scope by a `pub use` declaration
+#[derive(Debug, Clone, PartialEq)]
+struct UseTarget {
+    // Path segments, including the imported name unless this is a glob
+    path: Vec<String>,
+    kind: UseKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum UseKind {
+    // `path::Name` or `path::Name as Alias`, holding the name it is visible as
+    Name(String),
+    // `path::*`
+    Glob,
+}
+
+// An item brought into scope by a re-export, with where it was defined
+struct Resolved {
+    item: Item,
+    // `crate::module::Name in path/to/file.rs`, `None` for re-exports that
+    // could not be resolved
+    origin: Option<String>,
+}
+
+/// Replace `pub use` declarations that point into the same crate with the
+/// items they bring into scope.
+///
+/// Files are grouped into crates and modules by their path (`src/lib.rs` and
+/// `src/main.rs` are crate roots, `src/a/b.rs` and `src/a/b/mod.rs` are
+/// `crate::a::b` of either).
+/// Glob imports expand to every public item of the target module and `as`
+/// renames are applied to the expanded item. Re-exports of other crates are
+/// left as they are, and files in other languages than Rust are ignored.
+pub fn expand_reexports(
+    files: &mut [(PathBuf, FileMap)],
+    options: &ReexportOptions,
+) -> Result<(), Error> {
+    // The keys of every Rust file, its own module under each crate root
+    let keys: Vec<Vec<ModuleKey>> = module_keys(files);
+    let mut modules: HashMap<ModuleKey, usize> = HashMap::new();
+    for (index, keys) in keys.iter().enumerate() {
+        for key in keys {
+            modules.insert(key.clone(), index);
+        }
+    }
+
+    // Parse every `pub use` once up front, lookups revisit them a lot
+    let mut parser = rust_parser()?;
+    let mut use_targets = HashMap::new();
+    for (_, file_map) in files.iter().filter(|(path, _)| is_rust(path)) {
+        for item in &file_map.items {
+            if item.kind == ItemKind::UseDeclaration && !use_targets.contains_key(&item.text) {
+                use_targets.insert(
+                    item.text.clone(),
+                    parse_use_targets(&mut parser, &item.text),
+                );
+            }
+        }
+    }
+
+    let resolver = Resolver {
+        files,
+        modules: &modules,
+        use_targets: &use_targets,
+    };
+
+    // Resolve everything against the unexpanded maps first, then swap the
+    // results in
+    let mut expanded = Vec::with_capacity(files.len());
+    for ((_, file_map), keys) in files.iter().zip(&keys) {
+        // Files of other languages have no keys
+        let Some(module) = keys.first() else {
+            expanded.push(file_map.items.clone());
+            continue;
+        };
+        let mut items = Vec::new();
+        for item in &file_map.items {
+            if item.kind != ItemKind::UseDeclaration {
+                items.push(item.clone());
+                continue;
+            }
+            // Re-exported items keep the lines of their definition, along
+            // with the file it is in
+            for resolved in resolver.expand_use(module, item, &mut HashSet::new()) {
+                items.push(render(resolved, options));
+            }
+        }
+        expanded.push(items);
+    }
+
+    for ((_, file_map), items) in files.iter_mut().zip(expanded) {
+        file_map.items = items;
+    }
+    Ok(())
+}
+
+fn render(resolved: Resolved, options: &ReexportOptions) -> Item {
+    match resolved.origin {
+        Some(origin) if options.show_origin => Item {
+            text: format!("// defined at {}\n{}", origin, resolved.item.text),
+            ..resolved.item
+        },
+        _ => resolved.item,
+    }
+}
+
+struct Resolver<'a> {
+    files: &'a [(PathBuf, FileMap)],
+    modules: &'a HashMap<ModuleKey, usize>,
+    // Targets of each `pub use` declaration, keyed by its text
+    use_targets: &'a HashMap<String, Vec<UseTarget>>,
+}
+
+impl Resolver<'_> {
+    fn targets(&self, item: &Item) -> &[UseTarget] {
+        self.use_targets
+            .get(&item.text)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // Expand a single `pub use` declaration of `module`. Targets that can't be
+    // resolved inside the crate are kept as `pub use` lines
+    fn expand_use(
+        &self,
+        module: &ModuleKey,
+        item: &Item,
+        visited: &mut HashSet<(ModuleKey, String)>,
+    ) -> Vec<Resolved> {
+        let targets = self.targets(item);
+
+        let mut items = Vec::new();
+        let mut unresolved = Vec::new();
+        for target in targets {
+            match self.resolve_target(module, target, visited) {
+                Some(resolved) => items.extend(resolved),
+                None => unresolved.push(target),
+            }
+        }
+
+        // Nothing pointed into this crate, keep the declaration untouched
+        if unresolved.len() == targets.len() {
+            return vec![Resolved {
+                item: item.clone(),
+                origin: None,
+            }];
+        }
+
+        for target in unresolved {
+            items.push(Resolved {
+                item: Item {
+                    kind: ItemKind::UseDeclaration,
+                    name: None,
+                    text: format!("pub use {};", target.display()),
+                    lines: None,
+                    file: None,
+                },
+                origin: None,
+            });
+        }
+        items
+    }
+
+    // Resolve a target to the items it brings in, or `None` when it doesn't
+    // point at anything in this crate. A glob of a module already being
+    // expanded (a re-export cycle) resolves to nothing new
+    fn resolve_target(
+        &self,
+        module: &ModuleKey,
+        target: &UseTarget,
+        visited: &mut HashSet<(ModuleKey, String)>,
+    ) -> Option<Vec<Resolved>> {
+        match &target.kind {
+            UseKind::Glob => {
+                let target_module = self.resolve_module(module, &target.path)?;
+                Some(self.module_items(&target_module, visited))
+            }
+            UseKind::Name(alias) => {
+                let (name, module_path) = target.path.split_last()?;
+                let target_module = self.resolve_module(module, module_path)?;
+                let resolved: Vec<Resolved> = self
+                    .lookup(&target_module, name, visited)
+                    .into_iter()
+                    .map(|resolved| rename(resolved, name, alias))
+                    .collect();
+                (!resolved.is_empty()).then_some(resolved)
+            }
+        }
+    }
+
+    // Find the items named `name` visible in `module`, either defined there or
+    // re-exported by it
+    fn lookup(
+        &self,
+        module: &ModuleKey,
+        name: &str,
+        visited: &mut HashSet<(ModuleKey, String)>,
+    ) -> Vec<Resolved> {
+        // Re-export cycles are legal Rust as long as nothing is defined twice
+        if !visited.insert((module.clone(), name.to_string())) {
+            return Vec::new();
+        }
+
+        let Some(&index) = self.modules.get(module) else {
+            return Vec::new();
+        };
+        let (path, file_map) = &self.files[index];
+
+        let mut found = Vec::new();
+        for item in &file_map.items {
+            if item.kind != ItemKind::UseDeclaration {
+                if item.name.as_deref() == Some(name) {
+                    found.push(Resolved {
+                        item: defined_in(item, path),
+                        origin: Some(origin(module, path, name)),
+                    });
+                }
+                continue;
+            }
+
+            // Follow re-exports of this module that could bring `name` in
+            for target in self.targets(item) {
+                let (module_path, original) = match &target.kind {
+                    UseKind::Name(alias) if alias == name => match target.path.split_last() {
+                        Some((original, module_path)) => (module_path, original.as_str()),
+                        None => continue,
+                    },
+                    UseKind::Name(_) => continue,
+                    UseKind::Glob => (target.path.as_slice(), name),
+                };
+                let Some(target_module) = self.resolve_module(module, module_path) else {
+                    continue;
+                };
+                found.extend(
+                    self.lookup(&target_module, original, visited)
+                        .into_iter()
+                        .map(|resolved| rename(resolved, original, name)),
+                );
+            }
+        }
+        found
+    }
+
+    // All public items of `module`, with its own re-exports expanded, for a
+    // glob import
+    fn module_items(
+        &self,
+        module: &ModuleKey,
+        visited: &mut HashSet<(ModuleKey, String)>,
+    ) -> Vec<Resolved> {
+        if !visited.insert((module.clone(), "*".to_string())) {
+            return Vec::new();
+        }
+
+        let Some(&index) = self.modules.get(module) else {
+            return Vec::new();
+        };
+        let (path, file_map) = &self.files[index];
+
+        let mut items = Vec::new();
+        for item in &file_map.items {
+            if item.kind == ItemKind::UseDeclaration {
+                items.extend(self.expand_use(module, item, visited));
+            } else {
+                items.push(Resolved {
+                    item: defined_in(item, path),
+                    origin: item.name.as_deref().map(|name| origin(module, path, name)),
+                });
+            }
+        }
+        items
+    }
+
+    // Turn a use path into the module it points at, if that module is a file
+    // of this crate
+    fn resolve_module(&self, module: &ModuleKey, path: &[String]) -> Option<ModuleKey> {
+        let (crate_dir, root, current) = module;
+        let (first, rest) = path.split_first()?;
+
+        let mut resolved = match first.as_str() {
+            "crate" => Vec::new(),
+            "self" => current.clone(),
+            "super" => {
+                let mut parent = current.clone();
+                parent.pop()?;
+                parent
+            }
+            // A bare path starts at a child module, anything else is another crate
+            _ => {
+                let mut child = current.clone();
+                child.push(first.clone());
+                if !self
+                    .modules
+                    .contains_key(&(crate_dir.clone(), root.clone(), child.clone()))
+                {
+                    return None;
+                }
+                child
+            }
+        };
+
+        for segment in rest {
+            match segment.as_str() {
+                "super" => {
+                    resolved.pop()?;
+                }
+                "self" => {}
+                _ => resolved.push(segment.clone()),
+            }
+        }
+
+        let key = (crate_dir.clone(), root.clone(), resolved);
+        self.modules.contains_key(&key).then_some(key)
+    }
+}
+
+// A definition of the file at `path`, to be listed under another file
+fn defined_in(item: &Item, path: &Path) -> Item {
+    Item {
+        file: Some(path.to_path_buf()),
+        ..item.clone()
+    }
+}
+
+// Describe where an item is defined, e.g. `crate::inner::Foo in src/inner.rs`
+fn origin(module: &ModuleKey, path: &Path, name: &str) -> String {
+    let (_, _, module_path) = module;
+    let mut segments = vec!["crate"];
+    segments.extend(module_path.iter().map(String::as_str));
+    segments.push(name);
+    format!("{} in {}", segments.join("::"), path.display())
+}
+
+impl UseTarget {
+    fn display(&self) -> String {
+        let path = self.path.join("::");
+        match &self.kind {
+            UseKind::Glob => format!("{}::*", path),
+            UseKind::Name(alias) if Some(alias) == self.path.last() => path,
+            UseKind::Name(alias) => format!("{} as {}", path, alias),
+        }
+    }
+}
+
+// Only Rust files have modules and re-exports
+fn is_rust(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "rs")
+}
+
+// The module keys of every file, empty for files that aren't Rust. Crate
+// roots have a key of their own; other files are a module of every crate root
+// next to them, or of `lib` when there is none
+fn module_keys(files: &[(PathBuf, FileMap)]) -> Vec<Vec<ModuleKey>> {
+    let locations: Vec<Option<ModuleLocation>> = files
+        .iter()
+        .map(|(path, _)| is_rust(path).then(|| module_location(path)))
+        .collect();
+
+    let mut roots: HashMap<&PathBuf, Vec<String>> = HashMap::new();
+    for (crate_dir, root, _) in locations.iter().flatten() {
+        if let Some(root) = root {
+            roots.entry(crate_dir).or_default().push(root.clone());
+        }
+    }
+    for roots in roots.values_mut() {
+        roots.sort();
+    }
+
+    locations
+        .iter()
+        .map(|location| {
+            let Some((crate_dir, root, module)) = location else {
+                return Vec::new();
+            };
+            let roots = match root {
+                Some(root) => vec![root.clone()],
+                None => roots
+                    .get(crate_dir)
+                    .cloned()
+                    .unwrap_or_else(|| vec!["lib".to_string()]),
+            };
+            roots
+                .into_iter()
+                .map(|root| (crate_dir.clone(), root, module.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+// Work out which crate and module a file belongs to from its path, and which
+// crate root it is if it is one
+fn module_location(path: &Path) -> ModuleLocation {
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    // Everything up to the last `src` directory identifies the crate, files
+    // outside of any `src` directory are treated as one crate
+    let (crate_dir, mut module) = match components.iter().rposition(|part| part == "src") {
+        Some(src) => (
+            path.iter().take(src + 1).collect::<PathBuf>(),
+            components[src + 1..].to_vec(),
+        ),
+        None => (PathBuf::new(), components),
+    };
+
+    let mut root = None;
+    if let Some(file) = module.pop() {
+        let stem = file.strip_suffix(".rs").unwrap_or(&file).to_string();
+        if module.is_empty() && (stem == "lib" || stem == "main") {
+            root = Some(stem);
+        } else if stem != "mod" {
+            module.push(stem);
+        }
+    }
+
+    (crate_dir, root, module)
+}
+
+// Parse the names a `pub use` declaration brings into scope
+fn parse_use_targets(parser: &mut Parser, use_text: &str) -> Vec<UseTarget> {
+    let Some(tree) = parser.parse(use_text, None) else {
+        return Vec::new();
+    };
+    let root_node = tree.root_node();
+
+    let mut targets = Vec::new();
+    let mut cursor = root_node.walk();
+    for child in root_node.children(&mut cursor) {
+        if child.kind() != "use_declaration" {
+            continue;
+        }
+        if let Some(argument) = child.child_by_field_name("argument") {
+            collect_use_targets(&argument, use_text, &[], &mut targets);
+        }
+    }
+    targets
+}
+
+// Flatten a use tree into targets, carrying the path prefix of nested lists
+fn collect_use_targets(node: &Node, source: &str, prefix: &[String], targets: &mut Vec<UseTarget>) {
+    let text = |node: &Node| node_text(node, source);
+
+    match node.kind() {
+        "use_as_clause" => {
+            let (Some(path), Some(alias)) = (
+                node.child_by_field_name("path"),
+                node.child_by_field_name("alias"),
+            ) else {
+                return;
+            };
+            targets.push(UseTarget {
+                path: join_path(prefix, text(&path)),
+                kind: UseKind::Name(text(&alias).to_string()),
+            });
+        }
+        "use_wildcard" => {
+            let path = text(node).trim_end_matches('*').trim_end_matches("::");
+            targets.push(UseTarget {
+                path: join_path(prefix, path),
+                kind: UseKind::Glob,
+            });
+        }
+        "scoped_use_list" => {
+            let prefix = match node.child_by_field_name("path") {
+                Some(path) => join_path(prefix, text(&path)),
+                None => prefix.to_vec(),
+            };
+            if let Some(list) = node.child_by_field_name("list") {
+                collect_use_targets(&list, source, &prefix, targets);
+            }
+        }
+        "use_list" => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                collect_use_targets(&child, source, prefix, targets);
+            }
+        }
+        "scoped_identifier" | "identifier" | "crate" | "self" | "super" => {
+            let path = join_path(prefix, text(node));
+            // `use a::{self}` imports the module `a` itself
+            let name = match path.last().map(String::as_str) {
+                Some("self") if path.len() > 1 => path[path.len() - 2].clone(),
+                Some(name) => name.to_string(),
+                None => return,
+            };
+            targets.push(UseTarget {
+                path,
+                kind: UseKind::Name(name),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn join_path(prefix: &[String], path: &str) -> Vec<String> {
+    let mut segments = prefix.to_vec();
+    segments.extend(
+        path.split("::")
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string),
+    );
+    segments
+}
+
+// Give a re-exported item the name it is visible under
+fn rename(resolved: Resolved, name: &str, alias: &str) -> Resolved {
+    if name == alias {
+        return resolved;
+    }
+
+    Resolved {
+        item: Item {
+            name: Some(alias.to_string()),
+            text: rename_declaration(&resolved.item.text, name, alias),
+            ..resolved.item
+        },
+        ..resolved
+    }
+}
+
+// Rename what a rendered item declares: its name, and the type of the impl
+// blocks rendered with it. Fields, parameters and docs mentioning the name
+// are left alone
+fn rename_declaration(text: &str, name: &str, alias: &str) -> String {
+    let Some(tree) = rust_parser()
+        .ok()
+        .and_then(|mut parser| parser.parse(text, None))
+    else {
+        return text.to_string();
+    };
+    let root_node = tree.root_node();
+
+    let mut ranges = Vec::new();
+    for item in root_node.named_children(&mut root_node.walk()) {
+        let declared = match item.kind() {
+            "impl_item" => {
+                item.child_by_field_name("type")
+                    .and_then(|type_node| match type_node.kind() {
+                        "generic_type" => type_node.child_by_field_name("type"),
+                        _ => Some(type_node),
+                    })
+            }
+            _ => item.child_by_field_name("name"),
+        };
+        if let Some(declared) = declared
+            && node_text(&declared, text) == name
+        {
+            ranges.push(declared.byte_range());
+        }
+    }
+
+    let mut renamed = text.to_string();
+    for range in ranges.into_iter().rev() {
+        renamed.replace_range(range, alias);
+    }
+    renamed
+}
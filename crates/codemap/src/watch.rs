@@ -0,0 +1,161 @@
+use codemap::{CONFIG_FILE, Options, PathFilter, Workspace, diff_maps, map_directory, render_diff};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::fs::OpenOptions;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::{OutputArgs, WalkArgs, report_errors, write_output};
+
+// Editors and `git checkout` touch many files at once, wait for things to
+// settle before mapping again
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Map `path`, then map it again whenever a file under it that would be mapped
+/// changes, until the process is killed.
+///
+/// Changes to files the walk skips (ignored, excluded, hidden or in another
+/// language) are left alone. Mapping again only reads and parses the files
+/// that were modified, the others keep their map from the workspace. A
+/// changed `codemap.toml` or `Cargo.toml` under `path` is read again, so its
+/// settings apply from then on.
+///
+/// The whole map is printed first. Each change then prints it again, or with
+/// `diff` only the public items that changed since the previous map, added to
+/// the end of the output file if there is one.
+pub fn watch(path: &Path, walk: &WalkArgs, output: &OutputArgs, diff: bool) -> Result<(), String> {
+    // Keep the parse trees around so only changed files are re-parsed
+    let workspace = Arc::new(Workspace::new());
+    let mut config = walk.config(path)?;
+    let mut options = walk.options(&config, Some(Arc::clone(&workspace)));
+    let map = |options: &Options| {
+        let project = map_directory(path, options).map_err(|err| err.to_string())?;
+        report_errors(&project.errors);
+        Ok::<_, String>(project.files)
+    };
+    let new_filter =
+        |options: &Options| PathFilter::new(path, options).map_err(|err| err.to_string());
+    let mut filter = new_filter(&options)?;
+    let mut files = map(&options)?;
+    // Diffs are relative to this first map
+    show(output, &output.render(&config, &files))?;
+    let _ = std::io::stdout().flush();
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|err| err.to_string())?;
+    watcher
+        .watch(path, RecursiveMode::Recursive)
+        .map_err(|err| format!("can't watch {}: {}", path.display(), err))?;
+
+    while let Ok(event) = events.recv() {
+        let mut relevance = Relevance::of(event, &filter);
+        while let Ok(event) = events.recv_timeout(SETTLE_TIME) {
+            relevance = relevance.max(Relevance::of(event, &filter));
+        }
+        match relevance {
+            Relevance::None => continue,
+            Relevance::Config => match walk.config(path) {
+                Ok(new_config) => {
+                    config = new_config;
+                    options = walk.options(&config, Some(Arc::clone(&workspace)));
+                    filter = new_filter(&options)?;
+                }
+                // Likely saved halfway through an edit, keep the settings
+                // until it is fixed
+                Err(err) => {
+                    eprintln!("ERROR: {}", err);
+                    continue;
+                }
+            },
+            Relevance::IgnoreFile => filter = new_filter(&options)?,
+            Relevance::Mapped => {}
+        }
+
+        let new_files = map(&options)?;
+        workspace.remove_missing();
+        if new_files == files {
+            continue;
+        }
+
+        if diff {
+            // Diagnostics and empty files change the map but no public item
+            let diffs = diff_maps(&files, &new_files);
+            if !diffs.is_empty() {
+                append(output, &render_diff(&diffs, output.format(&config)))?;
+            }
+        } else {
+            show(output, &output.render(&config, &new_files))?;
+        }
+        files = new_files;
+        let _ = std::io::stdout().flush();
+    }
+
+    Ok(())
+}
+
+// How an event may change the map, from least to most work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Relevance {
+    None,
+    // A mapped file, or a directory that could contain some, was created,
+    // modified or removed
+    Mapped,
+    // An ignore file changed, which may change what is mapped at all
+    IgnoreFile,
+    // A file settings are read from changed, which may change how everything
+    // is mapped
+    Config,
+}
+
+impl Relevance {
+    fn of(event: notify::Result<notify::Event>, filter: &PathFilter) -> Relevance {
+        let Ok(event) = event else {
+            // Missed events (e.g. a queue overflow) could be anything
+            return Relevance::IgnoreFile;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return Relevance::None;
+        }
+        let is_ignore_file = |path: &Path| {
+            path.file_name()
+                .is_some_and(|name| name == ".gitignore" || name == ".ignore")
+        };
+        let is_config_file = |path: &Path| {
+            path.file_name()
+                .is_some_and(|name| name == CONFIG_FILE || name == "Cargo.toml")
+        };
+        if event.paths.iter().any(|path| is_config_file(path)) {
+            Relevance::Config
+        } else if event.paths.iter().any(|path| is_ignore_file(path)) {
+            Relevance::IgnoreFile
+        } else if event.paths.iter().any(|path| filter.is_mapped(path)) {
+            Relevance::Mapped
+        } else {
+            Relevance::None
+        }
+    }
+}
+
+// Replace what's on screen (or in the output file) with the new map. Diffs are
+// appended instead, so earlier changes stay visible
+fn show(output: &OutputArgs, rendered: &str) -> Result<(), String> {
+    if output.output.is_none() && std::io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+    write_output(output, rendered)
+}
+
+// Add a diff after the ones written before it
+fn append(output: &OutputArgs, rendered: &str) -> Result<(), String> {
+    let Some(path) = &output.output else {
+        return write_output(output, rendered);
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(rendered.as_bytes()))
+        .map_err(|err| format!("can't write {}: {}", path.display(), err))
+}
@@ -0,0 +1,262 @@
+use codemap::{C, CodemapOptions, Cpp, CppHeader, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+fn codemap_header(input: &str, options: CodemapOptions) -> String {
+    codemap_with(CppHeader, input, options)
+}
+
+#[test]
+fn test_c_header() {
+    let input = r#"#ifndef THING_H
+#define THING_H
+
+#include <stddef.h>
+
+#define THING_MAX 128
+#define THING_NAME "thing"
+#define THING_SQUARE(x) ((x) * (x))
+
+typedef struct thing {
+    int id;
+    const char *name;
+} thing_t;
+
+enum thing_kind { THING_SMALL, THING_LARGE = 2 };
+
+typedef void (*thing_callback)(thing_t *thing);
+
+int thing_open(const char *path,
+               size_t size);
+extern int thing_count;
+static inline int thing_helper(void) { return 0; }
+
+#endif
+"#;
+    let expected = r#"#define THING_MAX 128
+
+#define THING_NAME "thing"
+
+typedef struct thing {
+    int id;
+    const char *name;
+} thing_t;
+
+enum thing_kind {
+    THING_SMALL,
+    THING_LARGE = 2
+};
+
+typedef void (*thing_callback)(thing_t *thing);
+
+int thing_open(const char *path, size_t size);
+
+extern int thing_count;"#;
+    assert_eq!(codemap_header(input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_static_and_includes_are_private() {
+    let input = r#"#include <stddef.h>
+static inline int helper(void) { return 0; }
+int api(void);
+"#;
+    assert_eq!(
+        codemap_header(input, CodemapOptions::new()),
+        "int api(void);"
+    );
+    assert_eq!(
+        codemap_header(input, CodemapOptions::new().visibility(Visibility::Private)),
+        "#include <stddef.h>\n\nstatic inline int helper(void);\n\nint api(void);"
+    );
+}
+
+#[test]
+fn test_extern_c_block() {
+    let input = r#"#ifdef __cplusplus
+extern "C" {
+#endif
+
+int c_api(int x);
+
+#ifdef __cplusplus
+}
+#endif
+
+extern "C" void single(void);
+"#;
+    assert_eq!(
+        codemap_header(input, CodemapOptions::new()),
+        "int c_api(int x);\n\nextern \"C\" void single(void);"
+    );
+}
+
+#[test]
+fn test_class_public_sections() {
+    let input = r#"class Shape : public Base {
+public:
+    Shape();
+    virtual ~Shape() = default;
+    virtual double area() const = 0;
+    void resize(double factor) { factor_ = factor; }
+protected:
+    void invalidate();
+private:
+    double factor_;
+};
+
+struct Point {
+    int x;
+private:
+    int cache;
+};
+"#;
+    let expected = r#"class Shape : public Base {
+public:
+    Shape();
+    virtual ~Shape() = default;
+    virtual double area() const = 0;
+    void resize(double factor);
+};
+
+struct Point {
+    int x;
+};"#;
+    assert_eq!(codemap_header(input, CodemapOptions::new()), expected);
+
+    let expected = r#"class Shape : public Base {
+public:
+    Shape();
+    virtual ~Shape() = default;
+    virtual double area() const = 0;
+    void resize(double factor);
+protected:
+    void invalidate();
+};
+
+struct Point {
+    int x;
+};"#;
+    assert_eq!(
+        codemap_header(input, CodemapOptions::new().visibility(Visibility::Crate)),
+        expected
+    );
+
+    let expected = r#"struct Point {
+    int x;
+private:
+    int cache;
+};"#;
+    assert!(
+        codemap_header(input, CodemapOptions::new().visibility(Visibility::Private))
+            .ends_with(expected)
+    );
+}
+
+#[test]
+fn test_namespaces_and_templates() {
+    let input = r#"namespace geo {
+/// Largest of two values.
+template <typename T>
+T max(T a, T b) { return a > b ? a : b; }
+
+template <typename T>
+class Box {
+public:
+    T get() const;
+};
+
+using Id = int;
+
+namespace {
+int hidden();
+}
+}
+"#;
+    let expected = r#"namespace geo {
+    // Largest of two values.
+    template <typename T>
+    T max(T a, T b);
+
+    template <typename T>
+    class Box {
+    public:
+        T get() const;
+    };
+
+    using Id = int;
+}"#;
+    assert_eq!(codemap_header(input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_c_source_only_has_definitions() {
+    let input = r#"#include "thing.h"
+
+#define LOCAL 1
+
+struct local { int x; };
+
+int thing_open(const char *path);
+static int counter = 0;
+int thing_count = 3;
+extern int elsewhere;
+int table[] = {
+    1, 2,
+};
+
+static void helper(void) {}
+
+/* Opens a thing. */
+int thing_open(const char *path)
+{
+    return 0;
+}
+"#;
+    let expected = r#"int thing_count = 3;
+
+int table[];
+
+// Opens a thing.
+int thing_open(const char *path);"#;
+    assert_eq!(codemap_with(C, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_cpp_source_only_has_definitions() {
+    let input = r#"#include "shape.hpp"
+
+namespace {
+void hidden() {}
+}
+
+namespace geo {
+Shape::Shape() : factor_(1) {}
+
+double Shape::area() const { return 0; }
+
+class Local {};
+}
+"#;
+    let expected = r#"namespace geo {
+    Shape::Shape();
+
+    double Shape::area() const;
+}"#;
+    assert_eq!(codemap_with(Cpp, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_headers_are_fenced_as_cpp() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("api.h"), "int api(void);\n").unwrap();
+    std::fs::write(dir.path().join("api.c"), "int api(void) { return 0; }\n").unwrap();
+
+    let project = codemap::map_directory(dir.path(), &codemap::Options::default()).unwrap();
+    assert_eq!(
+        project.render(codemap::Format::Markdown),
+        "## api.c\n\n```c\nint api(void);\n```\n\n## api.h\n\n```cpp\nint api(void);\n```\n\n"
+    );
+}
@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use codemap::{Cache, map_source};
+
+#[test]
+fn test_cache_hit_and_miss() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    let path = source_dir.path().join("lib.rs");
+    let contents = "pub fn public_function(param: i32) -> i32 { todo!() }";
+    fs::write(&path, contents).unwrap();
+
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    assert_eq!(cache.get(&path, contents), None);
+
+    let file_map = map_source(contents).unwrap();
+    cache.put(&path, contents, &file_map).unwrap();
+    assert_eq!(cache.get(&path, contents), Some(file_map.clone()));
+
+    // Changed contents and other options don't use the entry
+    assert_eq!(cache.get(&path, "pub fn other() {}"), None);
+    let other_options = Cache::open(cache_dir.path(), "include_uses").unwrap();
+    assert_eq!(other_options.get(&path, contents), None);
+
+    // Entries of other options are kept side by side
+    let other_map = map_source("pub fn other() {}").unwrap();
+    other_options.put(&path, contents, &other_map).unwrap();
+    assert_eq!(other_options.get(&path, contents), Some(other_map));
+    assert_eq!(cache.get(&path, contents), Some(file_map));
+}
+
+#[test]
+fn test_cache_one_entry_per_file() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    let path = source_dir.path().join("lib.rs");
+
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    for contents in ["pub fn first() {}", "pub fn second() {}"] {
+        fs::write(&path, contents).unwrap();
+        cache
+            .put(&path, contents, &map_source(contents).unwrap())
+            .unwrap();
+    }
+
+    let entries = cache.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].source, path);
+    assert!(entries[0].fresh);
+}
+
+#[test]
+fn test_cache_prune_and_clear() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    let unchanged = source_dir.path().join("unchanged.rs");
+    let changed = source_dir.path().join("changed.rs");
+    let deleted = source_dir.path().join("deleted.rs");
+
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    for path in [&unchanged, &changed, &deleted] {
+        let contents = "pub struct Simple;";
+        fs::write(path, contents).unwrap();
+        cache
+            .put(path, contents, &map_source(contents).unwrap())
+            .unwrap();
+    }
+    fs::write(&changed, "pub struct Changed;").unwrap();
+    fs::remove_file(&deleted).unwrap();
+
+    let stale: Vec<_> = cache
+        .entries()
+        .unwrap()
+        .into_iter()
+        .filter(|entry| !entry.fresh)
+        .map(|entry| entry.source)
+        .collect();
+    assert_eq!(stale, [changed, deleted]);
+
+    assert_eq!(cache.prune().unwrap(), 2);
+    let entries = cache.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].source, unchanged);
+
+    cache.clear().unwrap();
+    assert!(cache.entries().unwrap().is_empty());
+}
+
+#[test]
+fn test_cache_prune_keeps_recent_temp_files() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+
+    // A write that may still be going on, and one interrupted long ago
+    let temp_file = |hash: char| {
+        let name = format!("{}.json.1.ThreadId(2).tmp", hash.to_string().repeat(64));
+        cache_dir.path().join("files").join(name)
+    };
+    let recent = temp_file('a');
+    let abandoned = temp_file('b');
+    fs::write(&recent, "{").unwrap();
+    fs::write(&abandoned, "{").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&abandoned)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))
+        .unwrap();
+
+    assert_eq!(cache.prune().unwrap(), 1);
+    assert!(recent.exists());
+    assert!(!abandoned.exists());
+}
+
+#[test]
+fn test_cache_leaves_other_files_alone() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    let path = source_dir.path().join("lib.rs");
+    let settings = cache_dir.path().join("settings.json");
+    let notes = cache_dir.path().join("files/notes.json");
+    fs::write(&settings, "not an entry").unwrap();
+
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    fs::write(&notes, "not an entry either").unwrap();
+    fs::write(&path, "pub fn f() {}").unwrap();
+    cache
+        .put(
+            &path,
+            "pub fn f() {}",
+            &map_source("pub fn f() {}").unwrap(),
+        )
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(cache.entries().unwrap().len(), 1);
+    assert_eq!(cache.prune().unwrap(), 1);
+    cache.clear().unwrap();
+    assert!(settings.exists());
+    assert!(notes.exists());
+}
+
+#[test]
+fn test_cache_crate_entries() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let files = vec![(
+        PathBuf::from("src/lib.rs"),
+        map_source("pub fn f() {}").unwrap(),
+    )];
+
+    // Crate entries of other options are listed as well
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    let other_options = Cache::open(cache_dir.path(), "include_uses").unwrap();
+    cache.put_crate("serde", "1.0.0", "", &files).unwrap();
+    other_options
+        .put_crate("serde", "1.0.0", "", &files)
+        .unwrap();
+    let entries = cache.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(
+        entries
+            .iter()
+            .all(|entry| entry.source == Path::new("serde-1.0.0") && entry.fresh)
+    );
+    assert_eq!(cache.prune().unwrap(), 0);
+
+    // Unreadable ones are stale and pruned
+    let broken = cache_dir
+        .path()
+        .join("crates/serde-0.9.0-0123456789abcdef.json");
+    fs::write(&broken, "{").unwrap();
+    let stale: Vec<_> = cache
+        .entries()
+        .unwrap()
+        .into_iter()
+        .filter(|entry| !entry.fresh)
+        .map(|entry| entry.source)
+        .collect();
+    assert_eq!(stale, [PathBuf::from("serde-0.9.0")]);
+    assert_eq!(cache.prune().unwrap(), 1);
+    assert!(!broken.exists());
+    assert_eq!(cache.entries().unwrap().len(), 2);
+}
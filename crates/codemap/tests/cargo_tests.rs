@@ -0,0 +1,157 @@
+use codemap::{CargoManifest, CodemapOptions};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_package_and_targets() {
+    let input = r#"[package]
+name = "demo"
+version = "0.1.0"
+edition = "2024"
+description = "A demo crate"
+
+[lib]
+path = "src/demo.rs"
+proc-macro = true
+
+[[bin]]
+name = "demo-cli"
+path = "src/cli.rs"
+required-features = ["cli"]
+"#;
+    assert_eq!(
+        codemap_with(CargoManifest, input, CodemapOptions::default()),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\ndescription = \"A demo crate\"\n\n[lib]\npath = \"src/demo.rs\"\nproc-macro = true\n\n[[bin]]\nname = \"demo-cli\"\npath = \"src/cli.rs\"\nrequired-features = [\"cli\"]"
+    );
+    assert_eq!(
+        codemap_with(
+            CargoManifest,
+            input,
+            CodemapOptions::new().include_docs(false)
+        ),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[lib]\npath = \"src/demo.rs\"\nproc-macro = true\n\n[[bin]]\nname = \"demo-cli\"\npath = \"src/cli.rs\"\nrequired-features = [\"cli\"]"
+    );
+}
+
+#[test]
+fn test_features_and_dependencies() {
+    let input = r#"[package]
+name = "demo"
+
+[features]
+default = ["std"]
+std = []
+cli = ["dep:clap", "serde/std"]
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+clap = { version = "4", optional = true }
+
+[dev-dependencies]
+tempfile = "3"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#;
+    assert_eq!(
+        codemap_with(CargoManifest, input, CodemapOptions::default()),
+        "[package]\nname = \"demo\"\n\n[features]\ncli = [\"dep:clap\", \"serde/std\"]\ndefault = [\"std\"]\nstd = []\n\n[dependencies]\nclap, serde\n\n[dev-dependencies]\ntempfile\n\n[target.\"cfg(unix)\".dependencies]\nlibc"
+    );
+}
+
+#[test]
+fn test_workspace() {
+    let input = r#"[workspace]
+members = ["crates/*"]
+exclude = ["crates/old"]
+resolver = "2"
+
+[workspace.dependencies]
+serde = "1"
+"#;
+    assert_eq!(
+        codemap_with(CargoManifest, input, CodemapOptions::default()),
+        "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n\n[workspace.dependencies]\nserde"
+    );
+}
+
+#[test]
+fn test_line_numbers() {
+    let input = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"\nclap = \"4\"\n";
+    assert_eq!(
+        codemap_with(
+            CargoManifest,
+            input,
+            CodemapOptions::new().include_line_numbers(true)
+        ),
+        "# L1-L2\n[package]\nname = \"demo\"\n\n# L4-L6\n[dependencies]\nclap, serde"
+    );
+}
+
+#[test]
+fn test_other_toml_has_no_items() {
+    let input = "[tool]\nname = \"x\"\n";
+    assert_eq!(
+        codemap_with(CargoManifest, input, CodemapOptions::default()),
+        ""
+    );
+}
+
+#[test]
+fn test_workspace_crates_discovered() {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |path: &str, contents: &str| {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    };
+    write(
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n",
+    );
+    write("crates/a/Cargo.toml", "[package]\nname = \"a\"\n");
+    write("crates/a/src/lib.rs", "pub fn a() {}\n");
+    write("crates/a/src/main.rs", "fn main() {}\n");
+    write("crates/a/src/bin/tool.rs", "fn main() {}\n");
+    write("crates/a/examples/demo.rs", "fn main() {}\n");
+    write(
+        "crates/b/Cargo.toml",
+        "[package]\nname = \"b\"\n\n[lib]\npath = \"src/b.rs\"\n",
+    );
+    write("crates/b/src/b.rs", "pub fn b() {}\n");
+    write("crates/b/src/lib.rs", "pub fn unused() {}\n");
+    write("crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+    // Not a manifest despite its package table
+    write("pixi.toml", "[package]\nname = \"pixi\"\n");
+
+    let project = codemap::map_directory(dir.path(), &codemap::Options::default()).unwrap();
+    let manifest = |path: &str| {
+        project
+            .files
+            .iter()
+            .find(|(file, _)| file.to_string_lossy().replace('\\', "/") == path)
+            .unwrap()
+            .1
+            .map()
+    };
+    assert_eq!(
+        manifest("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/a\", \"crates/b\"]"
+    );
+    assert_eq!(
+        manifest("a/Cargo.toml"),
+        "[package]\nname = \"a\"\n\n[lib]\npath = \"src/lib.rs\"\n\n[[bin]]\nname = \"tool\"\npath = \"src/bin/tool.rs\"\n\n[[bin]]\nname = \"a\"\npath = \"src/main.rs\"\n\n[[example]]\nname = \"demo\"\npath = \"examples/demo.rs\""
+    );
+    assert_eq!(
+        manifest("b/Cargo.toml"),
+        "[package]\nname = \"b\"\n\n[lib]\npath = \"src/b.rs\""
+    );
+    assert!(
+        project
+            .files
+            .iter()
+            .all(|(file, _)| !file.ends_with("pixi.toml"))
+    );
+}
@@ -0,0 +1,13 @@
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, LanguageBackend, Mapper};
+
+/// Map `input` with `backend` and render it the way `codemap` prints it.
+pub fn codemap_with(
+    backend: impl LanguageBackend + 'static,
+    input: &str,
+    options: CodemapOptions,
+) -> String {
+    let mut mapper = Mapper::with_backend(Arc::new(backend), options).unwrap();
+    mapper.map(input).unwrap().map()
+}
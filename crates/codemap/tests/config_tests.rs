@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+
+use codemap::{
+    CodemapOptions, Config, Error, Format, MapSettings, Options, Visibility, fit_token_budget,
+    map_directory,
+};
+
+#[test]
+fn test_discover_codemap_toml_above_path() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+    fs::write(
+        dir.path().join("codemap.toml"),
+        "exclude = [\"tests/**\"]\nvisibility = \"crate\"\nformat = \"markdown\"\ntoken_budget = 100\n",
+    )
+    .unwrap();
+
+    let (file, config) = Config::discover(&dir.path().join("src/nested"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(file, dir.path().join("codemap.toml"));
+    // The pattern is for a directory outside of the mapped one
+    assert_eq!(config.exclude, vec!["../../tests/**".to_string()]);
+    assert_eq!(config.settings.visibility, Some(Visibility::Crate));
+    assert_eq!(config.format, Some(Format::Markdown));
+    assert_eq!(config.token_budget, Some(100));
+}
+
+#[test]
+fn test_discover_cargo_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\n\n[package.metadata.codemap]\nuses = false\n",
+    )
+    .unwrap();
+
+    let (file, config) = Config::discover(dir.path()).unwrap().unwrap();
+    assert_eq!(file, dir.path().join("Cargo.toml"));
+    assert_eq!(config.settings.uses, Some(false));
+}
+
+#[test]
+fn test_discover_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    let config = Config::discover(dir.path()).unwrap();
+    // Directories above the temporary one might have a configuration, but not
+    // the manifest without metadata
+    assert!(config.is_none_or(|(file, _)| file != dir.path().join("Cargo.toml")));
+}
+
+#[test]
+fn test_malformed_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("codemap.toml");
+    fs::write(&file, "visibility = \"everything\"\n").unwrap();
+    assert!(matches!(Config::load(&file), Err(Error::Config { path, .. }) if path == file));
+}
+
+#[test]
+fn test_unknown_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("codemap.toml");
+    for contents in [
+        "visibilty = \"crate\"\n",
+        "[[overrides]]\npath = \"gen/**\"\ndoc = false\n",
+    ] {
+        fs::write(&file, contents).unwrap();
+        assert!(matches!(
+            Config::load(&file),
+            Err(Error::Config { message, .. }) if message.starts_with("unknown field")
+        ));
+    }
+}
+
+#[test]
+fn test_error_location() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("codemap.toml");
+    fs::write(&file, "docs = false\nvisibility = crate\n").unwrap();
+    let Err(Error::Config { message, .. }) = Config::load(&file) else {
+        panic!("expected a configuration error");
+    };
+    assert_eq!(
+        message,
+        "string values must be quoted, expected literal string at line 2, column 14"
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\n\n[package.metadata.codemap]\nvisibilty = \"crate\"\n",
+    )
+    .unwrap();
+    let Err(Error::Config { message, .. }) = Config::discover(dir.path()) else {
+        panic!("expected a configuration error");
+    };
+    // Settings are checked as a whole, so errors point at their table
+    assert_eq!(message, "unknown field `visibilty` at line 4, column 1");
+}
+
+#[test]
+fn test_patterns_relative_to_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("crates/app/src/generated")).unwrap();
+    fs::write(
+        dir.path().join("crates/app/src/generated/out.rs"),
+        "pub fn generated() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("crates/app/src/lib.rs"),
+        "pub fn public() {}\nfn private() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("codemap.toml"),
+        r#"include = ["crates/*/src/**", "docs/**"]
+exclude = ["*.txt", "**/generated/**", "crates/app/src/generated/**"]
+
+[[overrides]]
+path = "crates/app/src/lib.rs"
+visibility = "private"
+"#,
+    )
+    .unwrap();
+
+    let mapped = dir.path().join("crates/app");
+    let (_, config) = Config::discover(&mapped).unwrap().unwrap();
+    assert_eq!(config.include, ["src/**", "../../docs/**"]);
+    assert_eq!(
+        config.exclude,
+        ["*.txt", "**/generated/**", "src/generated/**"]
+    );
+    assert_eq!(config.overrides[0].path, "src/lib.rs");
+
+    let (codemap, overrides) = config.map_options(&MapSettings::default());
+    let options = Options {
+        include: config.include,
+        exclude: config.exclude,
+        codemap,
+        overrides,
+        ..Options::default()
+    };
+    let project = map_directory(&mapped, &options).unwrap();
+    assert_eq!(project.files.len(), 1);
+    assert_eq!(
+        project.files[0].1.map(),
+        "pub fn public();\n\nfn private();"
+    );
+}
+
+#[test]
+fn test_flags_take_precedence() {
+    let config: Config = toml_config(
+        "visibility = \"private\"\ndocs = false\n\n[[overrides]]\npath = \"gen/**\"\nvisibility = \"public\"\n",
+    );
+    let flags = MapSettings {
+        docs: Some(true),
+        ..MapSettings::default()
+    };
+
+    let (codemap, overrides) = config.map_options(&flags);
+    assert_eq!(
+        codemap,
+        CodemapOptions::new().visibility(Visibility::Private)
+    );
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0].glob, "gen/**");
+    assert_eq!(overrides[0].codemap, CodemapOptions::new());
+}
+
+#[test]
+fn test_path_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("gen")).unwrap();
+    let source = "pub fn public() {}\nfn private() {}\n";
+    fs::write(dir.path().join("lib.rs"), source).unwrap();
+    fs::write(dir.path().join("gen/out.rs"), source).unwrap();
+
+    let config = toml_config(
+        "visibility = \"private\"\n\n[[overrides]]\npath = \"gen/**\"\nvisibility = \"public\"\n",
+    );
+    let (codemap, overrides) = config.map_options(&MapSettings::default());
+    let options = Options {
+        codemap,
+        overrides,
+        ..Options::default()
+    };
+
+    let project = map_directory(dir.path(), &options).unwrap();
+    let maps: Vec<_> = project
+        .files
+        .iter()
+        .map(|(path, file_map)| (path.as_path(), file_map.map()))
+        .collect();
+    assert_eq!(
+        maps,
+        vec![
+            (Path::new("gen/out.rs"), "pub fn public();".to_string()),
+            (
+                Path::new("lib.rs"),
+                "pub fn public();\n\nfn private();".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_fit_token_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "pub fn first() {}\npub fn second() {}\npub fn third() {}\n",
+    )
+    .unwrap();
+    let mut files = map_directory(dir.path(), &Options::default())
+        .unwrap()
+        .files;
+
+    // Each `pub fn name();` is four or five tokens
+    assert_eq!(fit_token_budget(&mut files, 10), 1);
+    assert_eq!(files[0].1.map(), "pub fn first();\n\npub fn second();");
+}
+
+fn toml_config(contents: &str) -> Config {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("codemap.toml");
+    fs::write(&file, contents).unwrap();
+    Config::load(&file).unwrap()
+}
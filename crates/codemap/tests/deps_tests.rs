@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use codemap::{
+    Cache, Error, LockedPackage, Options, direct_dependencies, find_locked, locate_package,
+    map_dependency, read_lockfile,
+};
+
+const LOCKFILE: &str = r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "helper",
+ "rand 0.8.5",
+ "serde",
+]
+
+[[package]]
+name = "helper"
+version = "0.2.0"
+dependencies = [
+ "rand 0.7.3",
+]
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "6a6b1679d49b24bbfe0c803429aa1874472f50d9b363131f0e89fc356b544d03"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "rand 0.7.3 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn lockfile() -> Vec<LockedPackage> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Cargo.lock");
+    write(&path, LOCKFILE);
+    read_lockfile(&path).unwrap()
+}
+
+fn names(packages: &[&LockedPackage]) -> Vec<String> {
+    packages
+        .iter()
+        .map(|package| format!("{}@{}", package.name, package.version))
+        .collect()
+}
+
+#[test]
+fn test_read_lockfile() {
+    let packages = lockfile();
+    assert_eq!(packages.len(), 5);
+    assert_eq!(packages[0].source, None);
+    assert_eq!(packages[0].dependencies, ["helper", "rand 0.8.5", "serde"]);
+    assert_eq!(packages[3].dependencies, ["rand 0.7.3"]);
+    assert!(packages[4].is_published());
+}
+
+#[test]
+fn test_select_dependencies() {
+    let packages = lockfile();
+    assert_eq!(
+        names(&direct_dependencies(&packages, None).unwrap()),
+        ["helper@0.2.0", "rand@0.8.5", "serde@1.0.200"]
+    );
+    assert!(matches!(
+        direct_dependencies(&packages, Some("other")),
+        Err(Error::UnknownPackage { .. })
+    ));
+
+    assert_eq!(
+        names(&find_locked(&packages, "rand").unwrap()),
+        ["rand@0.7.3", "rand@0.8.5"]
+    );
+    assert_eq!(
+        names(&find_locked(&packages, "rand@0.7.3").unwrap()),
+        ["rand@0.7.3"]
+    );
+    assert!(find_locked(&packages, "rand@1.0.0").is_err());
+}
+
+#[test]
+fn test_path_dependencies() {
+    let packages = lockfile();
+    // `helper` is a path dependency of `app`, so its own dependencies aren't
+    // direct ones
+    assert_eq!(
+        names(&direct_dependencies(&packages, Some("app")).unwrap()),
+        ["helper@0.2.0", "rand@0.8.5", "serde@1.0.200"]
+    );
+    assert_eq!(
+        names(&direct_dependencies(&packages, Some("helper")).unwrap()),
+        ["rand@0.7.3"]
+    );
+}
+
+#[test]
+fn test_locate_package() {
+    let packages = lockfile();
+    // The path dependency is next to the project
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("app");
+    let cargo_home = tempfile::tempdir().unwrap();
+    let manifest = |name: &str, version: &str| {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"{}\"\n",
+            name, version
+        )
+    };
+
+    write(
+        &project.join("Cargo.toml"),
+        &format!(
+            "{}\n[dependencies]\nhelper = {{ path = \"../helper\" }}\n",
+            manifest("app", "0.1.0")
+        ),
+    );
+    let helper = project.join("../helper");
+    write(&helper.join("Cargo.toml"), &manifest("helper", "0.2.0"));
+    write(
+        &project.join("vendor/serde/Cargo.toml"),
+        &manifest("serde", "1.0.200"),
+    );
+    let registry = cargo_home
+        .path()
+        .join("registry/src/index.crates.io-1949cf8c6b5b557f");
+    write(
+        &registry.join("rand-0.8.5/Cargo.toml"),
+        &manifest("rand", "0.8.5"),
+    );
+
+    let locate = |spec: &str| {
+        let package = find_locked(&packages, spec).unwrap()[0];
+        locate_package(package, &project, Some(cargo_home.path()))
+    };
+    assert_eq!(locate("rand@0.8.5"), Some(registry.join("rand-0.8.5")));
+    assert_eq!(locate("rand@0.7.3"), None);
+    assert_eq!(locate("serde"), Some(project.join("vendor/serde")));
+    assert_eq!(locate("helper"), Some(helper));
+}
+
+#[test]
+fn test_map_dependency() {
+    let packages = lockfile();
+    let package = find_locked(&packages, "serde").unwrap()[0];
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        &dir.path().join("Cargo.toml"),
+        "[package]\nname = \"serde\"\nversion = \"1.0.200\"\n",
+    );
+    write(
+        &dir.path().join("src/lib.rs"),
+        "pub trait Serialize {}\nfn private() {}\n",
+    );
+    write(&dir.path().join("tests/test.rs"), "pub fn test() {}\n");
+    write(&dir.path().join("build.py"), "def build():\n    pass\n");
+
+    let cache_dir = tempfile::tempdir().unwrap();
+    let options = Options {
+        cache: Some(Cache::open(cache_dir.path(), "").unwrap()),
+        ..Options::default()
+    };
+    let project = map_dependency(package, dir.path(), &options).unwrap();
+    let paths: Vec<PathBuf> = project.files.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("serde-1.0.200/Cargo.toml"),
+            PathBuf::from("serde-1.0.200/src/lib.rs"),
+        ]
+    );
+    assert_eq!(project.files[1].1.map(), "pub trait Serialize {}");
+
+    // Published crates are cached per version, so later changes aren't seen
+    write(&dir.path().join("src/lib.rs"), "pub trait Changed {}\n");
+    let cached = map_dependency(package, dir.path(), &options).unwrap();
+    assert_eq!(cached.files, project.files);
+    // The files of the crate aren't stored on their own too
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    let sources: Vec<_> = cache
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.source)
+        .collect();
+    assert_eq!(sources, [PathBuf::from("serde-1.0.200")]);
+
+    // Mapping with other options leaves the entry of these alone
+    let other = Options {
+        cache: Some(Cache::open(cache_dir.path(), "private").unwrap()),
+        ..Options::default()
+    };
+    let remapped = map_dependency(package, dir.path(), &other).unwrap();
+    assert_eq!(remapped.files[1].1.map(), "pub trait Changed {}");
+    let cached = map_dependency(package, dir.path(), &options).unwrap();
+    assert_eq!(cached.files, project.files);
+}
+
+#[test]
+fn test_map_dependency_show_origin() {
+    let packages = lockfile();
+    let package = find_locked(&packages, "serde").unwrap()[0];
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        &dir.path().join("Cargo.toml"),
+        "[package]\nname = \"serde\"\nversion = \"1.0.200\"\n",
+    );
+    write(
+        &dir.path().join("src/lib.rs"),
+        "mod ser;\npub use ser::Serialize;\n",
+    );
+    write(&dir.path().join("src/ser.rs"), "pub trait Serialize {}\n");
+
+    // Origins are added after the files are mapped, so one cache holds both
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = Cache::open(cache_dir.path(), "").unwrap();
+    let map = |show_origin| {
+        let options = Options {
+            cache: Some(cache.clone()),
+            show_origin,
+            ..Options::default()
+        };
+        let project = map_dependency(package, dir.path(), &options).unwrap();
+        project.files[1].1.map()
+    };
+    assert_eq!(map(false), "pub trait Serialize {}");
+    assert_eq!(
+        map(true),
+        "// defined at crate::ser::Serialize in src/ser.rs\npub trait Serialize {}"
+    );
+    assert_eq!(map(false), "pub trait Serialize {}");
+}
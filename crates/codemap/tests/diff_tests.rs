@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use codemap::{FileMap, Format, diff_maps, map_source, render_diff};
+
+fn maps(files: &[(&str, &str)]) -> Vec<(PathBuf, FileMap)> {
+    files
+        .iter()
+        .map(|(path, source)| (PathBuf::from(path), map_source(source).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_diff_unchanged() {
+    let old = maps(&[("src/lib.rs", "pub fn public_function() {}")]);
+    let new = maps(&[("src/lib.rs", "pub fn public_function() { todo!() }")]);
+    assert!(diff_maps(&old, &new).is_empty());
+}
+
+#[test]
+fn test_diff_added_removed_changed() {
+    let old = maps(&[(
+        "src/lib.rs",
+        "pub fn removed() {}\npub fn changed(param: i32) {}\nfn private() {}",
+    )]);
+    let new = maps(&[(
+        "src/lib.rs",
+        "pub fn changed(param: i64) {}\npub fn added() {}\npub fn private() {}",
+    )]);
+
+    let diffs = diff_maps(&old, &new);
+    assert_eq!(diffs.len(), 1);
+    let diff = &diffs[0];
+    assert_eq!(diff.path, PathBuf::from("src/lib.rs"));
+
+    let names = |items: &[codemap::Item]| -> Vec<String> {
+        items.iter().filter_map(|item| item.name.clone()).collect()
+    };
+    assert_eq!(names(&diff.removed), ["removed"]);
+    assert_eq!(names(&diff.added), ["added", "private"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].0.text, "pub fn changed(param: i32);");
+    assert_eq!(diff.changed[0].1.text, "pub fn changed(param: i64);");
+}
+
+#[test]
+fn test_diff_added_and_removed_files() {
+    let old = maps(&[("src/old.rs", "pub struct Old;")]);
+    let new = maps(&[("src/new.rs", "pub struct New;"), ("src/empty.rs", "")]);
+
+    let diffs = diff_maps(&old, &new);
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(diffs[0].path, PathBuf::from("src/old.rs"));
+    assert_eq!(diffs[0].removed[0].text, "pub struct Old;");
+    assert_eq!(diffs[1].path, PathBuf::from("src/new.rs"));
+    assert_eq!(diffs[1].added[0].text, "pub struct New;");
+}
+
+#[test]
+fn test_diff_same_name_different_kind() {
+    let old = maps(&[("src/lib.rs", "pub struct Thing;")]);
+    let new = maps(&[("src/lib.rs", "pub enum Thing {}")]);
+
+    let diffs = diff_maps(&old, &new);
+    assert_eq!(diffs[0].removed.len(), 1);
+    assert_eq!(diffs[0].added.len(), 1);
+    assert!(diffs[0].changed.is_empty());
+}
+
+#[test]
+fn test_diff_path_escaped_in_xml() {
+    let new = maps(&[("src/a&b.rs", "pub struct New;")]);
+
+    let xml = render_diff(&diff_maps(&[], &new), Format::Xml);
+    assert!(xml.contains("<file path=\"src/a&amp;b.rs\">"));
+}
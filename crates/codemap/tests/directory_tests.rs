@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::PathBuf;
+
+use codemap::{
+    CodemapOptions, Error, Options, PathFilter, Workspace, map_directory, map_directory_iter,
+};
+use std::sync::Arc;
+
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/shapes")).unwrap();
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "mod shapes;\npub use shapes::Circle;\npub fn area() -> f64 { 0.0 }\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/shapes/mod.rs"),
+        "pub struct Circle {\n    pub radius: f64,\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_map_directory() {
+    let dir = project();
+    let project = map_directory(dir.path(), &Options::default()).unwrap();
+
+    let paths: Vec<_> = project.files.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("src/lib.rs"),
+            PathBuf::from("src/shapes/mod.rs")
+        ]
+    );
+    assert!(project.errors.is_empty());
+
+    // Re-exports are expanded to the item they point at
+    assert_eq!(
+        project.files[0].1.map(),
+        "pub struct Circle {\n    pub radius: f64\n}\n\npub fn area() -> f64;"
+    );
+}
+
+#[test]
+fn test_map_directory_reports_unreadable_files() {
+    let dir = project();
+    fs::write(dir.path().join("src/binary.rs"), [0xff, 0xfe, 0x00]).unwrap();
+
+    let project = map_directory(dir.path(), &Options::default()).unwrap();
+    assert_eq!(project.files.len(), 2);
+    assert_eq!(project.errors.len(), 1);
+    assert!(
+        matches!(&project.errors[0], Error::InvalidUtf8 { path } if path.ends_with("src/binary.rs"))
+    );
+}
+
+#[test]
+fn test_map_directory_reports_large_files() {
+    let dir = project();
+    let options = Options {
+        max_file_size: Some(50),
+        ..Options::default()
+    };
+
+    let project = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(project.files.len(), 1);
+    assert_eq!(project.errors.len(), 1);
+    assert!(matches!(
+        &project.errors[0],
+        Error::TooLarge { path, limit: 50, .. } if path.ends_with("src/lib.rs")
+    ));
+}
+
+#[test]
+fn test_map_directory_invalid_glob() {
+    let dir = project();
+    let options = Options {
+        include: vec!["src/{lib.rs".to_string()],
+        ..Options::default()
+    };
+    assert!(matches!(
+        map_directory(dir.path(), &options),
+        Err(Error::Glob(_))
+    ));
+}
+
+#[test]
+fn test_map_directory_iter() {
+    let dir = project();
+    let mut paths: Vec<_> = map_directory_iter(dir.path(), &Options::default())
+        .unwrap()
+        .map(|result| result.unwrap().0)
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("src/lib.rs"),
+            PathBuf::from("src/shapes/mod.rs")
+        ]
+    );
+}
+
+#[test]
+fn test_map_directory_line_numbers() {
+    let dir = project();
+    let options = Options {
+        codemap: CodemapOptions::new().include_line_numbers(true),
+        ..Options::default()
+    };
+
+    // Re-exported items name the file their lines are in
+    let project = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(
+        project.files[0].1.map(),
+        "// src/shapes/mod.rs L1-L3\npub struct Circle {\n    pub radius: f64\n}\n\n// L3-L3\npub fn area() -> f64;"
+    );
+
+    let (_, file_map) = map_directory_iter(dir.path(), &options)
+        .unwrap()
+        .map(Result::unwrap)
+        .find(|(path, _)| path == &PathBuf::from("src/shapes/mod.rs"))
+        .unwrap();
+    assert_eq!(
+        file_map.map(),
+        "// L1-L3\npub struct Circle {\n    pub radius: f64\n}"
+    );
+}
+
+fn workspace() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let write = |path: &str, contents: &str| {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    };
+    write("Cargo.toml", "[package]\nname = \"app\"\n\n[workspace]\n");
+    write("src/main.rs", "pub fn main() {}\n");
+    write("crates/util/Cargo.toml", "[package]\nname = \"util\"\n");
+    write("crates/util/src/lib.rs", "pub fn util() {}\n");
+    write("scripts/build.py", "def build():\n    pass\n");
+    dir
+}
+
+fn paths(project: &codemap::ProjectMap) -> Vec<PathBuf> {
+    project.files.iter().map(|(path, _)| path.clone()).collect()
+}
+
+#[test]
+fn test_map_directory_groups_crates() {
+    let dir = workspace();
+    let project = map_directory(dir.path(), &Options::default()).unwrap();
+    assert_eq!(
+        paths(&project),
+        vec![
+            PathBuf::from("app/Cargo.toml"),
+            PathBuf::from("app/scripts/build.py"),
+            PathBuf::from("app/src/main.rs"),
+            PathBuf::from("util/Cargo.toml"),
+            PathBuf::from("util/src/lib.rs"),
+        ]
+    );
+
+    // Crates are those of the mapped manifests
+    let options = Options {
+        exclude: vec!["Cargo.toml".to_string()],
+        ..Options::default()
+    };
+    let project = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(
+        paths(&project),
+        vec![
+            PathBuf::from("crates/util/src/lib.rs"),
+            PathBuf::from("scripts/build.py"),
+            PathBuf::from("src/main.rs"),
+        ]
+    );
+}
+
+#[test]
+fn test_map_directory_package() {
+    let dir = workspace();
+    let options = Options {
+        package: Some("util".to_string()),
+        ..Options::default()
+    };
+    let project = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(
+        paths(&project),
+        vec![
+            PathBuf::from("util/Cargo.toml"),
+            PathBuf::from("util/src/lib.rs"),
+        ]
+    );
+
+    // Packages nested in the selected one are left out
+    let options = Options {
+        package: Some("app".to_string()),
+        ..Options::default()
+    };
+    let project = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(
+        paths(&project),
+        vec![
+            PathBuf::from("app/Cargo.toml"),
+            PathBuf::from("app/scripts/build.py"),
+            PathBuf::from("app/src/main.rs"),
+        ]
+    );
+}
+
+#[test]
+fn test_map_directory_unknown_package() {
+    let dir = workspace();
+    let options = Options {
+        package: Some("missing".to_string()),
+        ..Options::default()
+    };
+    assert!(matches!(
+        map_directory(dir.path(), &options),
+        Err(Error::UnknownPackage { name }) if name == "missing"
+    ));
+}
+
+#[test]
+fn test_path_filter() {
+    let dir = project();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::write(dir.path().join(".gitignore"), "/target\n").unwrap();
+    let options = Options {
+        exclude: vec!["src/generated/**".to_string()],
+        ..Options::default()
+    };
+    let filter = PathFilter::new(dir.path(), &options).unwrap();
+
+    let is_mapped = |path: &str| filter.is_mapped(&dir.path().join(path));
+    assert!(is_mapped("src/lib.rs"));
+    assert!(is_mapped("src/shapes"));
+    // Removed files and directories are judged by their name
+    assert!(is_mapped("src/removed.rs"));
+    assert!(is_mapped("src/removed"));
+
+    assert!(!is_mapped("target/debug/build.rs"));
+    assert!(!is_mapped("target"));
+    assert!(!is_mapped("src/generated/types.rs"));
+    assert!(!is_mapped(".git/index"));
+    assert!(!is_mapped("src/notes.txt"));
+}
+
+#[test]
+fn test_map_directory_with_workspace() {
+    let dir = project();
+    let options = Options {
+        workspace: Some(Arc::new(Workspace::new())),
+        ..Options::default()
+    };
+    let first = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(
+        first.files,
+        map_directory(dir.path(), &options).unwrap().files
+    );
+
+    fs::write(dir.path().join("src/lib.rs"), "pub fn changed() {}\n").unwrap();
+    let changed = map_directory(dir.path(), &options).unwrap();
+    assert_eq!(changed.files[0].1.map(), "pub fn changed();");
+    assert_eq!(changed.files[1], first.files[1]);
+}
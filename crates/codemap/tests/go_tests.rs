@@ -0,0 +1,353 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, Go, ItemKind, Mapper, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_exported_functions() {
+    let input = r#"package util
+
+func Map[K comparable, V any](
+	m map[K]V,
+) []K {
+	return nil
+}
+
+func Parse(
+	s string, // the input
+	base int,
+) (int, error) {
+	return 0, nil
+}
+
+func helper() {}
+"#;
+    assert_eq!(
+        codemap_with(Go, input, CodemapOptions::new()),
+        "package util\n\nfunc Map[K comparable, V any](m map[K]V) []K\n\nfunc Parse(s string, base int) (int, error)"
+    );
+    assert_eq!(
+        codemap_with(
+            Go,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        "package util\n\nfunc Map[K comparable, V any](m map[K]V) []K\n\nfunc Parse(s string, base int) (int, error)\n\nfunc helper()"
+    );
+}
+
+#[test]
+fn test_struct_with_exported_fields() {
+    let input = r#"package server
+
+type Server struct {
+	Addr    string `json:"addr"`
+	handler http.Handler
+	Name, secret string
+	*Logger
+	sync.Mutex
+	mu sync.Mutex
+}
+"#;
+    let expected = r#"package server
+
+type Server struct {
+    Addr string `json:"addr"`
+    Name string
+    *Logger
+    sync.Mutex
+    // unexported fields
+}"#;
+    assert_eq!(codemap_with(Go, input, CodemapOptions::new()), expected);
+
+    let expected = r#"package server
+
+type Server struct {
+    Addr string
+    handler http.Handler
+    Name, secret string
+    *Logger
+    sync.Mutex
+    mu sync.Mutex
+}"#;
+    assert_eq!(
+        codemap_with(
+            Go,
+            input,
+            CodemapOptions::new()
+                .visibility(Visibility::Private)
+                .include_attributes(false)
+        ),
+        expected
+    );
+}
+
+#[test]
+fn test_interface_methods() {
+    let input = r#"package io
+
+type ReadCloser interface {
+	Read(p []byte) (n int, err error)
+	Closer
+	reset()
+}
+
+type empty interface{}
+"#;
+    let expected = r#"package io
+
+type ReadCloser interface {
+    Read(p []byte) (n int, err error)
+    Closer
+    // unexported methods
+}"#;
+    assert_eq!(codemap_with(Go, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_methods_under_receiver_type() {
+    let input = r#"package server
+
+type Server struct{}
+
+func New() *Server { return &Server{} }
+
+func (s *Server) Start() error { return nil }
+
+func (s Server) stop() {}
+
+func (l List[T]) Len() int { return 0 }
+"#;
+    let expected = r#"package server
+
+type Server struct{}
+
+func (s *Server) Start() error
+
+func New() *Server
+
+func (l List[T]) Len() int"#;
+    assert_eq!(codemap_with(Go, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_constants_and_variables() {
+    let input = r#"package state
+
+const Max = 10
+
+const (
+	Idle State = iota
+	Busy
+	hidden
+)
+
+var (
+	Default = New()
+	cache   = map[string]int{}
+)
+
+var Table = map[string]int{
+	"a": 1,
+}
+
+var Version, build = "1", "dev"
+
+var First, second, Third int
+
+var Left, right = split()
+"#;
+    let expected = r#"package state
+
+const Max = 10
+
+const (
+    Idle State = iota
+    Busy
+)
+
+var (
+    Default = New()
+)
+
+var Table = ...
+
+var Version = "1"
+
+var First, Third int
+
+var Left = ..."#;
+    assert_eq!(codemap_with(Go, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_grouped_type_declaration() {
+    let input = r#"package ids
+
+type (
+	ID    string
+	Alias = int
+	local bool
+)
+"#;
+    assert_eq!(
+        codemap_with(Go, input, CodemapOptions::new()),
+        "package ids\n\ntype ID string\n\ntype Alias = int"
+    );
+}
+
+#[test]
+fn test_type_kinds() {
+    let input = r#"package shapes
+
+type Point struct{ X, Y int }
+
+type Shape interface{ Area() float64 }
+
+type Celsius float64
+
+type Coord = Point
+"#;
+    let mut mapper = Mapper::with_backend(Arc::new(Go), CodemapOptions::new()).unwrap();
+    let kinds: Vec<ItemKind> = mapper
+        .map(input)
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|item| item.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ItemKind::Module,
+            ItemKind::Struct,
+            ItemKind::Interface,
+            ItemKind::TypeAlias,
+            ItemKind::TypeAlias
+        ]
+    );
+}
+
+#[test]
+fn test_doc_comments() {
+    let input = r#"// Package server serves.
+package server
+
+// Server serves requests.
+// It is safe for concurrent use.
+type Server struct {
+	// Addr to listen on.
+	Addr string
+}
+
+// Start starts the server.
+func (s *Server) Start() error { return nil }
+"#;
+    let expected = r#"// Package server serves.
+package server
+
+// Server serves requests.
+type Server struct {
+    // Addr to listen on.
+    Addr string
+}
+
+// Start starts the server.
+func (s *Server) Start() error"#;
+    assert_eq!(codemap_with(Go, input, CodemapOptions::new()), expected);
+    assert_eq!(
+        codemap_with(Go, input, CodemapOptions::new().include_docs(false)),
+        "package server\n\ntype Server struct {\n    Addr string\n}\n\nfunc (s *Server) Start() error"
+    );
+}
+
+#[test]
+fn test_imports_only_when_private() {
+    let input = r#"package main
+
+import "fmt"
+
+func Run() {}
+"#;
+    assert_eq!(
+        codemap_with(Go, input, CodemapOptions::new()),
+        "package main\n\nfunc Run()"
+    );
+    assert_eq!(
+        codemap_with(
+            Go,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        "package main\n\nimport \"fmt\"\n\nfunc Run()"
+    );
+}
+
+#[test]
+fn test_package_grouped_in_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("server")).unwrap();
+    std::fs::write(
+        dir.path().join("server/server.go"),
+        "// Package server serves.\npackage server\n\ntype Server struct{}\n\nfunc (s *Server) Start() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("server/stop.go"),
+        "package server\n\nfunc (s *Server) Stop() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("main.go"),
+        "package main\n\nfunc Main() {}\n",
+    )
+    .unwrap();
+
+    let project = codemap::map_directory(dir.path(), &codemap::Options::default()).unwrap();
+    assert_eq!(
+        project.render(codemap::Format::Markdown),
+        "## main.go\n\n```go\npackage main\n\nfunc Main()\n```\n\n## server/*.go\n\n```go\n// Package server serves.\npackage server\n\ntype Server struct{}\n\nfunc (s *Server) Start()\nfunc (s *Server) Stop()\n```\n\n"
+    );
+}
+
+#[test]
+fn test_grouped_items_name_their_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.go"),
+        "package server\n\ntype Server struct{}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("b.go"),
+        "package server\n\n\n\nfunc Run() {}\n",
+    )
+    .unwrap();
+
+    let options = codemap::Options {
+        codemap: CodemapOptions::new().include_line_numbers(true),
+        ..codemap::Options::default()
+    };
+    let project = codemap::map_directory(dir.path(), &options).unwrap();
+    let items: Vec<_> = project.files[0]
+        .1
+        .items
+        .iter()
+        .map(|item| (item.text.as_str(), item.file.as_deref()))
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("// a.go L1-L1\npackage server", Some(Path::new("a.go"))),
+            (
+                "// a.go L3-L3\ntype Server struct{}",
+                Some(Path::new("a.go"))
+            ),
+            ("// b.go L5-L5\nfunc Run()", Some(Path::new("b.go"))),
+        ]
+    );
+}
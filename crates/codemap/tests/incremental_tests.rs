@@ -0,0 +1,90 @@
+use codemap::{Mapper, Workspace, map_source};
+use std::path::Path;
+
+const ORIGINAL: &str = r#"
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+}
+
+pub fn distance(a: &Point, b: &Point) -> f64 {
+    0.0
+}
+"#;
+
+// Apply each version in turn and check the incremental result matches a
+// fresh parse
+fn assert_updates(versions: &[&str]) {
+    let mut mapper = Mapper::new().unwrap();
+    let mut file = mapper.parse_file(versions[0]).unwrap();
+    assert_eq!(file.file_map(), &map_source(versions[0]).unwrap());
+
+    for version in &versions[1..] {
+        mapper.update_file(&mut file, version).unwrap();
+        assert_eq!(file.source(), *version);
+        assert_eq!(file.file_map(), &map_source(version).unwrap());
+    }
+}
+
+#[test]
+fn test_update_insert_item() {
+    let inserted = ORIGINAL.replace(
+        "pub fn distance",
+        "pub enum Axis {\n    X,\n    Y,\n}\n\npub fn distance",
+    );
+    assert_updates(&[ORIGINAL, &inserted]);
+}
+
+#[test]
+fn test_update_remove_and_change_items() {
+    let removed = ORIGINAL.replace(
+        "pub fn distance(a: &Point, b: &Point) -> f64 {\n    0.0\n}\n",
+        "",
+    );
+    let changed = ORIGINAL
+        .replace("pub y: i32", "y: i32")
+        .replace("-> f64", "-> f32");
+    assert_updates(&[ORIGINAL, &removed, &changed, ORIGINAL]);
+}
+
+#[test]
+fn test_update_through_syntax_error() {
+    let broken = ORIGINAL.replace("pub x: i32,", "pub x: i32 = ,");
+    assert_updates(&[ORIGINAL, &broken, ORIGINAL]);
+}
+
+#[test]
+fn test_update_multibyte_characters() {
+    let original = "/// Größe\npub const SIZE: &str = \"äö\";\n";
+    let changed = "/// Größe\npub const SIZE: &str = \"äü\";\npub const ÉCHELLE: u8 = 1;\n";
+    assert_updates(&[original, changed, "", original]);
+}
+
+#[test]
+fn test_workspace_maps_incrementally() {
+    let workspace = Workspace::new();
+    let mut mapper = Mapper::new().unwrap();
+    let path = Path::new("src/lib.rs");
+
+    assert_eq!(
+        workspace.map(&mut mapper, path, ORIGINAL).unwrap(),
+        map_source(ORIGINAL).unwrap()
+    );
+    assert_eq!(workspace.len(), 1);
+
+    let changed = ORIGINAL.replace("distance", "dist");
+    assert_eq!(
+        workspace.map(&mut mapper, path, &changed).unwrap(),
+        map_source(&changed).unwrap()
+    );
+    assert_eq!(workspace.len(), 1);
+
+    workspace.remove(path);
+    assert!(workspace.is_empty());
+}
@@ -0,0 +1,221 @@
+use codemap::{CodemapOptions, Java, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_class_with_members() {
+    let input = r#"package com.example;
+
+import java.util.List;
+
+public final class Box<T extends Comparable<T>> extends Base implements Runnable {
+    public static final int MAX = 10;
+    private int count;
+    protected String name = "box", other;
+    int size;
+
+    public Box(T value) throws IOException {
+        this.value = value;
+    }
+
+    public <R> List<R> map(
+        Function<? super T, R> f
+    ) {
+        return null;
+    }
+
+    private void reset() {}
+}
+"#;
+    let expected = r#"package com.example;
+
+public final class Box<T extends Comparable<T>> extends Base implements Runnable {
+    public static final int MAX = 10;
+    protected String name, other;
+    public Box(T value) throws IOException;
+    public <R> List<R> map( Function<? super T, R> f );
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_visibility_levels() {
+    let input = r#"public class Public {
+    public void a() {}
+    protected void b() {}
+    void c() {}
+    private void d() {}
+}
+
+class PackagePrivate {}
+"#;
+    assert_eq!(
+        codemap_with(Java, input, CodemapOptions::new()),
+        "public class Public {\n    public void a();\n    protected void b();\n}"
+    );
+    assert_eq!(
+        codemap_with(
+            Java,
+            input,
+            CodemapOptions::new().visibility(Visibility::Crate)
+        ),
+        "public class Public {\n    public void a();\n    protected void b();\n    void c();\n}\n\nclass PackagePrivate {}"
+    );
+    assert_eq!(
+        codemap_with(
+            Java,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        "public class Public {\n    public void a();\n    protected void b();\n    void c();\n    private void d();\n}\n\nclass PackagePrivate {}"
+    );
+}
+
+#[test]
+fn test_interface_members_are_public() {
+    let input = r#"public interface Shape extends Comparable<Shape> {
+    int SIDES = 0;
+    double area();
+    default String name() { return "shape"; }
+    private void helper() {}
+}
+"#;
+    let expected = r#"public interface Shape extends Comparable<Shape> {
+    int SIDES = 0;
+    double area();
+    default String name();
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_enum_constants_and_methods() {
+    let input = r#"public enum Kind {
+    A,
+    B(1) {
+        void special() {}
+    };
+
+    Kind() {}
+    Kind(int code) {}
+
+    public int code() { return 0; }
+}
+
+public enum Empty { ONE, TWO }
+"#;
+    let expected = r#"public enum Kind {
+    A,
+    B(1);
+    public int code();
+}
+
+public enum Empty {
+    ONE,
+    TWO
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_records_and_nested_types() {
+    let input = r#"public record Point(int x, int y) implements Shape {
+    public Point {
+        check(x);
+    }
+
+    public double area() { return 0; }
+
+    public static class Builder {
+        public Builder x(int x) { return this; }
+    }
+}
+"#;
+    let expected = r#"public record Point(int x, int y) implements Shape {
+    public double area();
+    public static class Builder {
+        public Builder x(int x);
+    }
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_annotations() {
+    let input = r#"@Entity
+@Table(name = "users")
+public class User {
+    @Id
+    @GeneratedValue(strategy = GenerationType.AUTO)
+    public Long id;
+
+    @Override
+    public String toString() { return ""; }
+}
+
+public @interface Marker {
+    String value() default "";
+}
+"#;
+    let expected = r#"@Entity
+@Table(name = "users")
+public class User {
+    @Id
+    @GeneratedValue(strategy = GenerationType.AUTO)
+    public Long id;
+    @Override
+    public String toString();
+}
+
+public @interface Marker {
+    String value() default "";
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+    assert_eq!(
+        codemap_with(Java, input, CodemapOptions::new().include_attributes(false)),
+        "public class User {\n    public Long id;\n    public String toString();\n}\n\npublic @interface Marker {\n    String value() default \"\";\n}"
+    );
+}
+
+#[test]
+fn test_javadoc_summaries() {
+    let input = r#"/**
+ * A box of things.
+ *
+ * @param <T> the things
+ */
+public class Box<T> {
+    /** Creates an empty box. */
+    public Box() {}
+
+    // Not Javadoc
+    public void clear() {}
+}
+"#;
+    let expected = r#"/** A box of things. */
+public class Box<T> {
+    /** Creates an empty box. */
+    public Box();
+    public void clear();
+}"#;
+    assert_eq!(codemap_with(Java, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_imports_only_when_private() {
+    let input = "import java.util.List;\nimport static java.lang.Math.max;\n\npublic class A {}\n";
+    assert_eq!(
+        codemap_with(Java, input, CodemapOptions::new()),
+        "public class A {}"
+    );
+    assert_eq!(
+        codemap_with(
+            Java,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        "import java.util.List;\n\nimport static java.lang.Math.max;\n\npublic class A {}"
+    );
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, Kotlin, Mapper, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_top_level_functions_and_properties() {
+    let input = r#"@file:JvmName("Strings")
+package com.example.util
+
+import kotlin.math.max
+
+const val MAX_LENGTH = 80
+val EMPTY: String = ""
+
+/**
+ * Shortens a line.
+ *
+ * @param limit the length
+ */
+fun <T : CharSequence> T.shorten(
+    limit: Int = MAX_LENGTH,
+): String {
+    return toString().take(limit)
+}
+
+fun double(x: Int) = x *
+    2
+
+internal fun helper(): Int = 1
+
+private fun secret() {}
+"#;
+    let expected = r#"package com.example.util
+
+const val MAX_LENGTH = 80
+
+val EMPTY: String
+
+/** Shortens a line. */
+fun <T : CharSequence> T.shorten( limit: Int = MAX_LENGTH, ): String
+
+fun double(x: Int)"#;
+    assert_eq!(codemap_with(Kotlin, input, CodemapOptions::new()), expected);
+
+    let options = CodemapOptions::new()
+        .visibility(Visibility::Crate)
+        .include_docs(false);
+    assert!(
+        codemap_with(Kotlin, input, options)
+            .ends_with("fun double(x: Int)\n\ninternal fun helper(): Int")
+    );
+
+    let options = CodemapOptions::new().visibility(Visibility::Private);
+    let map = codemap_with(Kotlin, input, options);
+    assert!(map.starts_with("package com.example.util\n\nimport kotlin.math.max\n\n"));
+    assert!(map.ends_with("internal fun helper(): Int\n\nprivate fun secret()"));
+}
+
+#[test]
+fn test_data_classes() {
+    let input = r#"data class User(
+    val name: String,
+    val age: Int = 0,
+) : Entity {
+    val isAdult: Boolean
+        get() = age >= 18
+
+    private val cache = mutableMapOf<String, Int>()
+
+    init {
+        require(age >= 0)
+    }
+
+    fun greet(): String = "Hi, $name"
+
+    internal fun reset() {}
+
+    companion object {
+        fun of(name: String) = User(name)
+    }
+}
+
+data class Point(val x: Int, val y: Int)
+"#;
+    let expected = r#"data class User( val name: String, val age: Int = 0, ) : Entity {
+    val isAdult: Boolean
+    fun greet(): String
+    companion object {
+        fun of(name: String)
+    }
+}
+
+data class Point(val x: Int, val y: Int)"#;
+    assert_eq!(codemap_with(Kotlin, input, CodemapOptions::new()), expected);
+
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert!(codemap_with(Kotlin, input, options).starts_with("// L1-L21\ndata class"));
+}
+
+#[test]
+fn test_interfaces_enums_and_annotations() {
+    let input = r#"sealed interface Shape {
+    val area: Double
+    fun describe(): String = "shape"
+}
+
+fun interface Listener {
+    fun onEvent(event: Event)
+}
+
+/** The colors. */
+enum class Color(val rgb: Int) {
+    /** Pure red. */
+    RED(0xFF0000),
+    GREEN(0x00FF00) {
+        override fun toString() = "green"
+    },
+    BLUE(0x0000FF);
+
+    fun hex(): String = rgb.toString(16)
+}
+
+@Serializable
+class Config @Inject constructor(
+    @SerialName("url") val url: String,
+) {
+    @Deprecated("Use url")
+    var host: String? = null
+        private set
+
+    protected open fun validate() {}
+
+    constructor() : this("")
+}
+
+internal class Hidden
+"#;
+    let expected = r#"sealed interface Shape {
+    val area: Double
+    fun describe(): String
+}
+
+fun interface Listener {
+    fun onEvent(event: Event)
+}
+
+/** The colors. */
+enum class Color(val rgb: Int) {
+    /** Pure red. */
+    RED(0xFF0000),
+    GREEN(0x00FF00),
+    BLUE(0x0000FF);
+    fun hex(): String
+}
+
+@Serializable
+class Config @Inject constructor( @SerialName("url") val url: String, ) {
+    @Deprecated("Use url")
+    var host: String?
+    protected open fun validate()
+    constructor() : this("")
+}"#;
+    assert_eq!(codemap_with(Kotlin, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_type_aliases_and_untyped_properties() {
+    let input = r#"typealias Name = String
+typealias Handler<T> = (T) -> Unit
+
+const val VERSION = "1"
+val x = 1
+var count: Int = 0
+val table = mapOf(
+    "a" to 1,
+)
+
+class Counter {
+    val start = 0
+}
+"#;
+    let expected = r#"typealias Name = String
+
+typealias Handler<T> = (T) -> Unit
+
+const val VERSION = "1"
+
+val x = 1
+
+var count: Int
+
+val table
+
+class Counter {
+    val start = 0
+}"#;
+    assert_eq!(codemap_with(Kotlin, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_syntax_errors() {
+    let input = "class A {\n    fun a() {}\n}\n\nfun b(x: ) {}\n\nfun c() {}\n";
+    let mut mapper = Mapper::with_backend(Arc::new(Kotlin), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    // The broken declaration is left out, the others are still mapped
+    assert_eq!(file_map.map(), "class A {\n    fun a()\n}\n\nfun c()");
+    let lines: Vec<_> = file_map
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect();
+    assert_eq!(lines, vec![5]);
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use codemap::{
+    CodemapOptions, Item, ItemKind, ItemMemo, LanguageBackend, LanguageRegistry, Lines, Mapper,
+    Options, Rust, map_directory,
+};
+use tree_sitter::{Language, Tree};
+
+// Lists the names of the functions of a Rust file, whatever their visibility
+struct FunctionNames;
+
+impl LanguageBackend for FunctionNames {
+    fn name(&self) -> &'static str {
+        "function-names"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["fns"]
+    }
+
+    fn language(&self) -> Option<Language> {
+        Rust.language()
+    }
+
+    fn map_items(
+        &self,
+        tree: &Tree,
+        source: &str,
+        _options: &CodemapOptions,
+        _memo: Option<&mut ItemMemo>,
+    ) -> Vec<Item> {
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        root.children(&mut cursor)
+            .filter(|node| node.kind() == "function_item")
+            .filter_map(|node| {
+                let name = &source[node.child_by_field_name("name")?.byte_range()];
+                Some(Item {
+                    kind: ItemKind::Function,
+                    name: Some(name.to_string()),
+                    text: name.to_string(),
+                    lines: Some(Lines {
+                        start: node.start_position().row + 1,
+                        end: node.end_position().row + 1,
+                    }),
+                    file: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_registry_lookup() {
+    let mut registry = LanguageRegistry::default();
+    assert_eq!(
+        registry
+            .for_path(Path::new("src/lib.rs"))
+            .map(|backend| backend.name()),
+        Some("rust")
+    );
+    assert!(registry.for_path(Path::new("notes.fns")).is_none());
+    assert!(registry.for_path(Path::new("Makefile")).is_none());
+    // Manifests are matched by name, other TOML files aren't mapped
+    assert_eq!(
+        registry
+            .for_path(Path::new("crates/a/Cargo.toml"))
+            .map(|backend| backend.name()),
+        Some("cargo")
+    );
+    assert!(registry.for_path(Path::new("pixi.toml")).is_none());
+
+    registry.register(Arc::new(FunctionNames));
+    assert_eq!(
+        registry.for_extension("fns").map(|backend| backend.name()),
+        Some("function-names")
+    );
+    assert!(registry.extensions().any(|extension| extension == "fns"));
+}
+
+#[test]
+fn test_mapper_with_backend() {
+    let options = CodemapOptions::new().include_line_numbers(true);
+    let mut mapper = Mapper::with_backend(Arc::new(FunctionNames), options).unwrap();
+    let file_map = mapper.map("fn a() {}\n\npub fn b() {}\n").unwrap();
+    assert_eq!(file_map.map(), "// L1-L1\na\n\n// L3-L3\nb");
+}
+
+#[test]
+fn test_map_directory_dispatches_by_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = "pub fn public() {}\nfn private() {}\n";
+    fs::write(dir.path().join("lib.rs"), source).unwrap();
+    fs::write(dir.path().join("other.fns"), source).unwrap();
+    fs::write(dir.path().join("notes.txt"), source).unwrap();
+
+    let mut languages = LanguageRegistry::default();
+    languages.register(Arc::new(FunctionNames));
+    let options = Options {
+        // The include glob lets the text file through, but no language maps it
+        include: vec!["*".to_string()],
+        languages,
+        ..Options::default()
+    };
+
+    let project = map_directory(dir.path(), &options).unwrap();
+    let maps: Vec<_> = project
+        .files
+        .iter()
+        .map(|(path, file_map)| (path.as_path(), file_map.map()))
+        .collect();
+    assert_eq!(
+        maps,
+        vec![
+            (Path::new("lib.rs"), "pub fn public();".to_string()),
+            (Path::new("other.fns"), "public\n\nprivate".to_string()),
+        ]
+    );
+    assert!(project.errors.is_empty());
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde_json::{Value, json};
+
+// Send requests to `codemap mcp` and collect its responses until it exits
+fn exchange(requests: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codemap"))
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    for request in requests {
+        writeln!(stdin, "{}", request).unwrap();
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn call(id: u64, tool: &str, arguments: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": tool, "arguments": arguments },
+    })
+}
+
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "pub struct Config;\npub fn load() -> Config { Config }\nfn private() {}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_initialize_and_list_tools() {
+    let responses = exchange(&[
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-03-26" },
+        }),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    ]);
+
+    // The notification isn't answered
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-03-26");
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "codemap");
+
+    let tools: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, ["codemap", "search", "symbol"]);
+}
+
+#[test]
+fn test_call_tools() {
+    let dir = project();
+    let path = dir.path().to_str().unwrap();
+    let responses = exchange(&[
+        call(1, "codemap", json!({ "path": path, "format": "markdown" })),
+        call(2, "search", json!({ "path": path, "query": "CONFIG" })),
+        call(
+            3,
+            "symbol",
+            json!({ "path": path, "name": "load", "format": "json" }),
+        ),
+    ]);
+    assert_eq!(responses.len(), 3);
+
+    let text = |response: &Value| {
+        response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    for response in &responses {
+        assert_eq!(response["result"]["isError"], false);
+    }
+    assert_eq!(
+        text(&responses[0]),
+        "## src/lib.rs\n\n```rust\npub struct Config;\n\npub fn load() -> Config;\n```\n\n"
+    );
+    assert!(text(&responses[1]).contains("pub struct Config;"));
+
+    let symbol: Value = serde_json::from_str(&text(&responses[2])).unwrap();
+    let items = symbol["files"][0]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["name"], "load");
+}
+
+#[test]
+fn test_missing_arguments() {
+    let dir = project();
+    let path = dir.path().to_str().unwrap();
+    let responses = exchange(&[
+        call(1, "codemap", json!({})),
+        call(2, "search", json!({ "path": path })),
+        call(3, "symbol", json!({ "path": path })),
+        call(4, "other", json!({ "path": path })),
+        json!({ "jsonrpc": "2.0", "id": 5, "method": "unknown" }),
+    ]);
+
+    let errors: Vec<&str> = responses[..4]
+        .iter()
+        .map(|response| {
+            assert_eq!(response["result"]["isError"], true);
+            response["result"]["content"][0]["text"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "missing argument: path",
+            "missing argument: query",
+            "missing argument: name",
+            "unknown tool: other",
+        ]
+    );
+    assert_eq!(responses[4]["error"]["code"], -32601);
+}
+
+#[test]
+fn test_arguments_override_configuration() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("codemap.toml"), "docs = false\n").unwrap();
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "use std::fmt;\n\n/// Settings.\n#[derive(Debug)]\npub struct Config;\n\nimpl Config {\n    pub fn load() -> Config { Config }\n}\n",
+    )
+    .unwrap();
+    let path = dir.path().to_str().unwrap();
+    let responses = exchange(&[
+        call(1, "codemap", json!({ "path": path, "format": "markdown" })),
+        call(
+            2,
+            "codemap",
+            json!({
+                "path": path,
+                "format": "markdown",
+                "visibility": "private",
+                "uses": false,
+                "impls": false,
+                "docs": true,
+                "attributes": false,
+                "max_signature_length": 14,
+            }),
+        ),
+    ]);
+
+    let text = |response: &Value| {
+        response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        text(&responses[0]),
+        "## src/lib.rs\n\n```rust\n#[derive(Debug)]\npub struct Config;\n\nimpl Config {\n    pub fn load() -> Config;\n}\n```\n\n"
+    );
+    assert_eq!(
+        text(&responses[1]),
+        "## src/lib.rs\n\n```rust\n/// Settings.\npub struct Co…\n```\n\n"
+    );
+}
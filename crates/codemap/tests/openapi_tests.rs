@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, FileMap, Mapper, OpenApi, Options, map_directory};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_operations() {
+    let input = r##"openapi: 3.0.3
+info:
+  title: Pets
+  version: "1.0"
+paths:
+  /pets/{id}:
+    parameters:
+      - name: id
+        in: path
+        required: true
+        schema: {type: integer}
+    get:
+      summary: Show a pet
+      operationId: showPet
+      parameters:
+        - name: verbose
+          in: query
+          schema:
+            type: boolean
+      responses:
+        "200":
+          description: The pet
+          content:
+            application/json:
+              schema:
+                $ref: "#/components/schemas/Pet"
+        "404":
+          description: Not found
+    put:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/NewPet'
+      responses:
+        '204': {description: Updated}
+"##;
+    let expected = r##"# Pets 1.0 (OpenAPI 3.0.3)
+
+# Show a pet
+GET /pets/{id}(id: integer, verbose?: boolean) -> 200: Pet, 404
+
+PUT /pets/{id}(id: integer, body: NewPet) -> 204"##;
+    assert_eq!(
+        codemap_with(OpenApi, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_schemas() {
+    let input = r##"openapi: 3.1.0
+components:
+  schemas:
+    Pet:
+      description: |
+        A pet.
+        With a long description.
+      type: object
+      required: [id, name]
+      properties:
+        id: {type: integer, format: int64}
+        name:
+          type: string
+          description: What it answers to
+        tags:
+          type: array
+          items: {$ref: "#/components/schemas/Tag"}
+        owner:
+          oneOf:
+            - $ref: "#/components/schemas/Person"
+            - $ref: "#/components/schemas/Shelter"
+    Status:
+      type: string
+      enum: [available, sold]
+    Id:
+      type: string
+"##;
+    let expected = r##"# OpenAPI 3.1.0
+
+# A pet.
+schema Pet {
+    id: integer
+    # What it answers to
+    name: string
+    tags?: Tag[]
+    owner?: Person | Shelter
+}
+
+schema Status = "available" | "sold"
+
+schema Id = string"##;
+    assert_eq!(
+        codemap_with(OpenApi, input, CodemapOptions::new()),
+        expected
+    );
+
+    let expected = r##"# OpenAPI 3.1.0
+
+schema Pet {
+    id: integer
+    name: string
+    tags?: Tag[]
+    owner?: Person | Shelter
+}
+
+schema Status = "available" | "sold"
+
+schema Id = string"##;
+    assert_eq!(
+        codemap_with(OpenApi, input, CodemapOptions::new().include_docs(false)),
+        expected
+    );
+}
+
+#[test]
+fn test_swagger_2() {
+    let input = r##"swagger: "2.0"
+info: {title: Store, version: v2}
+paths:
+  /orders:
+    post:
+      parameters:
+        - in: body
+          name: order
+          schema: {$ref: "#/definitions/Order"}
+      responses:
+        200:
+          schema:
+            type: array
+            items: {$ref: "#/definitions/Order"}
+definitions:
+  Order:
+    properties:
+      id: {type: string}
+"##;
+    let expected = r##"# Store v2 (OpenAPI 2.0)
+
+POST /orders(body: Order) -> 200: Order[]
+
+schema Order {
+    id?: string
+}"##;
+    assert_eq!(
+        codemap_with(OpenApi, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_other_yaml_is_empty() {
+    let input = "name: CI\non:\n  push:\n    branches: [main]\n";
+    assert_eq!(codemap_with(OpenApi, input, CodemapOptions::new()), "");
+}
+
+#[test]
+fn test_other_yaml_is_skipped() {
+    // A Helm template isn't even valid YAML
+    let template = "apiVersion: v1\nkind: Service\nmetadata:\n  name: {{ .Release.Name }}\n{{- if .Values.ports }}\nspec: {}\n{{- end }}\n";
+    let mut mapper = Mapper::with_backend(Arc::new(OpenApi), CodemapOptions::new()).unwrap();
+    assert_eq!(mapper.map(template).unwrap(), FileMap::default());
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("service.yaml"), template).unwrap();
+    std::fs::write(dir.path().join("api.yaml"), "openapi: 3.0.0\npaths: {}\n").unwrap();
+    let project = map_directory(dir.path(), &Options::default()).unwrap();
+    let paths: Vec<_> = project.files.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(paths, [PathBuf::from("api.yaml")]);
+    assert!(project.errors.is_empty());
+}
+
+#[test]
+fn test_identical_operations_of_other_paths() {
+    let input = r#"openapi: 3.0.0
+paths:
+  /cats:
+    get:
+      responses:
+        '200': {description: ok}
+  /dogs:
+    get:
+      responses:
+        '200': {description: ok}
+"#;
+    let expected = "# OpenAPI 3.0.0\n\nGET /cats() -> 200\n\nGET /dogs() -> 200";
+    let mut mapper = Mapper::with_backend(Arc::new(OpenApi), CodemapOptions::new()).unwrap();
+    let mut file = mapper.parse_file(input).unwrap();
+    assert_eq!(file.file_map().map(), expected);
+
+    // Mapping again after an edit reuses renderings of the right operation
+    let edited = input.replace("/dogs", "/birds");
+    mapper.update_file(&mut file, &edited).unwrap();
+    assert_eq!(file.file_map().map(), expected.replace("/dogs", "/birds"));
+}
+
+#[test]
+fn test_operation_lines() {
+    let input = "openapi: 3.0.0\npaths:\n  /health:\n    get:\n      responses:\n        '200': {description: ok}\n";
+    assert_eq!(
+        codemap_with(
+            OpenApi,
+            input,
+            CodemapOptions::new().include_line_numbers(true)
+        ),
+        "# OpenAPI 3.0.0\n\n# L4-L6\nGET /health() -> 200"
+    );
+}
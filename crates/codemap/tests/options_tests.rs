@@ -0,0 +1,230 @@
+use codemap::{
+    CodemapOptions, Format, Lines, Visibility, codemap, codemap_with_options, map_source,
+    render_files,
+};
+use std::path::PathBuf;
+
+const INPUT: &str = r#"use std::fmt;
+
+pub use crate::inner::Thing;
+
+pub struct Point {
+    /// Horizontal position
+    pub x: i32,
+    pub(crate) y: i32,
+    z: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y, z: 0 }
+    }
+
+    pub(crate) fn reset(&mut self) {}
+
+    fn helper() {}
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.x)
+    }
+}
+
+pub enum Shape {
+    /// A round shape
+    #[default]
+    Circle,
+    Square,
+}
+
+pub(crate) fn crate_function() {}
+
+fn private_function() {}
+"#;
+
+#[test]
+fn test_default_options_match_codemap() {
+    assert_eq!(
+        codemap_with_options(INPUT, &CodemapOptions::default()).unwrap(),
+        codemap(INPUT).unwrap()
+    );
+}
+
+#[test]
+fn test_visibility_crate() {
+    let options = CodemapOptions::new()
+        .visibility(Visibility::Crate)
+        .include_uses(false);
+    let expected = r#"pub struct Point {
+    /// Horizontal position
+    pub x: i32,
+    pub(crate) y: i32
+    // private fields
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self;
+    pub(crate) fn reset(&mut self);
+}
+
+pub enum Shape {
+    /// A round shape
+    #[default]
+    Circle,
+    Square,
+}
+
+pub(crate) fn crate_function();"#;
+    assert_eq!(codemap_with_options(INPUT, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_visibility_private() {
+    let options = CodemapOptions::new()
+        .visibility(Visibility::Private)
+        .include_impls(false)
+        .include_docs(false)
+        .include_attributes(false);
+    let expected = r#"use std::fmt;
+
+pub use crate::inner::Thing;
+
+pub struct Point {
+    pub x: i32,
+    pub(crate) y: i32,
+    z: i32
+}
+
+pub enum Shape {
+    Circle,
+    Square,
+}
+
+pub(crate) fn crate_function();
+
+fn private_function();"#;
+    assert_eq!(codemap_with_options(INPUT, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_item_and_field_attributes() {
+    let input = r#"/// A request body.
+///
+/// Sent as JSON.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    #[serde(default)]
+    pub retries: u8,
+    /// Where to send it
+    #[serde(rename = "url")]
+    pub target: String,
+}"#;
+    let expected = r#"/// A request body.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    #[serde(default)]
+    pub retries: u8,
+    /// Where to send it
+    #[serde(rename = "url")]
+    pub target: String
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+
+    let expected = r#"/// A request body.
+pub struct Request {
+    pub retries: u8,
+    /// Where to send it
+    pub target: String
+}"#;
+    let options = CodemapOptions::new().include_attributes(false);
+    assert_eq!(codemap_with_options(input, &options).unwrap(), expected);
+
+    let expected = r#"pub struct Request {
+    pub retries: u8,
+    pub target: String
+}"#;
+    let options = options.include_docs(false);
+    assert_eq!(codemap_with_options(input, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_trait_impls() {
+    let input = r#"pub struct Wrapper(pub u8);
+
+impl fmt::Display for Wrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        todo!()
+    }
+}"#;
+    let expected = r#"pub struct Wrapper(pub u8);
+
+impl fmt::Display for Wrapper {}"#;
+    let options = CodemapOptions::new().include_trait_impls(true);
+    assert_eq!(codemap_with_options(input, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_line_numbers() {
+    let input = "pub const A: u8 = 1;\n\npub fn f(\n    a: u8,\n) {}\n";
+    let expected = "// L1-L1\npub const A: u8 = 1;\n\n// L3-L5\npub fn f(a: u8);";
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert_eq!(codemap_with_options(input, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_item_lines() {
+    let input = "pub const A: u8 = 1;\n\npub fn f(\n    a: u8,\n) {}\n";
+    let file_map = map_source(input).unwrap();
+    let lines: Vec<_> = file_map.items.iter().map(|item| item.lines).collect();
+    assert_eq!(
+        lines,
+        vec![
+            Some(Lines { start: 1, end: 1 }),
+            Some(Lines { start: 3, end: 5 })
+        ]
+    );
+
+    let json = render_files(&[(PathBuf::from("lib.rs"), file_map)], Format::Json);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        json["files"][0]["items"][1]["lines"],
+        serde_json::json!({ "start": 3, "end": 5 })
+    );
+}
+
+#[test]
+fn test_max_signature_length() {
+    let input = "pub fn long_function_name(first: u8, second: u8) -> u8 { 0 }";
+    let options = CodemapOptions::new().max_signature_length(Some(20));
+    assert_eq!(
+        codemap_with_options(input, &options).unwrap(),
+        "pub fn long_functio…"
+    );
+}
+
+#[test]
+fn test_visibility_inside_modules() {
+    let input = r#"pub mod inner {
+    pub trait Public {}
+    pub(crate) trait Restricted {}
+    trait Private {}
+}"#;
+    let with = |visibility| {
+        codemap_with_options(input, &CodemapOptions::new().visibility(visibility)).unwrap()
+    };
+    assert_eq!(
+        with(Visibility::Public),
+        "pub mod inner {\n    pub trait Public {}\n}"
+    );
+    assert_eq!(
+        with(Visibility::Crate),
+        "pub mod inner {\n    pub trait Public {}\n\n    pub(crate) trait Restricted {}\n}"
+    );
+    assert_eq!(
+        with(Visibility::Private),
+        "pub mod inner {\n    pub trait Public {}\n\n    pub(crate) trait Restricted {}\n\n    trait Private {}\n}"
+    );
+}
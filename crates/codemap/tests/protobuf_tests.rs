@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, Mapper, Protobuf, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_messages_and_enums() {
+    let input = r#"syntax = "proto3";
+
+package pets.v1;
+
+import "google/protobuf/timestamp.proto";
+
+option go_package = "example.com/pets";
+
+// A pet in the store.
+// Pets are never deleted.
+message Pet {
+  reserved 4;
+  // Unique id
+  string id = 1;
+  string name = 2; // trailing comment
+  map<string, string> labels = 3;
+  google.protobuf.Timestamp born = 5 [deprecated = true];
+
+  oneof owner {
+    string person = 6;
+    string shop = 7;
+  }
+
+  enum Kind {
+    option allow_alias = true;
+    KIND_UNSPECIFIED = 0;
+    DOG = 1;
+  }
+}
+
+message Empty {}
+"#;
+    let expected = r#"package pets.v1;
+
+// A pet in the store.
+message Pet {
+    // Unique id
+    string id = 1;
+    string name = 2;
+    map<string, string> labels = 3;
+    google.protobuf.Timestamp born = 5 [deprecated = true];
+    oneof owner {
+        string person = 6;
+        string shop = 7;
+    }
+    enum Kind {
+        KIND_UNSPECIFIED = 0;
+        DOG = 1;
+    }
+}
+
+message Empty {}"#;
+    assert_eq!(
+        codemap_with(Protobuf, input, CodemapOptions::new()),
+        expected
+    );
+
+    // Imports come with private items, like other languages' imports
+    let options = CodemapOptions::new()
+        .visibility(Visibility::Private)
+        .include_docs(false);
+    let map = codemap_with(Protobuf, input, options);
+    assert!(map.starts_with(
+        "package pets.v1;\n\nimport \"google/protobuf/timestamp.proto\";\n\nmessage Pet {\n    string id = 1;"
+    ));
+}
+
+#[test]
+fn test_services() {
+    let input = r#"service PetStore {
+  /* Lists the pets
+   * of the store */
+  rpc ListPets(ListPetsRequest) returns (stream Pet);
+  rpc GetPet(GetPetRequest) returns (Pet) {
+    option (google.api.http) = { get: "/v1/pets/{id}" };
+  }
+}
+"#;
+    let expected = r#"// L1-L8
+service PetStore {
+    // Lists the pets
+    rpc ListPets(ListPetsRequest) returns (stream Pet);
+    rpc GetPet(GetPetRequest) returns (Pet);
+}"#;
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert_eq!(codemap_with(Protobuf, input, options), expected);
+}
+
+#[test]
+fn test_syntax_errors() {
+    let input =
+        "message Pet {\n  string id = 1;\n}\n\n= 3;\n\nmessage Owner {\n  string name = 1\n";
+    let mut mapper = Mapper::with_backend(Arc::new(Protobuf), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    assert_eq!(file_map.map(), "message Pet {\n    string id = 1;\n}");
+    let diagnostics: Vec<_> = file_map
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+        .collect();
+    assert_eq!(diagnostics, vec![(5, "syntax error"), (7, "syntax error")]);
+}
+
+#[test]
+fn test_broken_member() {
+    let input = "message Pet {\n  string id = 1;\n  string = 2;\n  string name = 3;\n}\n";
+    let mut mapper = Mapper::with_backend(Arc::new(Protobuf), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    assert_eq!(
+        file_map.map(),
+        "message Pet {\n    string id = 1;\n    string name = 3;\n}"
+    );
+    assert_eq!(file_map.diagnostics.len(), 1);
+    assert_eq!(file_map.diagnostics[0].line, 3);
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, Mapper, Python, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_function_with_type_hints() {
+    let input = r#"async def fetch(
+    url: str,
+    *,
+    timeout: float = 1.0,  # seconds
+) -> bytes:
+    return b""
+"#;
+    let expected = r#"async def fetch(url: str, *, timeout: float = 1.0) -> bytes: ..."#;
+    assert_eq!(codemap_with(Python, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_underscore_names_are_private() {
+    let input = r#"def public(): pass
+def _private(): pass
+class _Hidden: pass
+"#;
+    assert_eq!(
+        codemap_with(Python, input, CodemapOptions::new()),
+        "def public(): ..."
+    );
+    assert_eq!(
+        codemap_with(
+            Python,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        "def public(): ...\n\ndef _private(): ...\n\nclass _Hidden: ..."
+    );
+}
+
+#[test]
+fn test_all_defines_public_names() {
+    let input = r#"__all__ = ["exported", "_special"]
+__all__ += ["Extra"]
+
+def exported(): pass
+def not_exported(): pass
+def _special(): pass
+class Extra: pass
+"#;
+    assert_eq!(
+        codemap_with(Python, input, CodemapOptions::new()),
+        "def exported(): ...\n\ndef _special(): ...\n\nclass Extra: ..."
+    );
+    assert_eq!(
+        codemap_with(
+            Python,
+            input,
+            CodemapOptions::new().visibility(Visibility::Crate)
+        ),
+        "def exported(): ...\n\ndef not_exported(): ...\n\ndef _special(): ...\n\nclass Extra: ..."
+    );
+}
+
+#[test]
+fn test_class_with_members() {
+    let input = r#"@dataclass(frozen=True)
+class Point(Base, metaclass=Meta):
+    """A point on the plane.
+
+    Longer description.
+    """
+
+    x: int = 0
+    y: int = 0
+    _cache = {}
+
+    def __init__(self, x: int, y: int) -> None:
+        self.x = x
+
+    @property
+    def norm(self) -> float:
+        """Distance from the origin."""
+        return 0.0
+
+    @staticmethod
+    def origin() -> "Point":
+        return Point(0, 0)
+
+    def _helper(self):
+        pass
+
+    class Meta:
+        ordering = ["x"]
+"#;
+    let expected = r#"@dataclass(frozen=True)
+class Point(Base, metaclass=Meta):
+    """A point on the plane."""
+    x: int = 0
+    y: int = 0
+    def __init__(self, x: int, y: int) -> None: ...
+    @property
+    def norm(self) -> float:
+        """Distance from the origin."""
+    @staticmethod
+    def origin() -> "Point": ...
+    class Meta:
+        ordering = ["x"]"#;
+    assert_eq!(codemap_with(Python, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_docs_and_decorators_can_be_left_out() {
+    let input = r#"@cache
+def compute(n: int) -> int:
+    """Compute something."""
+    return n
+"#;
+    assert_eq!(
+        codemap_with(Python, input, CodemapOptions::new()),
+        "@cache\ndef compute(n: int) -> int:\n    \"\"\"Compute something.\"\"\""
+    );
+    let options = CodemapOptions::new()
+        .include_docs(false)
+        .include_attributes(false);
+    assert_eq!(
+        codemap_with(Python, input, options),
+        "def compute(n: int) -> int: ..."
+    );
+}
+
+#[test]
+fn test_constants() {
+    let input = r#"MAX_SIZE = 1024
+TIMEOUT: float = 2.5
+DEFAULTS = {
+    "a": 1,
+}
+logger = logging.getLogger(__name__)
+_PRIVATE = 1
+type Pair = tuple[int, int]
+"#;
+    let expected = r#"MAX_SIZE = 1024
+
+TIMEOUT: float = 2.5
+
+DEFAULTS = ...
+
+type Pair = tuple[int, int]"#;
+    assert_eq!(codemap_with(Python, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_only_reexporting_imports() {
+    let input = r#"import os
+from typing import Any
+from .models import Model as Model
+from .views import view
+
+__all__ = ["view"]
+"#;
+    let expected = r#"from .models import Model as Model
+
+from .views import view"#;
+    assert_eq!(codemap_with(Python, input, CodemapOptions::new()), expected);
+    assert_eq!(
+        codemap_with(Python, input, CodemapOptions::new().include_uses(false)),
+        ""
+    );
+}
+
+#[test]
+fn test_line_numbers() {
+    let input = "def a():\n    pass\n\n\nclass B:\n    x = 1\n";
+    let expected = "# L1-L2\ndef a(): ...\n\n# L5-L6\nclass B:\n    x = 1";
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert_eq!(codemap_with(Python, input, options), expected);
+}
+
+#[test]
+fn test_syntax_error_keeps_valid_items() {
+    let input = r#"def ok(a: int) -> int:
+    return a
+
+def broken(:
+    pass
+
+class Fine:
+    pass
+"#;
+    let mut mapper = Mapper::with_backend(Arc::new(Python), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    assert!(!file_map.diagnostics.is_empty());
+    assert!(file_map.map().starts_with("def ok(a: int) -> int: ..."));
+    assert!(file_map.map().ends_with("class Fine: ..."));
+}
+
+#[test]
+fn test_map_directory_with_python_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.py"), "def main() -> None:\n    pass\n").unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "pub fn main() {}\n").unwrap();
+
+    let project = codemap::map_directory(dir.path(), &codemap::Options::default()).unwrap();
+    assert_eq!(
+        project.render(codemap::Format::Markdown),
+        "## app.py\n\n```python\ndef main() -> None: ...\n```\n\n## lib.rs\n\n```rust\npub fn main();\n```\n\n"
+    );
+}
@@ -0,0 +1,197 @@
+use std::path::PathBuf;
+
+use codemap::{
+    CodemapOptions, FileMap, Lines, ReexportOptions, expand_reexports, map_source,
+    map_source_with_options,
+};
+
+fn expand(files: &[(&str, &str)], options: &ReexportOptions) -> Vec<(PathBuf, FileMap)> {
+    let mut files: Vec<(PathBuf, FileMap)> = files
+        .iter()
+        .map(|(path, source)| (PathBuf::from(path), map_source(source).unwrap()))
+        .collect();
+    expand_reexports(&mut files, options).unwrap();
+    files
+}
+
+fn map_of(files: &[(PathBuf, FileMap)], path: &str) -> String {
+    files
+        .iter()
+        .find(|(file_path, _)| file_path == &PathBuf::from(path))
+        .map(|(_, file_map)| file_map.map())
+        .unwrap()
+}
+
+#[test]
+fn test_glob_reexport() {
+    let files = expand(
+        &[
+            ("src/lib.rs", "mod inner;\npub use inner::*;"),
+            (
+                "src/inner.rs",
+                "pub struct Foo;\npub fn make() -> Foo { todo!() }\nfn private() {}",
+            ),
+        ],
+        &ReexportOptions::default(),
+    );
+    let expected = r#"pub struct Foo;
+
+pub fn make() -> Foo;"#;
+    assert_eq!(map_of(&files, "src/lib.rs"), expected);
+}
+
+#[test]
+fn test_renamed_reexport() {
+    let files = expand(
+        &[
+            (
+                "src/lib.rs",
+                "mod inner;\npub use crate::inner::Foo as Bar;",
+            ),
+            (
+                "src/inner.rs",
+                r#"pub struct Foo(pub u8);
+
+impl Foo {
+    pub fn new(Foo: u8) -> Foo { todo!() }
+}
+
+pub struct FooBuilder;"#,
+            ),
+        ],
+        &ReexportOptions::default(),
+    );
+    // Only the declared name and the impl block's type are renamed
+    let expected = r#"pub struct Bar(pub u8);
+
+impl Bar {
+    pub fn new(Foo: u8) -> Foo;
+}"#;
+    assert_eq!(map_of(&files, "src/lib.rs"), expected);
+}
+
+#[test]
+fn test_nested_reexports_and_mod_rs() {
+    let files = expand(
+        &[
+            ("src/lib.rs", "mod a;\npub use a::{Leaf, b::Branch};"),
+            ("src/a/mod.rs", "pub mod b;\npub use self::b::leaf::*;"),
+            ("src/a/b.rs", "pub struct Branch;\npub mod leaf;"),
+            ("src/a/b/leaf.rs", "pub enum Leaf { A }"),
+        ],
+        &ReexportOptions::default(),
+    );
+    let expected = r#"pub enum Leaf {
+    A,
+}
+
+pub struct Branch;"#;
+    assert_eq!(map_of(&files, "src/lib.rs"), expected);
+}
+
+#[test]
+fn test_external_reexport_kept() {
+    let files = expand(
+        &[
+            (
+                "src/lib.rs",
+                "mod inner;\npub use anyhow::Result;\npub use inner::{Foo, Missing};",
+            ),
+            ("src/inner.rs", "pub struct Foo;"),
+        ],
+        &ReexportOptions::default(),
+    );
+    let expected = r#"pub use anyhow::Result;
+
+pub struct Foo;
+
+pub use inner::Missing;"#;
+    assert_eq!(map_of(&files, "src/lib.rs"), expected);
+}
+
+#[test]
+fn test_reexport_with_origin() {
+    let files = expand(
+        &[
+            (
+                "crates/facade/src/lib.rs",
+                "mod inner;\npub use inner::make;",
+            ),
+            ("crates/facade/src/inner.rs", "pub fn make() {}"),
+        ],
+        &ReexportOptions { show_origin: true },
+    );
+    let expected = r#"// defined at crate::inner::make in crates/facade/src/inner.rs
+pub fn make();"#;
+    assert_eq!(map_of(&files, "crates/facade/src/lib.rs"), expected);
+}
+
+#[test]
+fn test_reexports_stay_within_crate() {
+    let files = expand(
+        &[
+            ("crates/a/src/lib.rs", "mod inner;\npub use inner::Foo;"),
+            ("crates/b/src/inner.rs", "pub struct Foo;"),
+        ],
+        &ReexportOptions::default(),
+    );
+    assert_eq!(map_of(&files, "crates/a/src/lib.rs"), "pub use inner::Foo;");
+}
+
+#[test]
+fn test_reexport_cycle() {
+    let files = expand(
+        &[
+            ("src/lib.rs", "mod a;\nmod b;\npub use a::*;"),
+            ("src/a.rs", "pub use crate::b::*;\npub struct A;"),
+            ("src/b.rs", "pub use crate::a::*;\npub struct B;"),
+        ],
+        &ReexportOptions::default(),
+    );
+    let expected = r#"pub struct B;
+
+pub struct A;"#;
+    assert_eq!(map_of(&files, "src/lib.rs"), expected);
+}
+
+#[test]
+fn test_reexports_of_lib_and_main() {
+    let files = expand(
+        &[
+            ("src/lib.rs", "mod a;\npub use a::A;"),
+            ("src/main.rs", "mod b;\npub use crate::b::B;"),
+            ("src/a.rs", "pub struct A;"),
+            ("src/b.rs", "pub struct B;"),
+        ],
+        &ReexportOptions::default(),
+    );
+    assert_eq!(map_of(&files, "src/lib.rs"), "pub struct A;");
+    assert_eq!(map_of(&files, "src/main.rs"), "pub struct B;");
+}
+
+#[test]
+fn test_reexport_lines_point_at_definition() {
+    let options = CodemapOptions::new().include_line_numbers(true);
+    let mut files: Vec<(PathBuf, FileMap)> = [
+        ("src/lib.rs", "mod inner;\n\npub use inner::make;"),
+        (
+            "src/inner.rs",
+            "// Helpers\n\npub fn make(\n    size: u8,\n) {}",
+        ),
+    ]
+    .iter()
+    .map(|(path, source)| {
+        let file_map = map_source_with_options(source, &options).unwrap();
+        (PathBuf::from(path), file_map)
+    })
+    .collect();
+    expand_reexports(&mut files, &ReexportOptions { show_origin: true }).unwrap();
+
+    let item = &files[0].1.items[0];
+    assert_eq!(item.lines, Some(Lines { start: 3, end: 5 }));
+    assert_eq!(item.file, Some(PathBuf::from("src/inner.rs")));
+    let expected = r#"// defined at crate::inner::make in src/inner.rs
+// L3-L5
+pub fn make(size: u8);"#;
+    assert_eq!(item.text, expected);
+}
@@ -1,4 +1,5 @@
-use codemap::codemap;
+use codemap::{Format, Mapper, codemap, map_source, render_files};
+use std::path::PathBuf;
 
 #[test]
 fn test_public_struct_with_public_field() {
@@ -6,14 +7,16 @@ fn test_public_struct_with_public_field() {
     let expected = r#"pub struct Simple {
     pub public_field: i32
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_struct_with_private_field() {
     let input = r#"pub struct Simple { private_field: i32 }"#;
-    let expected = r#"pub struct Simple {}"#;
-    assert_eq!(codemap(input), expected);
+    let expected = r#"pub struct Simple {
+    // private fields
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -26,22 +29,23 @@ fn test_public_struct_with_mixed_fields() {
     let expected = r#"pub struct PublicPrivatePublic {
     pub public_field: i32,
     pub pub_field: String
+    // private fields
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_struct_no_fields() {
     let input = r#"struct PrivateNoFields;"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_struct_with_fields() {
     let input = r#"struct PrivateWithFields { private_field: i32 }"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -50,7 +54,7 @@ fn test_generic_struct() {
     let expected = r#"pub struct Invite<ID> {
     pub id: ID
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -62,7 +66,7 @@ fn test_private_enum() {
     Variant4 { field: i32 },
 }"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -79,14 +83,14 @@ fn test_public_enum() {
     Variant3(String),
     Variant4 { field: i32 },
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_constant() {
     let input = r#"pub const CONSTANT: i32 = 42;"#;
     let expected = r#"pub const CONSTANT: i32 = 42;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -107,56 +111,56 @@ impl ImplStruct {
 impl ImplStruct {
     pub fn public_method(param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_function() {
     let input = r#"pub fn public_function(param: i32) -> i32 { todo!() }"#;
     let expected = r#"pub fn public_function(param: i32) -> i32;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_async_function() {
     let input = r#"pub async fn public_async_function(param: i32) -> i32 { todo!() }"#;
     let expected = r#"pub async fn public_async_function(param: i32) -> i32;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_function() {
     let input = r#"fn private_function(param: i32) -> i32 { todo!() }"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_module() {
     let input = r#"pub mod public_mod;"#;
     let expected = r#"pub mod public_mod;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_module() {
     let input = r#"mod private_mod;"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_type_alias() {
     let input = r#"pub type PublicType = i32;"#;
     let expected = r#"pub type PublicType = i32;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_type_alias() {
     let input = r#"type PrivateType = i32;"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -167,7 +171,7 @@ fn test_public_trait() {
     let expected = r#"pub trait PublicTrait {
     fn public_method(&self, param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -176,21 +180,21 @@ fn test_private_trait() {
     fn private_method(&self, param: i32) -> i32;
 }"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_public_use() {
     let input = r#"pub use anyhow::Result;"#;
     let expected = r#"pub use anyhow::Result;"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_private_use() {
     let input = r#"use anyhow::Ok;"#;
     let expected = r#""#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -207,7 +211,7 @@ impl ImplStruct {
 impl ImplStruct {
     pub async fn public_async_method(param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -224,7 +228,7 @@ impl ImplStruct {
 impl ImplStruct {
     pub fn public_method_with_self_param(self, param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -241,7 +245,7 @@ impl ImplStruct {
 impl ImplStruct {
     pub fn public_self_referencing_method(&self, param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
@@ -258,12 +262,358 @@ impl ImplStruct {
 impl ImplStruct {
     pub fn public_self_mutating_reference_method(&mut self, param: i32) -> i32;
 }"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
 }
 
 #[test]
 fn test_struct_with_tuple_fields() {
     let input = r#"pub struct Parameter(pub String, pub String);"#;
     let expected = r#"pub struct Parameter(pub String, pub String);"#;
-    assert_eq!(codemap(input), expected);
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_syntax_error_keeps_valid_items() {
+    let input = r#"pub fn before(param: i32) -> i32 { todo!() }
+
+pub fn broken(param: i32 {
+    let x = ;
+}
+
+pub const AFTER: i32 = 42;"#;
+    let file_map = map_source(input).unwrap();
+    assert_eq!(
+        file_map.map(),
+        r#"pub fn before(param: i32) -> i32;
+
+pub const AFTER: i32 = 42;"#
+    );
+    assert_eq!(file_map.diagnostics.len(), 2);
+    assert_eq!(file_map.diagnostics[0].line, 3);
+    assert_eq!(file_map.diagnostics[0].column, 25);
+    assert_eq!(file_map.diagnostics[0].message, "missing `)`");
+    assert_eq!(
+        file_map.diagnostics[0].snippet,
+        "pub fn broken(param: i32 {"
+    );
+    assert_eq!(file_map.diagnostics[1].line, 4);
+    assert_eq!(file_map.diagnostics[1].message, "syntax error");
+}
+
+#[test]
+fn test_syntax_error_struct_without_name() {
+    let input = r#"pub struct
+pub const AFTER: i32 = 42;"#;
+    let file_map = map_source(input).unwrap();
+    assert_eq!(file_map.map(), "pub const AFTER: i32 = 42;");
+    assert_eq!(file_map.items[0].lines.unwrap().start, 2);
+    assert_eq!(file_map.diagnostics.len(), 1);
+}
+
+#[test]
+fn test_syntax_error_escaped_in_xml() {
+    let file_map = map_source("pub fn f() -> Vec<u8 && {}").unwrap();
+    let xml = render_files(&[(PathBuf::from("lib.rs"), file_map)], Format::Xml);
+    assert!(xml.contains("Vec&lt;u8 &amp;&amp; {}</error>"));
+}
+
+#[test]
+fn test_path_escaped_in_xml() {
+    let file_map = map_source("pub fn f() {}").unwrap();
+    let xml = render_files(&[(PathBuf::from("a\"&<b.rs"), file_map)], Format::Xml);
+    assert!(xml.contains("<file path=\"a&quot;&amp;&lt;b.rs\">"));
+}
+
+#[test]
+fn test_syntax_error_in_field_drops_field() {
+    let input = r#"pub struct Broken {
+    pub public_field: i32,
+    pub half_typed: ,
+}"#;
+    let file_map = map_source(input).unwrap();
+    assert_eq!(
+        file_map.map(),
+        r#"pub struct Broken {
+    pub public_field: i32
+}"#
+    );
+    assert_eq!(file_map.diagnostics.len(), 1);
+}
+
+#[test]
+fn test_clean_file_has_no_diagnostics() {
+    let input = r#"pub fn public_function(param: i32) -> i32 { todo!() }"#;
+    assert!(map_source(input).unwrap().diagnostics.is_empty());
+}
+
+#[test]
+fn test_private_trait_any_name() {
+    let input = r#"trait Storage {
+    fn load(&self) -> String;
+}
+
+trait Cache {}"#;
+    let expected = r#""#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_restricted_visibility_trait() {
+    let input = r#"pub(crate) trait CrateTrait {}
+
+pub(super) trait SuperTrait {}
+
+pub(in crate::inner) trait PathTrait {}"#;
+    let expected = r#""#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_public_and_private_traits_mixed() {
+    let input = r#"trait Hidden {
+    fn hidden(&self);
+}
+
+pub trait Visible {
+    fn visible(&self);
+}"#;
+    let expected = r#"pub trait Visible {
+    fn visible(&self);
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_traits_in_modules() {
+    let input = r#"mod private_mod {
+    pub trait InPrivateModule {}
+}
+
+pub mod public_mod {
+    trait PrivateInPublicModule {}
+    pub trait PublicInPublicModule {}
+}"#;
+    let expected = r#"pub mod public_mod {
+    pub trait PublicInPublicModule {}
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_traits_in_nested_modules() {
+    let input = r#"pub mod outer {
+    pub mod inner {
+        trait Hidden {}
+
+        /// Loads things.
+        pub trait Loader {
+            fn load(&self) -> String;
+        }
+    }
+
+    mod private_inner {
+        pub trait Unreachable {}
+    }
+
+    pub struct Store;
+
+    impl Store {
+        pub fn open() -> Self {
+            Store
+        }
+    }
+}"#;
+    let expected = r#"pub mod outer {
+    pub mod inner {
+        /// Loads things.
+        pub trait Loader {
+            fn load(&self) -> String;
+        }
+    }
+
+    pub struct Store;
+
+    impl Store {
+        pub fn open() -> Self;
+    }
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_module_without_public_items() {
+    let input = r#"pub mod empty {
+    trait Hidden {}
+}"#;
+    let expected = r#"pub mod empty {}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_traits_in_functions() {
+    let input = r#"pub fn with_local_trait() {
+    trait Local {}
+    pub trait LocalPub {}
+}"#;
+    let expected = r#"pub fn with_local_trait();"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_traits_in_impls() {
+    let input = r#"pub struct ImplStruct;
+
+impl ImplStruct {
+    pub fn method_with_local_trait(&self) {
+        trait Local {}
+    }
+}
+
+trait PrivateTrait {}
+
+impl PrivateTrait for ImplStruct {}"#;
+    let expected = r#"pub struct ImplStruct;
+
+impl ImplStruct {
+    pub fn method_with_local_trait(&self);
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_generic_enum_with_where_clause() {
+    let input = r#"pub enum Either<L, R: Clone>
+where
+    L: Default,
+{
+    Left(L),
+    Right(R),
+}"#;
+    let expected = r#"pub enum Either<L, R: Clone> where L: Default {
+    Left(L),
+    Right(R),
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_enum_discriminants() {
+    let input = r#"pub enum Status {
+    Active = 1,
+    Inactive = 1 << 2,
+    Unknown,
+}"#;
+    let expected = r#"pub enum Status {
+    Active = 1,
+    Inactive = 1 << 2,
+    Unknown,
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_enum_variant_attributes_and_docs() {
+    let input = r#"pub enum Event {
+    /// A user signed up.
+    ///
+    /// Sent once per account.
+    #[serde(rename = "signup")]
+    SignUp { user_id: u64 },
+    // Not a doc comment
+    #[serde(other)]
+    Unknown, // trailing comment
+    /* block comment */ Closed(u8),
+}"#;
+    let expected = r#"pub enum Event {
+    /// A user signed up.
+    #[serde(rename = "signup")]
+    SignUp { user_id: u64 },
+    #[serde(other)]
+    Unknown,
+    Closed(u8),
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_enum_multiline_variant_fields() {
+    let input = r#"pub enum Shape {
+    Rect {
+        width: u32,
+        height: u32,
+    },
+    Pair(
+        String,
+        Vec<u8>,
+    ),
+}"#;
+    let expected = r#"pub enum Shape {
+    Rect { width: u32, height: u32 },
+    Pair(String, Vec<u8>),
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_tuple_struct_with_private_fields() {
+    let input = r#"pub struct Parameter(String, pub u8, pub(crate) bool);"#;
+    let expected = r#"pub struct Parameter(_, pub u8, _);"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_generic_struct_with_bounds_and_where_clause() {
+    let input = r#"pub struct Cache<K: Hash + Eq, V>
+where
+    V: Clone,
+{
+    pub entries: HashMap<K, V>,
+}"#;
+    let expected = r#"pub struct Cache<K: Hash + Eq, V> where V: Clone {
+    pub entries: HashMap<K, V>
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_tuple_struct_with_where_clause() {
+    let input = r#"pub struct Wrapper<T>(pub T) where T: Clone;"#;
+    let expected = r#"pub struct Wrapper<T>(pub T) where T: Clone;"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_struct_field_docs() {
+    let input = r#"pub struct Config {
+    /// Address to bind to.
+    ///
+    /// Defaults to localhost.
+    pub address: String,
+    // Not a doc comment
+    #[serde(default)]
+    pub port: u16,
+    /// Internal only
+    secret: String,
+}"#;
+    let expected = r#"pub struct Config {
+    /// Address to bind to.
+    pub address: String,
+    #[serde(default)]
+    pub port: u16
+    // private fields
+}"#;
+    assert_eq!(codemap(input).unwrap(), expected);
+}
+
+#[test]
+fn test_mapper_reused_across_files() {
+    let inputs = [
+        r#"pub fn public_function(param: i32) -> i32 { todo!() }"#,
+        r#"pub struct Broken { pub half_typed: , }"#,
+        r#"pub const CONSTANT: i32 = 42;"#,
+    ];
+    let mut mapper = Mapper::new().unwrap();
+    for input in inputs {
+        assert_eq!(mapper.map(input).unwrap(), map_source(input).unwrap());
+    }
 }
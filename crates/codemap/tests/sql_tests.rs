@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use codemap::{CodemapOptions, Mapper, Sql};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_tables_and_views() {
+    let input = r#"-- Registered users.
+-- Rows are never deleted.
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    email   TEXT NOT NULL UNIQUE, -- lower case
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO users (id, email) VALUES (1, 'admin@example.com');
+
+CREATE TABLE posts (
+    id INTEGER,
+    user_id INTEGER REFERENCES users (id),
+    PRIMARY KEY (id)
+);
+
+CREATE VIEW recent_posts AS SELECT * FROM posts WHERE id > 100;
+CREATE UNIQUE INDEX users_email ON users (email);
+ALTER TABLE posts ADD COLUMN title TEXT;
+"#;
+    let expected = r#"-- Registered users.
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+)
+
+CREATE TABLE posts (
+    id INTEGER,
+    user_id INTEGER REFERENCES users (id),
+    PRIMARY KEY (id)
+)
+
+CREATE VIEW recent_posts
+
+CREATE UNIQUE INDEX users_email ON users(email)
+
+ALTER TABLE posts ADD COLUMN title TEXT"#;
+    assert_eq!(codemap_with(Sql, input, CodemapOptions::new()), expected);
+}
+
+#[test]
+fn test_dialects_and_lines() {
+    let input = "CREATE TYPE mood AS ENUM ('sad', 'happy');\n\nCREATE TABLE `events` (\n  `id` INT AUTO_INCREMENT\n) ENGINE=InnoDB;\n";
+    let expected = "-- L1-L1\nCREATE TYPE mood AS ENUM ('sad', 'happy')\n\n-- L3-L5\nCREATE TABLE `events` (\n    `id` INT AUTO_INCREMENT\n)";
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert_eq!(codemap_with(Sql, input, options), expected);
+}
+
+#[test]
+fn test_syntax_errors() {
+    let input = "CREATE TABLE a (id INT);\nCREATE TABLE b (id INT,,);\nCREATE TABLE c (id INT);\n";
+    let mut mapper = Mapper::with_backend(Arc::new(Sql), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    assert_eq!(
+        file_map.map(),
+        "CREATE TABLE a (\n    id INT\n)\n\nCREATE TABLE c (\n    id INT\n)"
+    );
+    let diagnostics: Vec<_> = file_map
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.snippet.as_str()))
+        .collect();
+    assert_eq!(diagnostics, vec![(2, "CREATE TABLE b (id INT,,);")]);
+}
+
+#[test]
+fn test_trigger_bodies() {
+    let input = r#"BEGIN;
+CREATE TABLE a (id INT);
+CREATE TRIGGER a_log AFTER INSERT ON a
+BEGIN
+    INSERT INTO log VALUES (NEW.id);
+    UPDATE counts SET n = CASE WHEN n IS NULL THEN 1 ELSE n + 1 END;
+END;
+CREATE TABLE b (id INT);
+COMMIT;
+"#;
+    let mut mapper = Mapper::with_backend(Arc::new(Sql), CodemapOptions::new()).unwrap();
+    let file_map = mapper.map(input).unwrap();
+    assert_eq!(
+        file_map.map(),
+        "CREATE TABLE a (\n    id INT\n)\n\nCREATE TABLE b (\n    id INT\n)"
+    );
+    let lines: Vec<_> = file_map
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect();
+    // The trigger is reported as a whole, not its statements one by one
+    assert_eq!(lines, vec![3]);
+}
+
+#[test]
+fn test_lines_start_at_doc() {
+    let input = "-- Users.\n-- One per account.\nCREATE TABLE users (id INT);\n";
+    let options = CodemapOptions::new().include_line_numbers(true);
+    assert_eq!(
+        codemap_with(Sql, input, options),
+        "-- L1-L3\n-- Users.\nCREATE TABLE users (\n    id INT\n)"
+    );
+}
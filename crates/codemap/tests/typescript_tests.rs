@@ -0,0 +1,255 @@
+use codemap::{CodemapOptions, JavaScript, Tsx, TypeScript, Visibility};
+
+mod common;
+
+use common::codemap_with;
+
+#[test]
+fn test_only_exported_declarations_are_public() {
+    let input = r#"import { dep } from "./dep";
+
+export function exported(a: number, b?: string): Promise<void> {
+    return dep(a);
+}
+
+function local(): void {}
+"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        "export function exported(a: number, b?: string): Promise<void>;"
+    );
+    assert_eq!(
+        codemap_with(
+            TypeScript,
+            input,
+            CodemapOptions::new().visibility(Visibility::Private)
+        ),
+        r#"import { dep } from "./dep";
+
+export function exported(a: number, b?: string): Promise<void>;
+
+function local(): void;"#
+    );
+}
+
+#[test]
+fn test_class_members() {
+    let input = r#"export class Store<T> extends Base implements Iterable<T> {
+    public items: T[] = [];
+    private cache = new Map();
+    protected size = 0;
+    #hidden = 1;
+    static readonly VERSION = 2;
+
+    constructor(private readonly name: string) {
+        super();
+    }
+
+    /** Number of items. */
+    get length(): number {
+        return this.items.length;
+    }
+
+    @memoize
+    async load(
+        id: string,
+    ): Promise<T> {
+        return fetch(id);
+    }
+
+    protected evict(): void {}
+}
+"#;
+    let expected = r#"export class Store<T> extends Base implements Iterable<T> {
+    public items: T[];
+    static readonly VERSION = 2;
+    constructor(private readonly name: string);
+    /** Number of items. */
+    get length(): number;
+    @memoize
+    async load(id: string): Promise<T>;
+}"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        expected
+    );
+
+    let crate_visible = codemap_with(
+        TypeScript,
+        input,
+        CodemapOptions::new().visibility(Visibility::Crate),
+    );
+    assert!(crate_visible.contains("    protected size = 0;\n"));
+    assert!(crate_visible.contains("    protected evict(): void;\n"));
+    assert!(!crate_visible.contains("cache"));
+}
+
+#[test]
+fn test_interfaces_types_and_enums() {
+    let input = r#"export interface Options extends Base {
+    name: string;
+    retries?: number,
+    log(message: string): void;
+}
+
+export type Id = string | number;
+
+export enum Color {
+    Red = "red",
+    Green = "green",
+}
+
+export const enum Flags { A = 1 }
+"#;
+    let expected = r#"export interface Options extends Base {
+    name: string;
+    retries?: number;
+    log(message: string): void;
+}
+
+export type Id = string | number;
+
+export enum Color {
+    Red = "red",
+    Green = "green"
+}
+
+export const enum Flags {
+    A = 1
+}"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_reexports() {
+    let input = r#"export * from "./a";
+export * as b from "./b";
+export { c, d as e } from "./c";
+"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        "export * from \"./a\";\n\nexport * as b from \"./b\";\n\nexport { c, d as e } from \"./c\";"
+    );
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new().include_uses(false)),
+        ""
+    );
+}
+
+#[test]
+fn test_variables() {
+    let input = r#"export const LIMIT = 10, name: string = "x";
+export const handler = async (event: Event): Promise<void> => {
+    await run(event);
+};
+export const config = {
+    debug: true,
+};
+"#;
+    let expected = r#"export const LIMIT = 10, name: string = "x";
+
+export const handler = async (event: Event): Promise<void> => ...;
+
+export const config = ...;"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_declaration_file() {
+    // A global declaration file has no exports, everything in it is public
+    let input = r#"declare function greet(name: string): void;
+
+interface Window {
+    app: App;
+}
+
+declare namespace App {
+    function start(): void;
+}
+"#;
+    let expected = r#"declare function greet(name: string): void;
+
+interface Window {
+    app: App;
+}
+
+declare namespace App {
+    function start(): void;
+}"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_namespace_exports() {
+    let input = r#"export namespace Api {
+    export function get(url: string): Response {
+        return fetch(url);
+    }
+    function helper() {}
+}
+"#;
+    let expected = r#"export namespace Api {
+    export function get(url: string): Response;
+}"#;
+    assert_eq!(
+        codemap_with(TypeScript, input, CodemapOptions::new()),
+        expected
+    );
+}
+
+#[test]
+fn test_tsx_component() {
+    let input = r#"export function Button({ label }: Props): JSX.Element {
+    return <button>{label}</button>;
+}
+"#;
+    assert_eq!(
+        codemap_with(Tsx, input, CodemapOptions::new()),
+        "export function Button({ label }: Props): JSX.Element;"
+    );
+}
+
+#[test]
+fn test_javascript() {
+    let input = r#"/**
+ * Adds numbers.
+ */
+export function add(a, b) {
+    return a + b;
+}
+
+export class Counter {
+    count = 0;
+    #step = 1;
+    increment() {}
+}
+
+export default Counter;
+"#;
+    let expected = r#"/** Adds numbers. */
+export function add(a, b);
+
+export class Counter {
+    count = 0;
+    increment();
+}
+
+export default Counter;"#;
+    assert_eq!(
+        codemap_with(JavaScript, input, CodemapOptions::new()),
+        expected
+    );
+    assert!(
+        !codemap_with(JavaScript, input, CodemapOptions::new().include_docs(false))
+            .contains("Adds numbers")
+    );
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Kills `codemap watch` when a test ends, even when it fails
+struct Watch(Child);
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn watch_diff(dir: &Path, output: &Path) -> Watch {
+    let child = Command::new(env!("CARGO_BIN_EXE_codemap"))
+        .arg("watch")
+        .arg("--diff")
+        .arg("--no-cache")
+        .arg("--output")
+        .arg(output)
+        .arg(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    // Give it time to map the directory and start watching
+    thread::sleep(Duration::from_secs(1));
+    Watch(child)
+}
+
+// The output file once it contains `text`
+fn wait_for(output: &Path, text: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let written = fs::read_to_string(output).unwrap_or_default();
+        if written.contains(text) || Instant::now() > deadline {
+            return written;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_diffs_are_appended() {
+    let dir = tempfile::tempdir().unwrap();
+    let lib = dir.path().join("lib.rs");
+    fs::write(&lib, "pub fn first() {}\n").unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let output = output.path().join("diff.xml");
+    let _watch = watch_diff(dir.path(), &output);
+
+    fs::write(&lib, "pub fn first() {}\npub fn second() {}\n").unwrap();
+    assert!(wait_for(&output, "pub fn second()").contains("<added>\npub fn second()"));
+
+    fs::write(&lib, "pub fn second() {}\n").unwrap();
+    let written = wait_for(&output, "<removed>");
+    assert!(written.contains("<added>\npub fn second()"));
+    assert!(written.contains("<removed>\npub fn first()"));
+    assert_eq!(written.matches("<diff>").count(), 2);
+}
+
+#[test]
+fn test_changes_without_public_items_write_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let lib = dir.path().join("lib.rs");
+    fs::write(&lib, "pub fn first() {}\n").unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let output = output.path().join("diff.xml");
+    let _watch = watch_diff(dir.path(), &output);
+
+    // Only adds a diagnostic
+    fs::write(&lib, "pub fn first() {}\nfn broken( {\n").unwrap();
+    // A new file without any items
+    fs::write(dir.path().join("empty.rs"), "").unwrap();
+    thread::sleep(Duration::from_secs(1));
+
+    fs::write(dir.path().join("other.rs"), "pub fn other() {}\n").unwrap();
+    let written = wait_for(&output, "pub fn other()");
+    assert_eq!(written.matches("<diff>").count(), 1);
+    assert!(written.contains("<added>\npub fn other()"));
+}
+
+#[test]
+fn test_map_is_written_first() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("lib.rs"), "pub fn first() {}\n").unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let output = output.path().join("diff.xml");
+    let _watch = watch_diff(dir.path(), &output);
+
+    let written = wait_for(&output, "pub fn first()");
+    assert!(written.contains("pub fn first()"));
+    assert_eq!(written.matches("<diff>").count(), 0);
+}
+
+#[test]
+fn test_config_changes_apply() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "pub fn public() {}\nfn private() {}\n",
+    )
+    .unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let output = output.path().join("diff.xml");
+    let _watch = watch_diff(dir.path(), &output);
+
+    fs::write(
+        dir.path().join("codemap.toml"),
+        "visibility = \"private\"\n",
+    )
+    .unwrap();
+    let written = wait_for(&output, "<added>\nfn private()");
+    assert!(written.contains("<added>\nfn private()"));
+}